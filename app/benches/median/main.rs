@@ -1,6 +1,6 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use image::{EncodableLayout, GenericImageView, ImageReader};
-use libblur::{BlurImage, BlurImageMut, FastBlurChannels, ThreadingPolicy};
+use libblur::{BlurImage, BlurImageMut, EdgeMode, FastBlurChannels, Scalar, ThreadingPolicy};
 use opencv::core::{find_file, Mat};
 use opencv::imgcodecs::{imread, IMREAD_COLOR};
 
@@ -28,7 +28,15 @@ pub fn criterion_benchmark(c: &mut Criterion) {
             FastBlurChannels::Channels4,
         );
         b.iter(|| {
-            libblur::median_blur(&src_image, &mut dst_image, 7, ThreadingPolicy::Adaptive).unwrap();
+            libblur::median_blur(
+                &src_image,
+                &mut dst_image,
+                7,
+                EdgeMode::Clamp,
+                Scalar::default(),
+                ThreadingPolicy::Adaptive,
+            )
+            .unwrap();
         })
     });
 
@@ -67,7 +75,15 @@ pub fn criterion_benchmark(c: &mut Criterion) {
             FastBlurChannels::Channels3,
         );
         b.iter(|| {
-            libblur::median_blur(&rgb_image, &mut dst_image, 7, ThreadingPolicy::Adaptive).unwrap();
+            libblur::median_blur(
+                &rgb_image,
+                &mut dst_image,
+                7,
+                EdgeMode::Clamp,
+                Scalar::default(),
+                ThreadingPolicy::Adaptive,
+            )
+            .unwrap();
         })
     });
 
@@ -66,6 +66,17 @@ impl Blend<u8> for u8 {
                         ((m as u32 * (*src) as u32 + (255 - m as u32) * (*dst) as u32) / 255) as u8;
                 }
             }
+            FastBlurChannels::Channels2 => {
+                for ((dst, src), mask) in destination
+                    .chunks_exact_mut(2)
+                    .zip(source.chunks_exact(2))
+                    .zip(mask.iter())
+                {
+                    let m = *mask;
+                    dst[0] =
+                        ((m as u32 * src[0] as u32 + (255 - m as u32) * dst[0] as u32) / 255) as u8;
+                }
+            }
             FastBlurChannels::Channels3 | FastBlurChannels::Channels4 => {
                 for ((dst, src), mask) in destination
                     .chunks_exact_mut(channels)
@@ -205,6 +216,9 @@ impl Gamma<u8> for u8 {
             FastBlurChannels::Plane => gamma.iter_mut().for_each(|dst| {
                 *dst = lut_table[*dst as usize];
             }),
+            FastBlurChannels::Channels2 => gamma.chunks_exact_mut(2).for_each(|dst| {
+                dst[0] = lut_table[dst[0] as usize];
+            }),
             FastBlurChannels::Channels3 | FastBlurChannels::Channels4 => {
                 gamma.chunks_exact_mut(channels.channels()).for_each(|dst| {
                     dst[0] = lut_table[dst[0] as usize];
@@ -318,6 +332,13 @@ impl Linearize<u8> for u8 {
             FastBlurChannels::Plane => dst.iter_mut().zip(source).for_each(|(dst, src)| {
                 *dst = lut_table[*src as usize];
             }),
+            FastBlurChannels::Channels2 => dst
+                .chunks_exact_mut(2)
+                .zip(source.chunks_exact(2))
+                .for_each(|(dst, src)| {
+                    dst[0] = lut_table[src[0] as usize];
+                    dst[1] = src[1];
+                }),
             FastBlurChannels::Channels3 => dst
                 .chunks_exact_mut(3)
                 .zip(source.chunks_exact(3))
@@ -358,6 +379,7 @@ impl Grayscale<u8> for u8 {
 
         match channels {
             FastBlurChannels::Plane => source.to_vec(),
+            FastBlurChannels::Channels2 => source.iter().step_by(2).copied().collect(),
             FastBlurChannels::Channels3 | FastBlurChannels::Channels4 => {
                 let mut dest = vec![0u8; width * height];
 
@@ -0,0 +1,603 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::alpha::premultiply::{
+    alpha_in_place, premultiply_into, unpremultiply_into, AlphaHandler,
+};
+use crate::filter2d::Filter2dHandler;
+use crate::to_storage::ToStorage;
+use crate::{
+    box_blur, box_blur_f32, box_blur_u16, fast_gaussian, fast_gaussian_f32, fast_gaussian_u16,
+    filter_2d_rgba, gaussian_blur, gaussian_blur_f32, gaussian_blur_u16, stack_blur,
+    stack_blur_f32, stack_blur_u16, AlphaMode, AnisotropicRadius, BlurError, BlurImage,
+    BlurImageMut, BoxBlurParameters, ConvolutionMode, EdgeMode, FastBlurChannels,
+    GaussianBlurParams, IeeeBinaryConvolutionMode, KernelShape, Scalar, ThreadingPolicy,
+};
+use novtb::{ParallelZonedIterator, TbSliceMut};
+use num_traits::{AsPrimitive, MulAdd};
+use std::fmt::Debug;
+use std::ops::Mul;
+
+fn alpha_executor<T, F>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+    executor: F,
+) -> Result<(), BlurError>
+where
+    T: AlphaHandler,
+    F: FnOnce(&BlurImage<T>, &mut BlurImageMut<T>) -> Result<(), BlurError>,
+{
+    if src.channels != FastBlurChannels::Channels4 || alpha_mode == AlphaMode::Premultiplied {
+        return executor(src, dst);
+    }
+    src.check_layout()?;
+    dst.check_layout(Some(src))?;
+    src.size_matches_mut(dst)?;
+
+    let thread_count = threading_policy.thread_count(src.width, src.height);
+    let pool = novtb::ThreadPool::new(thread_count);
+
+    match alpha_mode {
+        AlphaMode::Straight => {
+            let mut premultiplied = BlurImageMut::alloc(src.width, src.height, src.channels);
+            premultiply_into(src, &mut premultiplied, &pool);
+            executor(&premultiplied.to_immutable_ref(), dst)?;
+            alpha_in_place(dst, T::unpremultiply_row(), &pool);
+        }
+        AlphaMode::Premultiplied => unreachable!(),
+        AlphaMode::Ignore => {
+            executor(src, dst)?;
+            let src_stride = src.row_stride() as usize;
+            let dst_stride = dst.row_stride() as usize;
+            let row_length = src.width as usize * 4;
+            let src_data = src.data.as_ref();
+            // Last row may be shorter than stride
+            let dst_length = dst_stride * (dst.height as usize - 1) + row_length;
+            dst.data.borrow_mut()[..dst_length]
+                .tb_par_chunks_mut(dst_stride)
+                .for_each_enumerated(&pool, |y, row| {
+                    let src = &src_data[y * src_stride..y * src_stride + row_length];
+                    for (dst, src) in row[..row_length]
+                        .chunks_exact_mut(4)
+                        .zip(src.chunks_exact(4))
+                    {
+                        dst[3] = src[3];
+                    }
+                });
+        }
+    }
+    Ok(())
+}
+
+fn alpha_executor_in_place<T, F>(
+    image: &mut BlurImageMut<T>,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+    executor: F,
+) -> Result<(), BlurError>
+where
+    T: AlphaHandler,
+    F: FnOnce(&mut BlurImageMut<T>) -> Result<(), BlurError>,
+{
+    if image.channels != FastBlurChannels::Channels4 || alpha_mode == AlphaMode::Premultiplied {
+        return executor(image);
+    }
+    image.check_layout(None)?;
+
+    let thread_count = threading_policy.thread_count(image.width, image.height);
+    let pool = novtb::ThreadPool::new(thread_count);
+
+    match alpha_mode {
+        AlphaMode::Straight => {
+            // Blur runs on a premultiplied copy, premultiplication is lossy for integers,
+            // so caller's image is written only after blur succeeded
+            let mut premultiplied = BlurImageMut::alloc(image.width, image.height, image.channels);
+            premultiply_into(&image.to_immutable_ref(), &mut premultiplied, &pool);
+            executor(&mut premultiplied)?;
+            unpremultiply_into(&premultiplied.to_immutable_ref(), image, &pool);
+        }
+        AlphaMode::Premultiplied => unreachable!(),
+        AlphaMode::Ignore => {
+            let stride = image.row_stride() as usize;
+            let row_length = image.width as usize * 4;
+            let alpha = image
+                .data
+                .borrow()
+                .chunks(stride)
+                .take(image.height as usize)
+                .flat_map(|row| row[..row_length].chunks_exact(4).map(|px| px[3]))
+                .collect::<Vec<T>>();
+            executor(image)?;
+            let width = image.width as usize;
+            let length = stride * (image.height as usize - 1) + row_length;
+            image.data.borrow_mut()[..length]
+                .tb_par_chunks_mut(stride)
+                .for_each_enumerated(&pool, |y, row| {
+                    let alpha = &alpha[y * width..(y + 1) * width];
+                    for (dst, &a) in row[..row_length].chunks_exact_mut(4).zip(alpha.iter()) {
+                        dst[3] = a;
+                    }
+                });
+        }
+    }
+    Ok(())
+}
+
+/// Performs gaussian blur on the image with respect to alpha channel.
+///
+/// For images with alpha see [AlphaMode] for more info, other images are blurred as usual.
+///
+/// # Arguments
+///
+/// * `src` - Source image.
+/// * `dst` - Destination image.
+/// * `params` - See [GaussianBlurParams] for more info.
+/// * `edge_mode` - Rule to handle edge mode, sse [EdgeMode] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `hint` - see [ConvolutionMode] for more info.
+/// * `alpha_mode` - see [AlphaMode] for more info.
+pub fn gaussian_blur_alpha(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    params: GaussianBlurParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    hint: ConvolutionMode,
+    alpha_mode: AlphaMode,
+) -> Result<(), BlurError> {
+    alpha_executor(src, dst, alpha_mode, threading_policy, |s, d| {
+        gaussian_blur(s, d, params, edge_mode, threading_policy, hint)
+    })
+}
+
+/// Performs gaussian blur on the image with respect to alpha channel.
+///
+/// For images with alpha see [AlphaMode] for more info, other images are blurred as usual.
+///
+/// # Arguments
+///
+/// * `src` - Source image.
+/// * `dst` - Destination image.
+/// * `params` - See [GaussianBlurParams] for more info.
+/// * `edge_mode` - Rule to handle edge mode, sse [EdgeMode] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `hint` - see [ConvolutionMode] for more info.
+/// * `alpha_mode` - see [AlphaMode] for more info.
+pub fn gaussian_blur_alpha_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    params: GaussianBlurParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    hint: ConvolutionMode,
+    alpha_mode: AlphaMode,
+) -> Result<(), BlurError> {
+    alpha_executor(src, dst, alpha_mode, threading_policy, |s, d| {
+        gaussian_blur_u16(s, d, params, edge_mode, threading_policy, hint)
+    })
+}
+
+/// Performs gaussian blur on the image with respect to alpha channel.
+///
+/// For images with alpha see [AlphaMode] for more info, other images are blurred as usual.
+/// Alpha is expected to be normalized in `0..=1`.
+///
+/// # Arguments
+///
+/// * `src` - Source image.
+/// * `dst` - Destination image.
+/// * `params` - See [GaussianBlurParams] for more info.
+/// * `edge_mode` - Rule to handle edge mode, sse [EdgeMode] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `convolution_mode` - see [IeeeBinaryConvolutionMode] for more info.
+/// * `alpha_mode` - see [AlphaMode] for more info.
+pub fn gaussian_blur_alpha_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    params: GaussianBlurParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    convolution_mode: IeeeBinaryConvolutionMode,
+    alpha_mode: AlphaMode,
+) -> Result<(), BlurError> {
+    alpha_executor(src, dst, alpha_mode, threading_policy, |s, d| {
+        gaussian_blur_f32(s, d, params, edge_mode, threading_policy, convolution_mode)
+    })
+}
+
+/// Performs box blur on the image with respect to alpha channel.
+///
+/// For images with alpha see [AlphaMode] for more info, other images are blurred as usual.
+///
+/// # Arguments
+///
+/// * `src` - Source image.
+/// * `dst` - Destination image.
+/// * `parameters` - See [BoxBlurParameters] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `alpha_mode` - see [AlphaMode] for more info.
+pub fn box_blur_alpha(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    parameters: BoxBlurParameters,
+    threading_policy: ThreadingPolicy,
+    alpha_mode: AlphaMode,
+) -> Result<(), BlurError> {
+    alpha_executor(src, dst, alpha_mode, threading_policy, |s, d| {
+        box_blur(s, d, parameters, threading_policy)
+    })
+}
+
+/// Performs box blur on the image with respect to alpha channel.
+///
+/// For images with alpha see [AlphaMode] for more info, other images are blurred as usual.
+///
+/// # Arguments
+///
+/// * `src` - Source image.
+/// * `dst` - Destination image.
+/// * `parameters` - See [BoxBlurParameters] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `alpha_mode` - see [AlphaMode] for more info.
+pub fn box_blur_alpha_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    parameters: BoxBlurParameters,
+    threading_policy: ThreadingPolicy,
+    alpha_mode: AlphaMode,
+) -> Result<(), BlurError> {
+    alpha_executor(src, dst, alpha_mode, threading_policy, |s, d| {
+        box_blur_u16(s, d, parameters, threading_policy)
+    })
+}
+
+/// Performs box blur on the image with respect to alpha channel.
+///
+/// For images with alpha see [AlphaMode] for more info, other images are blurred as usual.
+/// Alpha is expected to be normalized in `0..=1`.
+///
+/// # Arguments
+///
+/// * `src` - Source image.
+/// * `dst` - Destination image.
+/// * `parameters` - See [BoxBlurParameters] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `alpha_mode` - see [AlphaMode] for more info.
+pub fn box_blur_alpha_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    parameters: BoxBlurParameters,
+    threading_policy: ThreadingPolicy,
+    alpha_mode: AlphaMode,
+) -> Result<(), BlurError> {
+    alpha_executor(src, dst, alpha_mode, threading_policy, |s, d| {
+        box_blur_f32(s, d, parameters, threading_policy)
+    })
+}
+
+/// Performs stack blur in place with respect to alpha channel.
+///
+/// For images with alpha see [AlphaMode] for more info, other images are blurred as usual.
+///
+/// # Arguments
+///
+/// * `image` - Image to work in place, see [BlurImageMut] for more info.
+/// * `radius` - See [crate::stack_blur] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `alpha_mode` - see [AlphaMode] for more info.
+pub fn stack_blur_alpha(
+    image: &mut BlurImageMut<u8>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+    alpha_mode: AlphaMode,
+) -> Result<(), BlurError> {
+    alpha_executor_in_place(image, alpha_mode, threading_policy, |i| {
+        stack_blur(i, radius, threading_policy)
+    })
+}
+
+/// Performs stack blur in place with respect to alpha channel.
+///
+/// For images with alpha see [AlphaMode] for more info, other images are blurred as usual.
+///
+/// # Arguments
+///
+/// * `image` - Image to work in place, see [BlurImageMut] for more info.
+/// * `radius` - See [crate::stack_blur_u16] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `alpha_mode` - see [AlphaMode] for more info.
+pub fn stack_blur_alpha_u16(
+    image: &mut BlurImageMut<u16>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+    alpha_mode: AlphaMode,
+) -> Result<(), BlurError> {
+    alpha_executor_in_place(image, alpha_mode, threading_policy, |i| {
+        stack_blur_u16(i, radius, threading_policy)
+    })
+}
+
+/// Performs stack blur in place with respect to alpha channel.
+///
+/// For images with alpha see [AlphaMode] for more info, other images are blurred as usual.
+/// Alpha is expected to be normalized in `0..=1`.
+///
+/// # Arguments
+///
+/// * `image` - Image to work in place, see [BlurImageMut] for more info.
+/// * `radius` - See [crate::stack_blur_f32] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `alpha_mode` - see [AlphaMode] for more info.
+pub fn stack_blur_alpha_f32(
+    image: &mut BlurImageMut<f32>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+    alpha_mode: AlphaMode,
+) -> Result<(), BlurError> {
+    alpha_executor_in_place(image, alpha_mode, threading_policy, |i| {
+        stack_blur_f32(i, radius, threading_policy)
+    })
+}
+
+/// Performs gaussian approximation in place with respect to alpha channel.
+///
+/// For images with alpha see [AlphaMode] for more info, other images are blurred as usual.
+///
+/// # Arguments
+///
+/// * `image` - Image to work in place, see [BlurImageMut] for more info.
+/// * `radius` - See [crate::fast_gaussian] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+/// * `alpha_mode` - see [AlphaMode] for more info.
+pub fn fast_gaussian_alpha(
+    image: &mut BlurImageMut<u8>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+) -> Result<(), BlurError> {
+    alpha_executor_in_place(image, alpha_mode, threading_policy, |i| {
+        fast_gaussian(i, radius, threading_policy, edge_mode)
+    })
+}
+
+/// Performs gaussian approximation in place with respect to alpha channel.
+///
+/// For images with alpha see [AlphaMode] for more info, other images are blurred as usual.
+///
+/// # Arguments
+///
+/// * `image` - Image to work in place, see [BlurImageMut] for more info.
+/// * `radius` - See [crate::fast_gaussian_u16] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+/// * `alpha_mode` - see [AlphaMode] for more info.
+pub fn fast_gaussian_alpha_u16(
+    image: &mut BlurImageMut<u16>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+) -> Result<(), BlurError> {
+    alpha_executor_in_place(image, alpha_mode, threading_policy, |i| {
+        fast_gaussian_u16(i, radius, threading_policy, edge_mode)
+    })
+}
+
+/// Performs gaussian approximation in place with respect to alpha channel.
+///
+/// For images with alpha see [AlphaMode] for more info, other images are blurred as usual.
+/// Alpha is expected to be normalized in `0..=1`.
+///
+/// # Arguments
+///
+/// * `image` - Image to work in place, see [BlurImageMut] for more info.
+/// * `radius` - See [crate::fast_gaussian_f32] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+/// * `alpha_mode` - see [AlphaMode] for more info.
+pub fn fast_gaussian_alpha_f32(
+    image: &mut BlurImageMut<f32>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+) -> Result<(), BlurError> {
+    alpha_executor_in_place(image, alpha_mode, threading_policy, |i| {
+        fast_gaussian_f32(i, radius, threading_policy, edge_mode)
+    })
+}
+
+/// This performs direct 2D convolution on RGBA image with respect to alpha channel.
+///
+/// See [AlphaMode] for more info, for `f32` alpha is expected to be normalized in `0..=1`.
+///
+/// # Arguments
+///
+/// * `src`: Source RGBA image.
+/// * `dst`: Destination RGBA image.
+/// * `kernel`: Kernel.
+/// * `kernel_shape`: Kernel size, see [KernelShape] for more info.
+/// * `border_mode`: Border handling mode see [EdgeMode] for more info.
+/// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy`: See [ThreadingPolicy] for more info
+/// * `alpha_mode` - see [AlphaMode] for more info.
+pub fn filter_2d_rgba_alpha<T, F>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    kernel: &[F],
+    kernel_shape: KernelShape,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+    alpha_mode: AlphaMode,
+) -> Result<(), BlurError>
+where
+    T: Copy + AsPrimitive<F> + Default + Send + Sync + Filter2dHandler<T, F> + Debug + AlphaHandler,
+    F: ToStorage<T> + Mul<F> + MulAdd<F, Output = F> + Send + Sync + PartialEq + AsPrimitive<f64>,
+    i32: AsPrimitive<F>,
+    f64: AsPrimitive<T>,
+{
+    if src.channels != FastBlurChannels::Channels4 {
+        return Err(BlurError::InvalidArguments);
+    }
+    alpha_executor(src, dst, alpha_mode, threading_policy, |s, d| {
+        filter_2d_rgba::<T, F>(
+            s,
+            d,
+            kernel,
+            kernel_shape,
+            border_mode,
+            border_constant,
+            threading_policy,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BufferStore;
+
+    #[test]
+    fn test_straight_alpha_has_no_dark_fringes() {
+        let width = 64usize;
+        let height = 64usize;
+        let mut src = vec![0u8; width * height * 4];
+        for (i, px) in src.chunks_exact_mut(4).enumerate() {
+            if (i % width) < width / 2 {
+                px.copy_from_slice(&[255, 255, 255, 255]);
+            }
+        }
+        let src_image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels4,
+        );
+        let mut dst = BlurImageMut::default();
+        gaussian_blur_alpha(
+            &src_image,
+            &mut dst,
+            GaussianBlurParams::new_from_kernel(9.),
+            EdgeMode::Clamp,
+            ThreadingPolicy::Single,
+            ConvolutionMode::Exact,
+            AlphaMode::Straight,
+        )
+        .unwrap();
+        for px in dst.data.borrow().chunks_exact(4) {
+            if px[3] > 0 {
+                for &c in px[..3].iter() {
+                    assert!(c >= 250, "Color was darkened to {c} with alpha {}", px[3]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_ignore_alpha_keeps_alpha() {
+        let width = 32usize;
+        let height = 32usize;
+        let mut data = vec![0f32; width * height * 4];
+        for (i, px) in data.chunks_exact_mut(4).enumerate() {
+            px.copy_from_slice(&[0.5, 0.25, 0.75, (i % 3) as f32 / 2.]);
+        }
+        let reference = data.clone();
+        let mut image = BlurImageMut::borrow(
+            &mut data,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels4,
+        );
+        stack_blur_alpha_f32(
+            &mut image,
+            AnisotropicRadius::new(5),
+            ThreadingPolicy::Single,
+            AlphaMode::Ignore,
+        )
+        .unwrap();
+        for (px, reference) in data.chunks_exact(4).zip(reference.chunks_exact(4)) {
+            assert_eq!(px[3], reference[3]);
+            assert!((px[0] - 0.5).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_ignore_alpha_restores_short_last_row() {
+        let (width, height, stride) = (8usize, 5usize, 40usize);
+        let mut data = vec![0u8; stride * (height - 1) + width * 4];
+        for (y, row) in data.chunks_mut(stride).enumerate() {
+            for (x, px) in row[..width * 4].chunks_exact_mut(4).enumerate() {
+                px.copy_from_slice(&[200, 100, 50, ((x + y) * 25) as u8]);
+            }
+        }
+        let reference = data.clone();
+        let mut image = BlurImageMut {
+            data: BufferStore::Borrowed(&mut data),
+            width: width as u32,
+            height: height as u32,
+            stride: stride as u32,
+            channels: FastBlurChannels::Channels4,
+        };
+        stack_blur_alpha(
+            &mut image,
+            AnisotropicRadius::new(3),
+            ThreadingPolicy::Single,
+            AlphaMode::Ignore,
+        )
+        .unwrap();
+        for (row, reference) in data.chunks(stride).zip(reference.chunks(stride)) {
+            for (px, reference) in row[..width * 4]
+                .chunks_exact(4)
+                .zip(reference.chunks_exact(4))
+            {
+                assert_eq!(px[3], reference[3]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_straight_alpha_untouched_on_error() {
+        // Low alpha premultiplication is lossy, 200 at alpha 1 would come back as 255
+        let mut data = [200u8, 100, 50, 1, 30, 60, 90, 128];
+        let reference = data;
+        let mut image = BlurImageMut::borrow(&mut data, 2, 1, FastBlurChannels::Channels4);
+        let result = alpha_executor_in_place(
+            &mut image,
+            AlphaMode::Straight,
+            ThreadingPolicy::Single,
+            |_| Err(BlurError::InvalidArguments),
+        );
+        assert!(matches!(result, Err(BlurError::InvalidArguments)));
+        assert_eq!(data, reference);
+    }
+}
@@ -0,0 +1,70 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::alpha::premultiply::{premultiply_row_f32, unpremultiply_row_f32};
+use std::arch::x86_64::*;
+
+pub(crate) fn premultiply_row_avx_f32(row: &mut [f32]) {
+    unsafe {
+        premultiply_row_f32_impl(row);
+    }
+}
+
+pub(crate) fn unpremultiply_row_avx_f32(row: &mut [f32]) {
+    unsafe {
+        unpremultiply_row_f32_impl(row);
+    }
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn premultiply_row_f32_impl(row: &mut [f32]) {
+    let mut chunks = row.chunks_exact_mut(8);
+    for chunk in &mut chunks {
+        let v = _mm256_loadu_ps(chunk.as_ptr());
+        let a = _mm256_permute_ps::<0xFF>(v);
+        let premultiplied = _mm256_blend_ps::<0b1000_1000>(_mm256_mul_ps(v, a), v);
+        _mm256_storeu_ps(chunk.as_mut_ptr(), premultiplied);
+    }
+    premultiply_row_f32(chunks.into_remainder());
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn unpremultiply_row_f32_impl(row: &mut [f32]) {
+    let mut chunks = row.chunks_exact_mut(8);
+    for chunk in &mut chunks {
+        let v = _mm256_loadu_ps(chunk.as_ptr());
+        let a = _mm256_permute_ps::<0xFF>(v);
+        let non_zero = _mm256_cmp_ps::<_CMP_NEQ_OQ>(a, _mm256_setzero_ps());
+        let divided = _mm256_and_ps(_mm256_div_ps(v, a), non_zero);
+        _mm256_storeu_ps(
+            chunk.as_mut_ptr(),
+            _mm256_blend_ps::<0b1000_1000>(divided, v),
+        );
+    }
+    unpremultiply_row_f32(chunks.into_remainder());
+}
@@ -0,0 +1,45 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+mod alpha_blur;
+mod alpha_mode;
+#[cfg(all(target_arch = "x86_64", feature = "avx"))]
+mod avx;
+#[cfg(all(target_arch = "aarch64", feature = "neon"))]
+mod neon;
+mod premultiply;
+#[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
+mod sse;
+
+pub use alpha_blur::{
+    box_blur_alpha, box_blur_alpha_f32, box_blur_alpha_u16, fast_gaussian_alpha,
+    fast_gaussian_alpha_f32, fast_gaussian_alpha_u16, filter_2d_rgba_alpha, gaussian_blur_alpha,
+    gaussian_blur_alpha_f32, gaussian_blur_alpha_u16, stack_blur_alpha, stack_blur_alpha_f32,
+    stack_blur_alpha_u16,
+};
+pub use alpha_mode::AlphaMode;
@@ -0,0 +1,108 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::alpha::premultiply::{
+    premultiply_row_f32, premultiply_row_u8, unpremultiply_row_f32, unpremultiply_row_u8,
+};
+use std::arch::aarch64::*;
+
+pub(crate) fn premultiply_row_neon_u8(row: &mut [u8]) {
+    unsafe {
+        let mut chunks = row.chunks_exact_mut(8);
+        for chunk in &mut chunks {
+            process_u8_x2(chunk, premultiply_u8_ps);
+        }
+        premultiply_row_u8(chunks.into_remainder());
+    }
+}
+
+pub(crate) fn unpremultiply_row_neon_u8(row: &mut [u8]) {
+    unsafe {
+        let mut chunks = row.chunks_exact_mut(8);
+        for chunk in &mut chunks {
+            process_u8_x2(chunk, unpremultiply_u8_ps);
+        }
+        unpremultiply_row_u8(chunks.into_remainder());
+    }
+}
+
+pub(crate) fn premultiply_row_neon_f32(row: &mut [f32]) {
+    unsafe {
+        let mut chunks = row.chunks_exact_mut(4);
+        for chunk in &mut chunks {
+            let v = vld1q_f32(chunk.as_ptr());
+            let a = vdupq_laneq_f32::<3>(v);
+            let premultiplied = vcopyq_laneq_f32::<3, 3>(vmulq_f32(v, a), v);
+            vst1q_f32(chunk.as_mut_ptr(), premultiplied);
+        }
+        premultiply_row_f32(chunks.into_remainder());
+    }
+}
+
+pub(crate) fn unpremultiply_row_neon_f32(row: &mut [f32]) {
+    unsafe {
+        let mut chunks = row.chunks_exact_mut(4);
+        for chunk in &mut chunks {
+            let v = vld1q_f32(chunk.as_ptr());
+            let a = vdupq_laneq_f32::<3>(v);
+            let divided = vbslq_f32(vceqzq_f32(a), vdupq_n_f32(0.), vdivq_f32(v, a));
+            vst1q_f32(chunk.as_mut_ptr(), vcopyq_laneq_f32::<3, 3>(divided, v));
+        }
+        unpremultiply_row_f32(chunks.into_remainder());
+    }
+}
+
+#[inline(always)]
+unsafe fn premultiply_u8_ps(v: float32x4_t) -> float32x4_t {
+    let a = vdupq_laneq_f32::<3>(v);
+    let premultiplied = vdivq_f32(vmulq_f32(v, a), vdupq_n_f32(255.));
+    vaddq_f32(vcopyq_laneq_f32::<3, 3>(premultiplied, v), vdupq_n_f32(0.5))
+}
+
+#[inline(always)]
+unsafe fn unpremultiply_u8_ps(v: float32x4_t) -> float32x4_t {
+    let a = vdupq_laneq_f32::<3>(v);
+    let divided = vminq_f32(
+        vdivq_f32(vmulq_f32(v, vdupq_n_f32(255.)), a),
+        vdupq_n_f32(255.),
+    );
+    let divided = vbslq_f32(vceqzq_f32(a), vdupq_n_f32(0.), divided);
+    vaddq_f32(vcopyq_laneq_f32::<3, 3>(divided, v), vdupq_n_f32(0.5))
+}
+
+/// Expands 2 RGBA pixels into 2 vectors, applies handler and packs them back with truncation.
+#[inline(always)]
+unsafe fn process_u8_x2(chunk: &mut [u8], handler: unsafe fn(float32x4_t) -> float32x4_t) {
+    let v = vmovl_u8(vld1_u8(chunk.as_ptr()));
+    let lo = vcvtq_f32_u32(vmovl_u16(vget_low_u16(v)));
+    let hi = vcvtq_f32_u32(vmovl_high_u16(v));
+    let lo = vcvtq_u32_f32(handler(lo));
+    let hi = vcvtq_u32_f32(handler(hi));
+    let packed = vqmovn_u16(vcombine_u16(vqmovn_u32(lo), vqmovn_u32(hi)));
+    vst1_u8(chunk.as_mut_ptr(), packed);
+}
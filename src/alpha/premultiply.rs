@@ -0,0 +1,290 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::{BlurImage, BlurImageMut};
+use novtb::{ParallelZonedIterator, TbSliceMut};
+use std::fmt::Debug;
+
+/// Handles premultiplication of interleaved 4 channels rows, alpha expected to be last.
+pub trait AlphaHandler: Copy + Default + Debug + Send + Sync + 'static {
+    /// Multiplies color channels by normalized alpha in place
+    fn premultiply_row() -> fn(&mut [Self]);
+
+    /// Divides color channels by normalized alpha in place, fully transparent pixels become zero
+    fn unpremultiply_row() -> fn(&mut [Self]);
+}
+
+pub(crate) fn premultiply_row_u8(row: &mut [u8]) {
+    for px in row.chunks_exact_mut(4) {
+        let a = px[3] as u16;
+        for c in px[..3].iter_mut() {
+            *c = ((*c as u16 * a + 127) / 255) as u8;
+        }
+    }
+}
+
+pub(crate) fn unpremultiply_row_u8(row: &mut [u8]) {
+    for px in row.chunks_exact_mut(4) {
+        let a = px[3] as u32;
+        if a == 0 {
+            px[..3].fill(0);
+            continue;
+        }
+        for c in px[..3].iter_mut() {
+            *c = ((*c as u32 * 255 + a / 2) / a).min(255) as u8;
+        }
+    }
+}
+
+fn premultiply_row_u16(row: &mut [u16]) {
+    for px in row.chunks_exact_mut(4) {
+        let a = px[3] as u32;
+        for c in px[..3].iter_mut() {
+            *c = ((*c as u32 * a + 32767) / 65535) as u16;
+        }
+    }
+}
+
+fn unpremultiply_row_u16(row: &mut [u16]) {
+    for px in row.chunks_exact_mut(4) {
+        let a = px[3] as u32;
+        if a == 0 {
+            px[..3].fill(0);
+            continue;
+        }
+        for c in px[..3].iter_mut() {
+            *c = ((*c as u32 * 65535 + a / 2) / a).min(65535) as u16;
+        }
+    }
+}
+
+pub(crate) fn premultiply_row_f32(row: &mut [f32]) {
+    for px in row.chunks_exact_mut(4) {
+        let a = px[3];
+        for c in px[..3].iter_mut() {
+            *c *= a;
+        }
+    }
+}
+
+pub(crate) fn unpremultiply_row_f32(row: &mut [f32]) {
+    for px in row.chunks_exact_mut(4) {
+        let a = px[3];
+        if a == 0. {
+            px[..3].fill(0.);
+            continue;
+        }
+        for c in px[..3].iter_mut() {
+            *c /= a;
+        }
+    }
+}
+
+impl AlphaHandler for u8 {
+    #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+    fn premultiply_row() -> fn(&mut [u8]) {
+        use crate::alpha::neon::premultiply_row_neon_u8;
+        premultiply_row_neon_u8
+    }
+
+    #[cfg(not(all(target_arch = "aarch64", feature = "neon")))]
+    fn premultiply_row() -> fn(&mut [u8]) {
+        #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
+        if std::arch::is_x86_feature_detected!("sse4.1") {
+            use crate::alpha::sse::premultiply_row_sse_u8;
+            return premultiply_row_sse_u8;
+        }
+        premultiply_row_u8
+    }
+
+    #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+    fn unpremultiply_row() -> fn(&mut [u8]) {
+        use crate::alpha::neon::unpremultiply_row_neon_u8;
+        unpremultiply_row_neon_u8
+    }
+
+    #[cfg(not(all(target_arch = "aarch64", feature = "neon")))]
+    fn unpremultiply_row() -> fn(&mut [u8]) {
+        #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
+        if std::arch::is_x86_feature_detected!("sse4.1") {
+            use crate::alpha::sse::unpremultiply_row_sse_u8;
+            return unpremultiply_row_sse_u8;
+        }
+        unpremultiply_row_u8
+    }
+}
+
+impl AlphaHandler for u16 {
+    fn premultiply_row() -> fn(&mut [u16]) {
+        premultiply_row_u16
+    }
+
+    fn unpremultiply_row() -> fn(&mut [u16]) {
+        unpremultiply_row_u16
+    }
+}
+
+impl AlphaHandler for f32 {
+    #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+    fn premultiply_row() -> fn(&mut [f32]) {
+        use crate::alpha::neon::premultiply_row_neon_f32;
+        premultiply_row_neon_f32
+    }
+
+    #[cfg(not(all(target_arch = "aarch64", feature = "neon")))]
+    fn premultiply_row() -> fn(&mut [f32]) {
+        #[cfg(all(target_arch = "x86_64", feature = "avx"))]
+        if std::arch::is_x86_feature_detected!("avx2") {
+            use crate::alpha::avx::premultiply_row_avx_f32;
+            return premultiply_row_avx_f32;
+        }
+        #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
+        if std::arch::is_x86_feature_detected!("sse4.1") {
+            use crate::alpha::sse::premultiply_row_sse_f32;
+            return premultiply_row_sse_f32;
+        }
+        premultiply_row_f32
+    }
+
+    #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+    fn unpremultiply_row() -> fn(&mut [f32]) {
+        use crate::alpha::neon::unpremultiply_row_neon_f32;
+        unpremultiply_row_neon_f32
+    }
+
+    #[cfg(not(all(target_arch = "aarch64", feature = "neon")))]
+    fn unpremultiply_row() -> fn(&mut [f32]) {
+        #[cfg(all(target_arch = "x86_64", feature = "avx"))]
+        if std::arch::is_x86_feature_detected!("avx2") {
+            use crate::alpha::avx::unpremultiply_row_avx_f32;
+            return unpremultiply_row_avx_f32;
+        }
+        #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
+        if std::arch::is_x86_feature_detected!("sse4.1") {
+            use crate::alpha::sse::unpremultiply_row_sse_f32;
+            return unpremultiply_row_sse_f32;
+        }
+        unpremultiply_row_f32
+    }
+}
+
+/// Copies source into destination premultiplying each row right after it was copied.
+pub(crate) fn premultiply_into<T: AlphaHandler>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    pool: &novtb::ThreadPool,
+) {
+    let row_length = src.width as usize * 4;
+    let src_stride = src.row_stride() as usize;
+    let dst_stride = dst.row_stride() as usize;
+    let executor = T::premultiply_row();
+    let src_data = src.data.as_ref();
+    // Last row may be shorter than stride
+    let dst_length = dst_stride * (dst.height as usize - 1) + row_length;
+    dst.data.borrow_mut()[..dst_length]
+        .tb_par_chunks_mut(dst_stride)
+        .for_each_enumerated(pool, |y, row| {
+            let row = &mut row[..row_length];
+            row.copy_from_slice(&src_data[y * src_stride..y * src_stride + row_length]);
+            executor(row);
+        });
+}
+
+/// Copies source into destination un-premultiplying each row right after it was copied.
+pub(crate) fn unpremultiply_into<T: AlphaHandler>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    pool: &novtb::ThreadPool,
+) {
+    let row_length = src.width as usize * 4;
+    let src_stride = src.row_stride() as usize;
+    let dst_stride = dst.row_stride() as usize;
+    let executor = T::unpremultiply_row();
+    let src_data = src.data.as_ref();
+    // Last row may be shorter than stride
+    let dst_length = dst_stride * (dst.height as usize - 1) + row_length;
+    dst.data.borrow_mut()[..dst_length]
+        .tb_par_chunks_mut(dst_stride)
+        .for_each_enumerated(pool, |y, row| {
+            let row = &mut row[..row_length];
+            row.copy_from_slice(&src_data[y * src_stride..y * src_stride + row_length]);
+            executor(row);
+        });
+}
+
+/// Applies row handler to every row of the image in place.
+pub(crate) fn alpha_in_place<T: AlphaHandler>(
+    image: &mut BlurImageMut<T>,
+    executor: fn(&mut [T]),
+    pool: &novtb::ThreadPool,
+) {
+    let row_length = image.width as usize * 4;
+    let stride = image.row_stride() as usize;
+    let length = stride * (image.height as usize - 1) + row_length;
+    image.data.borrow_mut()[..length]
+        .tb_par_chunks_mut(stride)
+        .for_each(pool, |row| {
+            executor(&mut row[..row_length]);
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_premultiply_handlers_match_scalar() {
+        let mut src = vec![0u8; 4 * 257];
+        for (i, px) in src.chunks_exact_mut(4).enumerate() {
+            px[0] = (i * 7 % 256) as u8;
+            px[1] = (i * 13 % 256) as u8;
+            px[2] = (i * 29 % 256) as u8;
+            px[3] = (i % 256) as u8;
+        }
+        let mut scalar = src.clone();
+        premultiply_row_u8(&mut scalar);
+        let mut dispatched = src.clone();
+        <u8 as AlphaHandler>::premultiply_row()(&mut dispatched);
+        assert_eq!(scalar, dispatched);
+
+        unpremultiply_row_u8(&mut scalar);
+        <u8 as AlphaHandler>::unpremultiply_row()(&mut dispatched);
+        assert_eq!(scalar, dispatched);
+
+        let src_f32: Vec<f32> = src.iter().map(|&x| x as f32 / 255.).collect();
+        let mut scalar = src_f32.clone();
+        premultiply_row_f32(&mut scalar);
+        unpremultiply_row_f32(&mut scalar);
+        let mut dispatched = src_f32.clone();
+        <f32 as AlphaHandler>::premultiply_row()(&mut dispatched);
+        <f32 as AlphaHandler>::unpremultiply_row()(&mut dispatched);
+        for (a, b) in scalar.iter().zip(dispatched.iter()) {
+            assert!((a - b).abs() < 1e-5, "Scalar {a} and SIMD {b} differs");
+        }
+    }
+}
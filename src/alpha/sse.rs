@@ -0,0 +1,146 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::alpha::premultiply::{
+    premultiply_row_f32, premultiply_row_u8, unpremultiply_row_f32, unpremultiply_row_u8,
+};
+#[cfg(target_arch = "x86")]
+use std::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+pub(crate) fn premultiply_row_sse_u8(row: &mut [u8]) {
+    unsafe {
+        premultiply_row_u8_impl(row);
+    }
+}
+
+pub(crate) fn unpremultiply_row_sse_u8(row: &mut [u8]) {
+    unsafe {
+        unpremultiply_row_u8_impl(row);
+    }
+}
+
+pub(crate) fn premultiply_row_sse_f32(row: &mut [f32]) {
+    unsafe {
+        premultiply_row_f32_impl(row);
+    }
+}
+
+pub(crate) fn unpremultiply_row_sse_f32(row: &mut [f32]) {
+    unsafe {
+        unpremultiply_row_f32_impl(row);
+    }
+}
+
+#[inline(always)]
+unsafe fn premultiply_ps(v: __m128) -> __m128 {
+    let a = _mm_shuffle_ps::<0xFF>(v, v);
+    _mm_blend_ps::<0b1000>(_mm_mul_ps(v, a), v)
+}
+
+#[inline(always)]
+unsafe fn unpremultiply_ps(v: __m128) -> __m128 {
+    let a = _mm_shuffle_ps::<0xFF>(v, v);
+    let non_zero = _mm_cmpneq_ps(a, _mm_setzero_ps());
+    let divided = _mm_and_ps(_mm_div_ps(v, a), non_zero);
+    _mm_blend_ps::<0b1000>(divided, v)
+}
+
+#[inline(always)]
+unsafe fn premultiply_u8_ps(v: __m128) -> __m128 {
+    let a = _mm_shuffle_ps::<0xFF>(v, v);
+    let premultiplied = _mm_div_ps(_mm_mul_ps(v, a), _mm_set1_ps(255.));
+    _mm_add_ps(_mm_blend_ps::<0b1000>(premultiplied, v), _mm_set1_ps(0.5))
+}
+
+#[inline(always)]
+unsafe fn unpremultiply_u8_ps(v: __m128) -> __m128 {
+    let a = _mm_shuffle_ps::<0xFF>(v, v);
+    let non_zero = _mm_cmpneq_ps(a, _mm_setzero_ps());
+    let divided = _mm_min_ps(
+        _mm_div_ps(_mm_mul_ps(v, _mm_set1_ps(255.)), a),
+        _mm_set1_ps(255.),
+    );
+    let divided = _mm_and_ps(divided, non_zero);
+    _mm_add_ps(_mm_blend_ps::<0b1000>(divided, v), _mm_set1_ps(0.5))
+}
+
+/// Expands 4 RGBA pixels into 4 vectors, applies handler and packs them back with truncation.
+#[inline(always)]
+unsafe fn process_u8_x4(chunk: &mut [u8], handler: unsafe fn(__m128) -> __m128) {
+    let v = _mm_loadu_si128(chunk.as_ptr() as *const __m128i);
+    let p0 = _mm_cvtepi32_ps(_mm_cvtepu8_epi32(v));
+    let p1 = _mm_cvtepi32_ps(_mm_cvtepu8_epi32(_mm_srli_si128::<4>(v)));
+    let p2 = _mm_cvtepi32_ps(_mm_cvtepu8_epi32(_mm_srli_si128::<8>(v)));
+    let p3 = _mm_cvtepi32_ps(_mm_cvtepu8_epi32(_mm_srli_si128::<12>(v)));
+    let r0 = _mm_cvttps_epi32(handler(p0));
+    let r1 = _mm_cvttps_epi32(handler(p1));
+    let r2 = _mm_cvttps_epi32(handler(p2));
+    let r3 = _mm_cvttps_epi32(handler(p3));
+    let packed = _mm_packus_epi16(_mm_packus_epi32(r0, r1), _mm_packus_epi32(r2, r3));
+    _mm_storeu_si128(chunk.as_mut_ptr() as *mut __m128i, packed);
+}
+
+#[target_feature(enable = "sse4.1")]
+unsafe fn premultiply_row_u8_impl(row: &mut [u8]) {
+    let mut chunks = row.chunks_exact_mut(16);
+    for chunk in &mut chunks {
+        process_u8_x4(chunk, premultiply_u8_ps);
+    }
+    premultiply_row_u8(chunks.into_remainder());
+}
+
+#[target_feature(enable = "sse4.1")]
+unsafe fn unpremultiply_row_u8_impl(row: &mut [u8]) {
+    let mut chunks = row.chunks_exact_mut(16);
+    for chunk in &mut chunks {
+        process_u8_x4(chunk, unpremultiply_u8_ps);
+    }
+    unpremultiply_row_u8(chunks.into_remainder());
+}
+
+#[target_feature(enable = "sse4.1")]
+unsafe fn premultiply_row_f32_impl(row: &mut [f32]) {
+    let mut chunks = row.chunks_exact_mut(4);
+    for chunk in &mut chunks {
+        let v = _mm_loadu_ps(chunk.as_ptr());
+        _mm_storeu_ps(chunk.as_mut_ptr(), premultiply_ps(v));
+    }
+    premultiply_row_f32(chunks.into_remainder());
+}
+
+#[target_feature(enable = "sse4.1")]
+unsafe fn unpremultiply_row_f32_impl(row: &mut [f32]) {
+    let mut chunks = row.chunks_exact_mut(4);
+    for chunk in &mut chunks {
+        let v = _mm_loadu_ps(chunk.as_ptr());
+        _mm_storeu_ps(chunk.as_mut_ptr(), unpremultiply_ps(v));
+    }
+    unpremultiply_row_f32(chunks.into_remainder());
+}
@@ -0,0 +1,351 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::{
+    box_blur, box_blur_f32, box_blur_u16, stack_blur, stack_blur_f32, stack_blur_u16,
+    AnisotropicRadius, BlurError, BlurImage, BlurImageMut, BoxBlurParameters, BufferStore,
+    FastBlurChannels, ThreadingPolicy,
+};
+use std::borrow::Cow;
+use std::fmt::Debug;
+
+/// Copies one component of interleaved N-channel image into a plane.
+fn extract_channel<T: Copy + Default + Debug, const N: usize>(
+    src: &[T],
+    src_stride: usize,
+    channel: usize,
+    plane: &mut BlurImageMut<T>,
+) {
+    let width = plane.width as usize;
+    let plane_stride = plane.row_stride() as usize;
+    for (dst, src) in plane
+        .data
+        .borrow_mut()
+        .chunks_exact_mut(plane_stride)
+        .zip(src.chunks(src_stride))
+    {
+        for (dst, src) in dst.iter_mut().zip(src[..width * N].chunks_exact(N)) {
+            *dst = src[channel];
+        }
+    }
+}
+
+/// Writes plane back into one component of interleaved N-channel image.
+fn insert_channel<T: Copy + Default + Debug, const N: usize>(
+    plane: &BlurImageMut<T>,
+    dst: &mut [T],
+    dst_stride: usize,
+    channel: usize,
+) {
+    let width = plane.width as usize;
+    let plane_stride = plane.row_stride() as usize;
+    for (src, dst) in plane
+        .data
+        .borrow()
+        .chunks_exact(plane_stride)
+        .zip(dst.chunks_mut(dst_stride))
+    {
+        for (src, dst) in src.iter().zip(dst[..width * N].chunks_exact_mut(N)) {
+            dst[channel] = *src;
+        }
+    }
+}
+
+/// Runs executor natively when N is one of [FastBlurChannels],
+/// otherwise executes it on every channel plane separately.
+fn arbitrary_executor<T, const N: usize>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    executor: impl Fn(&BlurImage<T>, &mut BlurImageMut<T>) -> Result<(), BlurError>,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug,
+{
+    if N == 0 {
+        return Err(BlurError::ChannelsNotSupported(N));
+    }
+    src.check_layout_channels(N)?;
+    dst.check_layout_channels(N, Some(src))?;
+    src.only_size_matches_mut(dst)?;
+    let (width, height) = (src.width, src.height);
+    let (src_stride, dst_stride) = (src.stride, dst.stride);
+
+    if let Ok(channels) = FastBlurChannels::try_from(N) {
+        let src_view = BlurImage {
+            data: Cow::Borrowed(src.data.as_ref()),
+            width,
+            height,
+            stride: src_stride,
+            channels,
+        };
+        let mut dst_view = BlurImageMut {
+            data: BufferStore::Borrowed(dst.data.borrow_mut()),
+            width,
+            height,
+            stride: dst_stride,
+            channels,
+        };
+        return executor(&src_view, &mut dst_view);
+    }
+
+    let mut plane = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
+    let mut blurred = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
+    for channel in 0..N {
+        extract_channel::<T, N>(src.data.as_ref(), src_stride as usize, channel, &mut plane);
+        executor(&plane.to_immutable_ref(), &mut blurred)?;
+        insert_channel::<T, N>(
+            &blurred,
+            dst.data.borrow_mut(),
+            dst_stride as usize,
+            channel,
+        );
+    }
+    Ok(())
+}
+
+fn arbitrary_executor_in_place<T, const N: usize>(
+    image: &mut BlurImageMut<T>,
+    executor: impl Fn(&mut BlurImageMut<T>) -> Result<(), BlurError>,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug,
+{
+    if N == 0 {
+        return Err(BlurError::ChannelsNotSupported(N));
+    }
+    image.check_layout_channels(N, None)?;
+    let (width, height, stride) = (image.width, image.height, image.stride);
+
+    if let Ok(channels) = FastBlurChannels::try_from(N) {
+        let mut view = BlurImageMut {
+            data: BufferStore::Borrowed(image.data.borrow_mut()),
+            width,
+            height,
+            stride,
+            channels,
+        };
+        return executor(&mut view);
+    }
+
+    let mut plane = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
+    for channel in 0..N {
+        extract_channel::<T, N>(image.data.borrow(), stride as usize, channel, &mut plane);
+        executor(&mut plane)?;
+        insert_channel::<T, N>(&plane, image.data.borrow_mut(), stride as usize, channel);
+    }
+    Ok(())
+}
+
+/// Performs box blur on the image with arbitrary interleaved channels count.
+///
+/// Images with 1, 2, 3 or 4 channels are blurred natively as with [box_blur],
+/// any other count is blurred plane by plane.
+/// `FastBlurChannels` of the images are ignored, `stride` must be set explicitly.
+///
+/// # Arguments
+///
+/// * `N` - Channels count.
+/// * `image` - Source image.
+/// * `dst_image` - Destination image.
+/// * `parameters` - See [BoxBlurParameters] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn box_blur_arbitrary<const N: usize>(
+    image: &BlurImage<u8>,
+    dst_image: &mut BlurImageMut<u8>,
+    parameters: BoxBlurParameters,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    arbitrary_executor::<u8, N>(image, dst_image, |src, dst| {
+        box_blur(src, dst, parameters, threading_policy)
+    })
+}
+
+/// Performs box blur on the 16-bit image with arbitrary interleaved channels count.
+///
+/// See [box_blur_arbitrary] for details.
+pub fn box_blur_arbitrary_u16<const N: usize>(
+    image: &BlurImage<u16>,
+    dst_image: &mut BlurImageMut<u16>,
+    parameters: BoxBlurParameters,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    arbitrary_executor::<u16, N>(image, dst_image, |src, dst| {
+        box_blur_u16(src, dst, parameters, threading_policy)
+    })
+}
+
+/// Performs box blur on the f32 image with arbitrary interleaved channels count.
+///
+/// See [box_blur_arbitrary] for details.
+pub fn box_blur_arbitrary_f32<const N: usize>(
+    image: &BlurImage<f32>,
+    dst_image: &mut BlurImageMut<f32>,
+    parameters: BoxBlurParameters,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    arbitrary_executor::<f32, N>(image, dst_image, |src, dst| {
+        box_blur_f32(src, dst, parameters, threading_policy)
+    })
+}
+
+/// Performs stack blur on the image with arbitrary interleaved channels count.
+///
+/// Images with 1, 2, 3 or 4 channels are blurred natively as with [stack_blur],
+/// any other count is blurred plane by plane.
+/// `FastBlurChannels` of the image is ignored, `stride` must be set explicitly.
+///
+/// # Arguments
+///
+/// * `N` - Channels count.
+/// * `image` - Mutable image, used as source and destination.
+/// * `radius` - Radius more than 1, see [AnisotropicRadius] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn stack_blur_arbitrary<const N: usize>(
+    image: &mut BlurImageMut<u8>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    arbitrary_executor_in_place::<u8, N>(image, |image| stack_blur(image, radius, threading_policy))
+}
+
+/// Performs stack blur on the 16-bit image with arbitrary interleaved channels count.
+///
+/// See [stack_blur_arbitrary] for details.
+pub fn stack_blur_arbitrary_u16<const N: usize>(
+    image: &mut BlurImageMut<u16>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    arbitrary_executor_in_place::<u16, N>(image, |image| {
+        stack_blur_u16(image, radius, threading_policy)
+    })
+}
+
+/// Performs stack blur on the f32 image with arbitrary interleaved channels count.
+///
+/// See [stack_blur_arbitrary] for details.
+pub fn stack_blur_arbitrary_f32<const N: usize>(
+    image: &mut BlurImageMut<f32>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    arbitrary_executor_in_place::<f32, N>(image, |image| {
+        stack_blur_f32(image, radius, threading_policy)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_image<const N: usize>(width: u32, height: u32) -> Vec<u8> {
+        (0..width as usize * height as usize * N)
+            .map(|i| ((i * 37 + i / N * 11) % 251) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn test_box_blur_arbitrary_matches_planes() {
+        const N: usize = 6;
+        let (width, height) = (31u32, 17u32);
+        let data = make_image::<N>(width, height);
+        let src = BlurImage {
+            data: Cow::Borrowed(&data),
+            width,
+            height,
+            stride: width * N as u32,
+            channels: FastBlurChannels::Plane,
+        };
+        let mut dst = BlurImageMut::default();
+        box_blur_arbitrary::<N>(
+            &src,
+            &mut dst,
+            BoxBlurParameters::new(5),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let blurred = dst.data.borrow();
+        for channel in 0..N {
+            let plane: Vec<u8> = data.iter().skip(channel).step_by(N).copied().collect();
+            let plane_image = BlurImage::borrow(&plane, width, height, FastBlurChannels::Plane);
+            let mut plane_dst = BlurImageMut::default();
+            box_blur(
+                &plane_image,
+                &mut plane_dst,
+                BoxBlurParameters::new(5),
+                ThreadingPolicy::Single,
+            )
+            .unwrap();
+            for (a, b) in plane_dst
+                .data
+                .borrow()
+                .iter()
+                .zip(blurred.iter().skip(channel).step_by(N))
+            {
+                assert_eq!(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_stack_blur_arbitrary_two_channels() {
+        const N: usize = 2;
+        let (width, height) = (25u32, 19u32);
+        let mut data = vec![0u8; width as usize * height as usize * N];
+        for px in data.chunks_exact_mut(N) {
+            px[0] = 77;
+            px[1] = 201;
+        }
+        let mut image = BlurImageMut::borrow(&mut data, width, height, FastBlurChannels::Plane);
+        image.stride = width * N as u32;
+        stack_blur_arbitrary::<N>(
+            &mut image,
+            AnisotropicRadius::new(4),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        for px in image.data.borrow().chunks_exact(N) {
+            assert!((px[0] as i32 - 77).abs() <= 2);
+            assert!((px[1] as i32 - 201).abs() <= 2);
+        }
+    }
+
+    #[test]
+    fn test_arbitrary_rejects_short_stride() {
+        let data = make_image::<5>(8, 8);
+        let src = BlurImage::borrow(&data, 8, 8, FastBlurChannels::Channels4);
+        let mut dst = BlurImageMut::default();
+        let result = box_blur_arbitrary::<5>(
+            &src,
+            &mut dst,
+            BoxBlurParameters::new(3),
+            ThreadingPolicy::Single,
+        );
+        assert!(result.is_err());
+    }
+}
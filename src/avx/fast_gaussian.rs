@@ -114,7 +114,7 @@ unsafe fn fg_horizontal_pass_avx_u8_impl<const CN: usize>(
 
                 let ps01 = _mm256_cvtepi32_ps(summs0);
                 let ps23 = _mm256_cvtepi32_ps(summs1);
-                let ps45 = _mm256_cvtepi32_ps(summs1);
+                let ps45 = _mm256_cvtepi32_ps(summs2);
 
                 let r01 = _mm256_mul_ps(ps01, v_weight);
                 let r23 = _mm256_mul_ps(ps23, v_weight);
@@ -102,6 +102,13 @@ struct ExecutionUnit3<'a> {
     src_width: usize,
 }
 
+struct ExecutionUnit2<'a> {
+    arena: Arena,
+    params: BilateralBlurParams,
+    store: &'a BilateralStore,
+    src_width: usize,
+}
+
 struct ExecutionUnitPlane<'a> {
     arena: Arena,
     params: BilateralBlurParams,
@@ -220,6 +227,51 @@ impl BilateralUnit<u8> for ExecutionUnit3<'_> {
     }
 }
 
+impl BilateralUnit<u8> for ExecutionUnit2<'_> {
+    fn execute(&self, a_src: &[u8], y: usize, dst_row: &mut [u8], src_row: &[u8]) {
+        const N: usize = 2;
+        let sliced_range = &self.store.range[..self.params.kernel_size * self.params.kernel_size];
+        let ss = &self.store.spatial;
+        let useful_width = self.src_width * N;
+        let a_stride = self.arena.width * self.arena.components;
+        let dst_row = &mut dst_row[..useful_width];
+        let src_row = &src_row[..useful_width];
+        for (x, (dst, center)) in dst_row
+            .chunks_exact_mut(N)
+            .zip(src_row.chunks_exact(N))
+            .enumerate()
+        {
+            let mut sum0 = 0f32;
+            let mut sum1 = 0f32;
+
+            let mut iw0 = 0f32;
+            let mut iw1 = 0f32;
+
+            for (ky, ky_row) in sliced_range
+                .chunks_exact(self.params.kernel_size)
+                .enumerate()
+            {
+                let c_slice = (y + ky) * a_stride + x * N;
+                let c_px_slice = &a_src[c_slice..(c_slice + N * self.params.kernel_size)];
+                for (c_px, &rwz) in c_px_slice.chunks_exact(N).zip(ky_row.iter()) {
+                    let z0 = rwz * ss[(center[0] as u16 * 256 + c_px[0] as u16) as usize];
+                    let z1 = rwz * ss[(center[1] as u16 * 256 + c_px[1] as u16) as usize];
+                    sum0 += z0 * c_px[0] as f32;
+                    sum1 += z1 * c_px[1] as f32;
+                    iw0 += z0;
+                    iw1 += z1;
+                }
+            }
+
+            iw0 = if iw0 == 0. { 1. } else { iw0 };
+            iw1 = if iw1 == 0. { 1. } else { iw1 };
+
+            dst[0] = (sum0 / iw0).round().min(255.).max(0.) as u8;
+            dst[1] = (sum1 / iw1).round().min(255.).max(0.) as u8;
+        }
+    }
+}
+
 impl BilateralUnit<u8> for ExecutionUnitPlane<'_> {
     fn execute(&self, a_src: &[u8], y: usize, dst_row: &mut [u8], src_row: &[u8]) {
         const N: usize = 1;
@@ -293,6 +345,12 @@ fn bilateral_filter_impl<const N: usize>(
             store: &store,
             src_width: src.width as usize,
         }),
+        2 => Box::new(ExecutionUnit2 {
+            arena: arena_cfg,
+            params,
+            store: &store,
+            src_width: src.width as usize,
+        }),
         3 => Box::new(ExecutionUnit3 {
             arena: arena_cfg,
             params,
@@ -332,6 +390,7 @@ fn bilateral_filter_impl<const N: usize>(
     Ok(())
 }
 
+#[repr(C)]
 #[derive(Copy, Clone, Debug)]
 pub struct BilateralBlurParams {
     pub kernel_size: usize,
@@ -396,6 +455,14 @@ pub fn bilateral_filter(
             constant_border,
             threading_policy,
         ),
+        FastBlurChannels::Channels2 => bilateral_filter_impl::<2>(
+            src,
+            dst,
+            copied_params,
+            edge_mode,
+            constant_border,
+            threading_policy,
+        ),
         FastBlurChannels::Channels3 => bilateral_filter_impl::<3>(
             src,
             dst,
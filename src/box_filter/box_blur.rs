@@ -34,8 +34,25 @@ use novtb::{ParallelZonedIterator, TbSliceMut};
 use num_traits::cast::FromPrimitive;
 use num_traits::AsPrimitive;
 use std::fmt::Debug;
+use std::ops::Range;
+
+/// Part of `0..total` processed by the given thread, last thread takes the remainder.
+pub(crate) fn thread_segment(
+    total: usize,
+    thread_count: usize,
+    thread_index: usize,
+) -> Range<usize> {
+    let segment_size = total / thread_count;
+    let start = thread_index * segment_size;
+    if thread_index == thread_count - 1 {
+        start..total
+    } else {
+        start..start + segment_size
+    }
+}
 
 /// Both kernels are expected to be odd.
+#[repr(C)]
 #[derive(Copy, Clone, Debug)]
 pub struct BoxBlurParameters {
     /// X-axis kernel size
@@ -468,12 +485,7 @@ fn box_blur_horizontal_pass<
     let unsafe_dst = UnsafeSlice::new(dst);
     let pool = novtb::ThreadPool::new(thread_count as usize);
     pool.parallel_for(|thread_index| {
-        let segment_size = height / thread_count;
-        let start_y = thread_index as u32 * segment_size;
-        let mut end_y = (thread_index as u32 + 1) * segment_size;
-        if thread_index as u32 == thread_count - 1 {
-            end_y = height;
-        }
+        let rows = thread_segment(height as usize, thread_count as usize, thread_index);
         _dispatcher_horizontal(
             src,
             src_stride,
@@ -481,8 +493,8 @@ fn box_blur_horizontal_pass<
             dst_stride,
             width,
             radius,
-            start_y,
-            end_y,
+            rows.start as u32,
+            rows.end as u32,
         );
     });
 }
@@ -726,13 +738,7 @@ fn box_blur_vertical_pass<
 
     let pool = novtb::ThreadPool::new(thread_count as usize);
     pool.parallel_for(|thread_index| {
-        let total_width = width as usize * CN;
-        let segment_size = total_width / thread_count as usize;
-        let start_x = thread_index * segment_size;
-        let mut end_x = (thread_index + 1) * segment_size;
-        if thread_index == thread_count as usize - 1 {
-            end_x = total_width;
-        }
+        let columns = thread_segment(width as usize * CN, thread_count as usize, thread_index);
         _dispatcher_vertical(
             src,
             src_stride,
@@ -741,8 +747,8 @@ fn box_blur_vertical_pass<
             width,
             height,
             radius,
-            start_x as u32,
-            end_x as u32,
+            columns.start as u32,
+            columns.end as u32,
         );
     });
 }
@@ -1376,6 +1382,7 @@ pub fn box_blur(
     let thread_count = threading_policy.thread_count(width, height) as u32;
     let _dispatcher = match image.channels {
         FastBlurChannels::Plane => box_blur_impl::<u8, 1>,
+        FastBlurChannels::Channels2 => box_blur_impl::<u8, 2>,
         FastBlurChannels::Channels3 => box_blur_impl::<u8, 3>,
         FastBlurChannels::Channels4 => box_blur_impl::<u8, 4>,
     };
@@ -1425,6 +1432,7 @@ pub fn box_blur_u16(
     let thread_count = threading_policy.thread_count(width, height) as u32;
     let dispatcher = match image.channels {
         FastBlurChannels::Plane => box_blur_impl::<u16, 1>,
+        FastBlurChannels::Channels2 => box_blur_impl::<u16, 2>,
         FastBlurChannels::Channels3 => box_blur_impl::<u16, 3>,
         FastBlurChannels::Channels4 => box_blur_impl::<u16, 4>,
     };
@@ -1475,6 +1483,7 @@ pub fn box_blur_f32(
     let thread_count = threading_policy.thread_count(width, height) as u32;
     let dispatcher = match image.channels {
         FastBlurChannels::Plane => box_blur_impl::<f32, 1>,
+        FastBlurChannels::Channels2 => box_blur_impl::<f32, 2>,
         FastBlurChannels::Channels3 => box_blur_impl::<f32, 3>,
         FastBlurChannels::Channels4 => box_blur_impl::<f32, 4>,
     };
@@ -1628,6 +1637,7 @@ pub fn tent_blur(
     parameters.validate()?;
     let dispatcher = match image.channels {
         FastBlurChannels::Plane => tent_blur_impl::<u8, 1>,
+        FastBlurChannels::Channels2 => tent_blur_impl::<u8, 2>,
         FastBlurChannels::Channels3 => tent_blur_impl::<u8, 3>,
         FastBlurChannels::Channels4 => tent_blur_impl::<u8, 4>,
     };
@@ -1678,6 +1688,7 @@ pub fn tent_blur_u16(
     parameters.validate()?;
     let dispatcher = match image.channels {
         FastBlurChannels::Plane => tent_blur_impl::<u16, 1>,
+        FastBlurChannels::Channels2 => tent_blur_impl::<u16, 2>,
         FastBlurChannels::Channels3 => tent_blur_impl::<u16, 3>,
         FastBlurChannels::Channels4 => tent_blur_impl::<u16, 4>,
     };
@@ -1726,6 +1737,7 @@ pub fn tent_blur_f32(
     parameters.validate()?;
     let dispatcher = match image.channels {
         FastBlurChannels::Plane => tent_blur_impl::<f32, 1>,
+        FastBlurChannels::Channels2 => tent_blur_impl::<f32, 2>,
         FastBlurChannels::Channels3 => tent_blur_impl::<f32, 3>,
         FastBlurChannels::Channels4 => tent_blur_impl::<f32, 4>,
     };
@@ -1853,6 +1865,7 @@ pub fn gaussian_box_blur(
     parameters.validate()?;
     let dispatcher = match image.channels {
         FastBlurChannels::Plane => gaussian_box_blur_impl::<u8, 1>,
+        FastBlurChannels::Channels2 => gaussian_box_blur_impl::<u8, 2>,
         FastBlurChannels::Channels3 => gaussian_box_blur_impl::<u8, 3>,
         FastBlurChannels::Channels4 => gaussian_box_blur_impl::<u8, 4>,
     };
@@ -1906,6 +1919,7 @@ pub fn gaussian_box_blur_u16(
     let channels = image.channels;
     let executor = match channels {
         FastBlurChannels::Plane => gaussian_box_blur_impl::<u16, 1>,
+        FastBlurChannels::Channels2 => gaussian_box_blur_impl::<u16, 2>,
         FastBlurChannels::Channels3 => gaussian_box_blur_impl::<u16, 3>,
         FastBlurChannels::Channels4 => gaussian_box_blur_impl::<u16, 4>,
     };
@@ -1959,6 +1973,7 @@ pub fn gaussian_box_blur_f32(
     let channels = image.channels;
     let dispatcher = match channels {
         FastBlurChannels::Plane => gaussian_box_blur_impl::<f32, 1>,
+        FastBlurChannels::Channels2 => gaussian_box_blur_impl::<f32, 2>,
         FastBlurChannels::Channels3 => gaussian_box_blur_impl::<f32, 3>,
         FastBlurChannels::Channels4 => gaussian_box_blur_impl::<f32, 4>,
     };
@@ -0,0 +1,416 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::box_filter::box_blur::thread_segment;
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{AnisotropicRadius, BlurError, BlurImage, BlurImageMut, ThreadingPolicy};
+
+/// Columns processed at once in vertical pass
+const VERTICAL_LANES: usize = 256;
+
+#[inline(always)]
+fn select<T: Copy + PartialOrd, const MIN: bool>(a: T, b: T) -> T {
+    if MIN {
+        if b < a {
+            b
+        } else {
+            a
+        }
+    } else if b > a {
+        b
+    } else {
+        a
+    }
+}
+
+/// Runs van Herk/Gil-Werman filter along `n` samples spaced by `src_step`,
+/// every sample has `lanes` consecutive items filtered independently.
+///
+/// Line is virtually padded by `radius` replicated samples on both sides,
+/// prefix and suffix extremums are accumulated inside blocks of window size,
+/// so any window is covered by one suffix and one prefix.
+#[allow(clippy::too_many_arguments)]
+fn van_herk_line<T: Copy + PartialOrd + Default, const MIN: bool>(
+    src: &[T],
+    src_start: usize,
+    src_step: usize,
+    dst: &UnsafeSlice<T>,
+    dst_start: usize,
+    dst_step: usize,
+    n: usize,
+    lanes: usize,
+    radius: usize,
+    prefix: &mut Vec<T>,
+    suffix: &mut Vec<T>,
+) {
+    // Edges are replicated, so any radius past the line length gives the same extremum
+    let radius = radius.min(n - 1);
+    let window = 2 * radius + 1;
+    let padded = n + 2 * radius;
+    prefix.resize(padded * lanes, T::default());
+    suffix.resize(padded * lanes, T::default());
+
+    let sample = |j: usize| {
+        let source = j.saturating_sub(radius).min(n - 1);
+        &src[src_start + source * src_step..][..lanes]
+    };
+
+    for j in 0..padded {
+        let sample = sample(j);
+        let (previous, current) = prefix.split_at_mut(j * lanes);
+        let current = &mut current[..lanes];
+        if j % window == 0 {
+            current.copy_from_slice(sample);
+        } else {
+            let previous = &previous[(j - 1) * lanes..];
+            for ((dst, &p), &s) in current.iter_mut().zip(previous.iter()).zip(sample.iter()) {
+                *dst = select::<T, MIN>(p, s);
+            }
+        }
+    }
+
+    for j in (0..padded).rev() {
+        let sample = sample(j);
+        let (current, next) = suffix.split_at_mut((j + 1) * lanes);
+        let current = &mut current[j * lanes..];
+        if j == padded - 1 || (j + 1) % window == 0 {
+            current.copy_from_slice(sample);
+        } else {
+            for ((dst, &p), &s) in current.iter_mut().zip(next.iter()).zip(sample.iter()) {
+                *dst = select::<T, MIN>(p, s);
+            }
+        }
+    }
+
+    for i in 0..n {
+        let suffix = &suffix[i * lanes..][..lanes];
+        let prefix = &prefix[(i + window - 1) * lanes..][..lanes];
+        let offset = dst_start + i * dst_step;
+        for (l, (&s, &p)) in suffix.iter().zip(prefix.iter()).enumerate() {
+            unsafe {
+                dst.write(offset + l, select::<T, MIN>(s, p));
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn min_max_horizontal_pass<T: Copy + PartialOrd + Default + Send + Sync, const MIN: bool>(
+    src: &[T],
+    src_stride: u32,
+    dst: &mut [T],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    channels: usize,
+    radius: u32,
+    thread_count: u32,
+) {
+    let unsafe_dst = UnsafeSlice::new(dst);
+    let pool = novtb::ThreadPool::new(thread_count as usize);
+    pool.parallel_for(|thread_index| {
+        let mut prefix = Vec::new();
+        let mut suffix = Vec::new();
+        for y in thread_segment(height as usize, thread_count as usize, thread_index) {
+            van_herk_line::<T, MIN>(
+                src,
+                y * src_stride as usize,
+                channels,
+                &unsafe_dst,
+                y * dst_stride as usize,
+                channels,
+                width as usize,
+                channels,
+                radius as usize,
+                &mut prefix,
+                &mut suffix,
+            );
+        }
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn min_max_vertical_pass<T: Copy + PartialOrd + Default + Send + Sync, const MIN: bool>(
+    src: &[T],
+    src_stride: u32,
+    dst: &mut [T],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    channels: usize,
+    radius: u32,
+    thread_count: u32,
+) {
+    let unsafe_dst = UnsafeSlice::new(dst);
+    let pool = novtb::ThreadPool::new(thread_count as usize);
+    pool.parallel_for(|thread_index| {
+        let columns = thread_segment(
+            width as usize * channels,
+            thread_count as usize,
+            thread_index,
+        );
+        let mut prefix = Vec::new();
+        let mut suffix = Vec::new();
+        let mut x = columns.start;
+        while x < columns.end {
+            let lanes = (columns.end - x).min(VERTICAL_LANES);
+            van_herk_line::<T, MIN>(
+                src,
+                x,
+                src_stride as usize,
+                &unsafe_dst,
+                x,
+                dst_stride as usize,
+                height as usize,
+                lanes,
+                radius as usize,
+                &mut prefix,
+                &mut suffix,
+            );
+            x += lanes;
+        }
+    });
+}
+
+fn min_max_filter_impl<
+    T: Copy + PartialOrd + Default + Send + Sync + std::fmt::Debug,
+    const MIN: bool,
+>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    dst.check_layout(Some(src))?;
+    src.size_matches_mut(dst)?;
+    let width = src.width;
+    let height = src.height;
+    let channels = src.channels.channels();
+    let thread_count = threading_policy.thread_count(width, height) as u32;
+    let transient_stride = width * channels as u32;
+    let mut transient = vec![T::default(); transient_stride as usize * height as usize];
+    min_max_horizontal_pass::<T, MIN>(
+        src.data.as_ref(),
+        src.row_stride(),
+        &mut transient,
+        transient_stride,
+        width,
+        height,
+        channels,
+        radius.x_axis,
+        thread_count,
+    );
+    let dst_stride = dst.row_stride();
+    min_max_vertical_pass::<T, MIN>(
+        &transient,
+        transient_stride,
+        dst.data.borrow_mut(),
+        dst_stride,
+        width,
+        height,
+        channels,
+        radius.y_axis,
+        thread_count,
+    );
+    Ok(())
+}
+
+/// Performs rectangular minimum filter on the image.
+///
+/// This is grayscale erosion with rectangle `2 * radius + 1` structuring element,
+/// uses van Herk/Gil-Werman algorithm, edge pixels are replicated.
+///
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source immutable image, see [BlurImage] for more info.
+/// * `dst` - Destination mutable image, see [BlurImageMut] for more info.
+/// * `radius` - see [AnisotropicRadius] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn min_filter(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    min_max_filter_impl::<u8, true>(src, dst, radius, threading_policy)
+}
+
+/// Performs rectangular minimum filter on the 16-bit image.
+///
+/// See [min_filter] for more info.
+pub fn min_filter_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    min_max_filter_impl::<u16, true>(src, dst, radius, threading_policy)
+}
+
+/// Performs rectangular minimum filter on the f32 image.
+///
+/// See [min_filter] for more info.
+pub fn min_filter_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    min_max_filter_impl::<f32, true>(src, dst, radius, threading_policy)
+}
+
+/// Performs rectangular maximum filter on the image.
+///
+/// This is grayscale dilation with rectangle `2 * radius + 1` structuring element,
+/// uses van Herk/Gil-Werman algorithm, edge pixels are replicated.
+///
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source immutable image, see [BlurImage] for more info.
+/// * `dst` - Destination mutable image, see [BlurImageMut] for more info.
+/// * `radius` - see [AnisotropicRadius] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn max_filter(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    min_max_filter_impl::<u8, false>(src, dst, radius, threading_policy)
+}
+
+/// Performs rectangular maximum filter on the 16-bit image.
+///
+/// See [max_filter] for more info.
+pub fn max_filter_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    min_max_filter_impl::<u16, false>(src, dst, radius, threading_policy)
+}
+
+/// Performs rectangular maximum filter on the f32 image.
+///
+/// See [max_filter] for more info.
+pub fn max_filter_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    min_max_filter_impl::<f32, false>(src, dst, radius, threading_policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FastBlurChannels;
+
+    #[test]
+    fn test_min_filter_grows_dark_spots_into_rectangles() {
+        let (width, height) = (67usize, 41usize);
+        let spots = [(0usize, 0usize, 40u8), (30, 20, 10)];
+        let mut src = vec![126u8; width * height * 3];
+        for &(x, y, v) in spots.iter() {
+            src[(y * width + x) * 3..(y * width + x + 1) * 3].fill(v);
+        }
+        let src_image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        // Last radii exceed the image, every pixel takes the global minimum
+        for (x_radius, y_radius) in [(0, 0), (1, 1), (4, 2), (9, 17), (50, 30), (100, 1000)] {
+            let mut dst_image = BlurImageMut::default();
+            min_filter(
+                &src_image,
+                &mut dst_image,
+                AnisotropicRadius::create(x_radius as u32, y_radius as u32),
+                ThreadingPolicy::Adaptive,
+            )
+            .unwrap();
+            let dst = dst_image.data.borrow();
+            for y in 0..height {
+                for x in 0..width {
+                    let expected = spots
+                        .iter()
+                        .filter(|&&(sx, sy, _)| {
+                            x.abs_diff(sx) <= x_radius && y.abs_diff(sy) <= y_radius
+                        })
+                        .map(|&(_, _, v)| v)
+                        .fold(126u8, u8::min);
+                    let offset = (y * width + x) * 3;
+                    assert_eq!(
+                        dst[offset..offset + 3],
+                        [expected; 3],
+                        "radius {x_radius}x{y_radius} at {x}, {y}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_max_filter_u16_grows_bright_spots_into_rectangles() {
+        let (width, height) = (33usize, 57usize);
+        let spots = [(32usize, 56usize, 40000u16), (11, 25, 60000)];
+        let mut src = vec![17234u16; width * height];
+        for &(x, y, v) in spots.iter() {
+            src[y * width + x] = v;
+        }
+        let src_image =
+            BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        let mut dst_image = BlurImageMut::default();
+        max_filter_u16(
+            &src_image,
+            &mut dst_image,
+            AnisotropicRadius::create(6, 3),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let dst = dst_image.data.borrow();
+        for y in 0..height {
+            for x in 0..width {
+                let expected = spots
+                    .iter()
+                    .filter(|&&(sx, sy, _)| x.abs_diff(sx) <= 6 && y.abs_diff(sy) <= 3)
+                    .map(|&(_, _, v)| v)
+                    .fold(17234u16, u16::max);
+                assert_eq!(dst[y * width + x], expected, "at {x}, {y}");
+            }
+        }
+    }
+}
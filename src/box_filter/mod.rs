@@ -28,12 +28,16 @@
 #[cfg(all(target_arch = "x86_64", feature = "avx"))]
 mod avx;
 mod box_blur;
+mod min_max_filter;
 #[cfg(all(target_arch = "aarch64", feature = "neon"))]
 mod neon;
 #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
 mod sse;
 
 pub use box_blur::*;
+pub use min_max_filter::{
+    max_filter, max_filter_f32, max_filter_u16, min_filter, min_filter_f32, min_filter_u16,
+};
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,305 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::gradient::gradient_xy;
+use crate::{
+    gaussian_blur, BlurError, BlurImage, BlurImageMut, BufferStore, ConvolutionMode, EdgeMode,
+    FastBlurChannels, GaussianBlurParams, GradientKernel, Scalar, ThreadingPolicy,
+};
+use novtb::{ParallelZonedIterator, TbSliceMut};
+
+/// Canny edge detector parameters
+#[derive(Copy, Clone, Debug)]
+pub struct CannyParams {
+    /// Gaussian pre-smoothing, `None` to skip smoothing.
+    pub blur: Option<GaussianBlurParams>,
+    /// Kernel used to compute gradient.
+    pub gradient_kernel: GradientKernel,
+    /// Gradients below this value are never edges.
+    pub low_threshold: f32,
+    /// Gradients above this value are always edges.
+    pub high_threshold: f32,
+    /// Use `sqrt(dx^2 + dy^2)` instead of `|dx| + |dy|` as gradient magnitude.
+    pub l2_gradient: bool,
+}
+
+impl CannyParams {
+    /// Creates parameters with 5x5 Gaussian pre-smoothing, Sobel 3x3 and L1 gradient.
+    pub fn new(low_threshold: f32, high_threshold: f32) -> CannyParams {
+        CannyParams {
+            blur: Some(GaussianBlurParams::new(5, 1.4)),
+            gradient_kernel: GradientKernel::Sobel3,
+            low_threshold,
+            high_threshold,
+            l2_gradient: false,
+        }
+    }
+}
+
+const NO_EDGE: u8 = 0;
+const WEAK_EDGE: u8 = 1;
+const STRONG_EDGE: u8 = 2;
+
+/// Quantizes gradient direction, 0 - horizontal, 1 - main diagonal, 2 - vertical, 3 - anti-diagonal.
+#[inline(always)]
+fn gradient_sector(dx: f32, dy: f32) -> u8 {
+    // tan(22.5) and tan(67.5)
+    const TAN_22_5: f32 = 0.41421357;
+    const TAN_67_5: f32 = 2.4142137;
+    let ax = dx.abs();
+    let ay = dy.abs();
+    if ay <= ax * TAN_22_5 {
+        0
+    } else if ay > ax * TAN_67_5 {
+        2
+    } else if (dx > 0.) == (dy > 0.) {
+        1
+    } else {
+        3
+    }
+}
+
+/// Detects edges using Canny algorithm.
+///
+/// Performs Gaussian smoothing, gradient computation, non-maximum suppression
+/// and hysteresis thresholding. For multichannel images the channel with the strongest
+/// gradient is used at each pixel.
+///
+/// # Arguments
+///
+/// * `src` - Source image, see [BlurImage] for more info.
+/// * `dst` - Destination single channel mask, edges are `255`, everything else is `0`.
+/// * `params` - See [CannyParams] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn canny(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    params: CannyParams,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    if !(params.low_threshold >= 0. && params.high_threshold >= params.low_threshold) {
+        return Err(BlurError::InvalidArguments);
+    }
+    if matches!(dst.data, BufferStore::Owned(_)) {
+        dst.resize(src.width, src.height, FastBlurChannels::Plane);
+    }
+    dst.check_layout(None)?;
+    if dst.width != src.width || dst.height != src.height || dst.channels != FastBlurChannels::Plane
+    {
+        return Err(BlurError::ImagesMustMatch);
+    }
+
+    let (dx, dy) = match params.blur {
+        Some(blur_params) => {
+            let mut blurred = BlurImageMut::default();
+            gaussian_blur(
+                src,
+                &mut blurred,
+                blur_params,
+                EdgeMode::Reflect101,
+                threading_policy,
+                ConvolutionMode::Exact,
+            )?;
+            gradient_xy(
+                &blurred.to_immutable_ref(),
+                params.gradient_kernel,
+                EdgeMode::Reflect101,
+                Scalar::default(),
+                threading_policy,
+            )?
+        }
+        None => gradient_xy(
+            src,
+            params.gradient_kernel,
+            EdgeMode::Reflect101,
+            Scalar::default(),
+            threading_policy,
+        )?,
+    };
+
+    let width = src.width as usize;
+    let height = src.height as usize;
+    let cn = src.channels.channels();
+    let thread_count = threading_policy.thread_count(src.width, src.height);
+    let pool = novtb::ThreadPool::new(thread_count);
+
+    // Magnitude and direction sector of the strongest channel
+    let mut gradient = vec![(0f32, 0u8); width * height];
+    let dx_stride = dx.row_stride() as usize;
+    let dy_stride = dy.row_stride() as usize;
+    let dx_data = dx.data.borrow();
+    let dy_data = dy.data.borrow();
+    gradient
+        .tb_par_chunks_exact_mut(width)
+        .for_each_enumerated(&pool, |y, row| {
+            let dx_row = &dx_data[y * dx_stride..];
+            let dy_row = &dy_data[y * dy_stride..];
+            for ((dst, dx), dy) in row
+                .iter_mut()
+                .zip(dx_row.chunks_exact(cn))
+                .zip(dy_row.chunks_exact(cn))
+            {
+                let mut best = (0f32, 0f32, 0f32);
+                for (&dx, &dy) in dx.iter().zip(dy.iter()) {
+                    let magnitude = if params.l2_gradient {
+                        dx.hypot(dy)
+                    } else {
+                        dx.abs() + dy.abs()
+                    };
+                    if magnitude > best.0 {
+                        best = (magnitude, dx, dy);
+                    }
+                }
+                *dst = (best.0, gradient_sector(best.1, best.2));
+            }
+        });
+
+    // Non-maximum suppression
+    let mut edges = vec![NO_EDGE; width * height];
+    let gradient = gradient.as_slice();
+    let magnitude_at = |x: isize, y: isize| -> f32 {
+        if x < 0 || y < 0 || x >= width as isize || y >= height as isize {
+            0.
+        } else {
+            gradient[y as usize * width + x as usize].0
+        }
+    };
+    edges
+        .tb_par_chunks_exact_mut(width)
+        .for_each_enumerated(&pool, |y, row| {
+            let y = y as isize;
+            for (x, dst) in row.iter_mut().enumerate() {
+                let (magnitude, sector) = gradient[y as usize * width + x];
+                if magnitude <= params.low_threshold {
+                    continue;
+                }
+                let x = x as isize;
+                let (ox, oy) = match sector {
+                    0 => (1, 0),
+                    1 => (1, 1),
+                    2 => (0, 1),
+                    _ => (1, -1),
+                };
+                if magnitude > magnitude_at(x - ox, y - oy)
+                    && magnitude >= magnitude_at(x + ox, y + oy)
+                {
+                    *dst = if magnitude > params.high_threshold {
+                        STRONG_EDGE
+                    } else {
+                        WEAK_EDGE
+                    };
+                }
+            }
+        });
+
+    // Hysteresis, weak edges connected to strong ones are promoted
+    let mut stack = edges
+        .iter()
+        .enumerate()
+        .filter(|x| *x.1 == STRONG_EDGE)
+        .map(|x| x.0)
+        .collect::<Vec<_>>();
+    while let Some(index) = stack.pop() {
+        let x = index % width;
+        let y = index / width;
+        for ny in y.saturating_sub(1)..(y + 2).min(height) {
+            for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                let neighbour = ny * width + nx;
+                if edges[neighbour] == WEAK_EDGE {
+                    edges[neighbour] = STRONG_EDGE;
+                    stack.push(neighbour);
+                }
+            }
+        }
+    }
+
+    let dst_stride = dst.row_stride() as usize;
+    for (dst, src) in dst
+        .data
+        .borrow_mut()
+        .chunks_mut(dst_stride)
+        .zip(edges.chunks_exact(width))
+    {
+        for (dst, &src) in dst[..width].iter_mut().zip(src.iter()) {
+            *dst = if src == STRONG_EDGE { 255 } else { 0 };
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canny_square() {
+        let (width, height) = (32usize, 32usize);
+        let mut src = vec![0u8; width * height];
+        for y in 8..24 {
+            for x in 8..24 {
+                src[y * width + x] = 255;
+            }
+        }
+        let src_image =
+            BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        canny(
+            &src_image,
+            &mut dst,
+            CannyParams::new(50., 150.),
+            ThreadingPolicy::Adaptive,
+        )
+        .unwrap();
+        let dst = dst.data.borrow();
+        assert!(dst.iter().all(|&x| x == 0 || x == 255));
+        // Flat areas have no edges
+        assert_eq!(dst[16 * width + 16], 0);
+        assert_eq!(dst[2 * width + 2], 0);
+        // Every side of the square is detected, one pixel thick
+        for y in 10..22 {
+            let row = &dst[y * width..(y + 1) * width];
+            assert_eq!(row[4..12].iter().filter(|&&x| x == 255).count(), 1);
+            assert_eq!(row[20..28].iter().filter(|&&x| x == 255).count(), 1);
+        }
+    }
+
+    #[test]
+    fn test_canny_thresholds() {
+        let src = vec![0u8; 16 * 16];
+        let src_image = BlurImage::borrow(&src, 16, 16, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        assert!(canny(
+            &src_image,
+            &mut dst,
+            CannyParams::new(100., 50.),
+            ThreadingPolicy::Single,
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,994 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+//! C ABI for the blur entry points.
+//!
+//! Every function takes images through [BlurImageDescriptor], validates it the same way
+//! the Rust API does and reports the outcome as [BlurErrorCode].
+//! Element type is encoded in the function name, `stride` is measured in elements,
+//! not in bytes, exactly as in [BlurImage].
+//! Enum arguments are passed as `u32` holding discriminant of the matching Rust enum,
+//! unknown values are reported as [BlurErrorCode::InvalidArguments].
+//! Threading policy is passed as thread count, where `0` means [ThreadingPolicy::Adaptive].
+//!
+//! Build with `cargo cbuild --features capi` to get a shared/static library and
+//! a `libblur.h` header generated by `cbindgen`.
+use crate::{
+    bilateral_filter, box_blur, box_blur_f32, box_blur_u16, fast_gaussian, fast_gaussian_f32,
+    fast_gaussian_u16, filter_2d, gaussian_blur, gaussian_blur_f32, gaussian_blur_u16, median_blur,
+    median_blur_f32, median_blur_u16, morphology, morphology_f32, morphology_u16, rank_filter,
+    rank_filter_f32, rank_filter_u16, stack_blur, stack_blur_f32, stack_blur_u16,
+    AnisotropicRadius, BilateralBlurParams, BlurError, BlurImage, BlurImageMut, BoxBlurParameters,
+    BufferStore, ConvolutionMode, EdgeMode, FastBlurChannels, GaussianBlurParams,
+    IeeeBinaryConvolutionMode, KernelShape, MorphOp, Scalar, StructuringElement, ThreadingPolicy,
+};
+use std::ffi::c_void;
+use std::fmt::Debug;
+use std::num::NonZeroUsize;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Image passed over C ABI.
+///
+/// `data` must point to at least `stride * height` elements
+/// of the type that the called function expects.
+/// `stride` is count of elements per row, 0 means `width * channels`.
+/// `channels` is 1, 2, 3 or 4, other values are rejected with
+/// [BlurErrorCode::ChannelsNotSupported].
+/// Source and destination passed into the same call must not overlap.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct BlurImageDescriptor {
+    pub data: *mut c_void,
+    pub width: u32,
+    pub height: u32,
+    pub stride: u32,
+    pub channels: u32,
+}
+
+/// Validated [BlurImageDescriptor].
+struct ImageLayout {
+    data: *mut c_void,
+    width: u32,
+    height: u32,
+    stride: u32,
+    channels: FastBlurChannels,
+    length: usize,
+}
+
+/// Result code of every C ABI call, mirrors [BlurError].
+#[repr(i32)]
+#[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
+pub enum BlurErrorCode {
+    Ok = 0,
+    ZeroBaseSize = 1,
+    MinimumSliceSizeMismatch = 2,
+    MinimumStrideSizeMismatch = 3,
+    OddKernel = 4,
+    KernelSizeMismatch = 5,
+    ImagesMustMatch = 6,
+    StrideIsNotSupported = 7,
+    FftChannelsNotSupported = 8,
+    ExceedingPointerSize = 9,
+    NegativeOrZeroSigma = 10,
+    InvalidArguments = 11,
+    /// Null pointer or misaligned pointer was passed.
+    NullPointer = 12,
+    /// Unexpected panic was caught at the ABI boundary.
+    Panic = 13,
+    ChannelsNotSupported = 14,
+}
+
+impl From<BlurError> for BlurErrorCode {
+    fn from(value: BlurError) -> Self {
+        match value {
+            BlurError::ZeroBaseSize => BlurErrorCode::ZeroBaseSize,
+            BlurError::MinimumSliceSizeMismatch(_) => BlurErrorCode::MinimumSliceSizeMismatch,
+            BlurError::MinimumStrideSizeMismatch(_) => BlurErrorCode::MinimumStrideSizeMismatch,
+            BlurError::OddKernel(_) => BlurErrorCode::OddKernel,
+            BlurError::KernelSizeMismatch(_) => BlurErrorCode::KernelSizeMismatch,
+            BlurError::ImagesMustMatch => BlurErrorCode::ImagesMustMatch,
+            BlurError::StrideIsNotSupported => BlurErrorCode::StrideIsNotSupported,
+            BlurError::FftChannelsNotSupported => BlurErrorCode::FftChannelsNotSupported,
+            BlurError::ExceedingPointerSize => BlurErrorCode::ExceedingPointerSize,
+            BlurError::NegativeOrZeroSigma => BlurErrorCode::NegativeOrZeroSigma,
+            BlurError::InvalidArguments => BlurErrorCode::InvalidArguments,
+            BlurError::ChannelsNotSupported(_) => BlurErrorCode::ChannelsNotSupported,
+        }
+    }
+}
+
+impl From<Result<(), BlurError>> for BlurErrorCode {
+    fn from(value: Result<(), BlurError>) -> Self {
+        match value {
+            Ok(_) => BlurErrorCode::Ok,
+            Err(err) => err.into(),
+        }
+    }
+}
+
+/// Computes required slice length and validates pointer.
+unsafe fn descriptor_len<T>(
+    descriptor: *const BlurImageDescriptor,
+) -> Result<ImageLayout, BlurErrorCode> {
+    if descriptor.is_null() {
+        return Err(BlurErrorCode::NullPointer);
+    }
+    let desc = *descriptor;
+    if desc.data.is_null() || (desc.data as usize) % std::mem::align_of::<T>() != 0 {
+        return Err(BlurErrorCode::NullPointer);
+    }
+    if desc.width == 0 || desc.height == 0 {
+        return Err(BlurErrorCode::ZeroBaseSize);
+    }
+    let channels = FastBlurChannels::try_from(desc.channels as usize)
+        .map_err(|_| BlurErrorCode::ChannelsNotSupported)?;
+    let cn = channels.channels();
+    let row_length = (desc.width as usize)
+        .checked_mul(cn)
+        .ok_or(BlurErrorCode::ExceedingPointerSize)?;
+    let stride = if desc.stride == 0 {
+        row_length
+    } else {
+        desc.stride as usize
+    };
+    if stride < row_length {
+        return Err(BlurErrorCode::MinimumStrideSizeMismatch);
+    }
+    let length = stride
+        .checked_mul(desc.height as usize)
+        .ok_or(BlurErrorCode::ExceedingPointerSize)?;
+    Ok(ImageLayout {
+        data: desc.data,
+        width: desc.width,
+        height: desc.height,
+        stride: u32::try_from(stride).map_err(|_| BlurErrorCode::ExceedingPointerSize)?,
+        channels,
+        length,
+    })
+}
+
+unsafe fn make_image<'a, T: Copy + Default + Debug>(
+    descriptor: *const BlurImageDescriptor,
+) -> Result<BlurImage<'a, T>, BlurErrorCode> {
+    let desc = descriptor_len::<T>(descriptor)?;
+    Ok(BlurImage {
+        data: std::borrow::Cow::Borrowed(std::slice::from_raw_parts(
+            desc.data as *const T,
+            desc.length,
+        )),
+        width: desc.width,
+        height: desc.height,
+        stride: desc.stride,
+        channels: desc.channels,
+    })
+}
+
+unsafe fn make_image_mut<'a, T: Copy + Default + Debug>(
+    descriptor: *const BlurImageDescriptor,
+) -> Result<BlurImageMut<'a, T>, BlurErrorCode> {
+    let desc = descriptor_len::<T>(descriptor)?;
+    Ok(BlurImageMut {
+        data: BufferStore::Borrowed(std::slice::from_raw_parts_mut(
+            desc.data as *mut T,
+            desc.length,
+        )),
+        width: desc.width,
+        height: desc.height,
+        stride: desc.stride,
+        channels: desc.channels,
+    })
+}
+
+/// Enums cross C ABI as plain integers, reading out of range value
+/// directly into Rust enum is undefined behaviour.
+fn to_edge_mode(value: u32) -> Result<EdgeMode, BlurError> {
+    match value {
+        0 => Ok(EdgeMode::Clamp),
+        1 => Ok(EdgeMode::Wrap),
+        2 => Ok(EdgeMode::Reflect),
+        3 => Ok(EdgeMode::Reflect101),
+        4 => Ok(EdgeMode::Constant),
+        _ => Err(BlurError::InvalidArguments),
+    }
+}
+
+fn to_convolution_mode(value: u32) -> Result<ConvolutionMode, BlurError> {
+    match value {
+        0 => Ok(ConvolutionMode::Exact),
+        1 => Ok(ConvolutionMode::FixedPoint),
+        _ => Err(BlurError::InvalidArguments),
+    }
+}
+
+fn to_ieee_convolution_mode(value: u32) -> Result<IeeeBinaryConvolutionMode, BlurError> {
+    match value {
+        0 => Ok(IeeeBinaryConvolutionMode::Normal),
+        1 => Ok(IeeeBinaryConvolutionMode::Zealous),
+        _ => Err(BlurError::InvalidArguments),
+    }
+}
+
+fn to_morph_op(value: u32) -> Result<MorphOp, BlurError> {
+    match value {
+        0 => Ok(MorphOp::Erode),
+        1 => Ok(MorphOp::Dilate),
+        2 => Ok(MorphOp::Open),
+        3 => Ok(MorphOp::Close),
+        4 => Ok(MorphOp::Gradient),
+        5 => Ok(MorphOp::TopHat),
+        6 => Ok(MorphOp::BlackHat),
+        _ => Err(BlurError::InvalidArguments),
+    }
+}
+
+/// `0` is [ThreadingPolicy::Adaptive], `1` is [ThreadingPolicy::Single],
+/// any other value is [ThreadingPolicy::Fixed] thread count.
+fn to_threading_policy(threads: u32) -> ThreadingPolicy {
+    match NonZeroUsize::new(threads as usize) {
+        None => ThreadingPolicy::Adaptive,
+        Some(threads) if threads.get() == 1 => ThreadingPolicy::Single,
+        Some(threads) => ThreadingPolicy::Fixed(threads),
+    }
+}
+
+/// Runs the call and converts any outcome, including panic, into [BlurErrorCode].
+fn guarded<F: FnOnce() -> Result<(), BlurErrorCode>>(executor: F) -> BlurErrorCode {
+    match catch_unwind(AssertUnwindSafe(executor)) {
+        Ok(Ok(_)) => BlurErrorCode::Ok,
+        Ok(Err(code)) => code,
+        Err(_) => BlurErrorCode::Panic,
+    }
+}
+
+macro_rules! out_of_place {
+    ($src: expr, $dst: expr, $store: ty, $call: expr) => {{
+        guarded(|| {
+            let src = make_image::<$store>($src)?;
+            let mut dst = make_image_mut::<$store>($dst)?;
+            match $call(&src, &mut dst).into() {
+                BlurErrorCode::Ok => Ok(()),
+                code => Err(code),
+            }
+        })
+    }};
+}
+
+macro_rules! in_place {
+    ($image: expr, $store: ty, $call: expr) => {{
+        guarded(|| {
+            let mut image = make_image_mut::<$store>($image)?;
+            match $call(&mut image).into() {
+                BlurErrorCode::Ok => Ok(()),
+                code => Err(code),
+            }
+        })
+    }};
+}
+
+/// Performs gaussian blur on u8 image, see [gaussian_blur] for more info.
+///
+/// # Safety
+/// All descriptors must satisfy [BlurImageDescriptor] contract.
+#[no_mangle]
+pub unsafe extern "C" fn libblur_gaussian_blur_u8(
+    src: *const BlurImageDescriptor,
+    dst: *const BlurImageDescriptor,
+    params: GaussianBlurParams,
+    edge_mode: u32,
+    threading_policy: u32,
+    hint: u32,
+) -> BlurErrorCode {
+    out_of_place!(
+        src,
+        dst,
+        u8,
+        |s: &BlurImage<u8>, d: &mut BlurImageMut<u8>| {
+            gaussian_blur(
+                s,
+                d,
+                params,
+                to_edge_mode(edge_mode)?,
+                to_threading_policy(threading_policy),
+                to_convolution_mode(hint)?,
+            )
+        }
+    )
+}
+
+/// Performs gaussian blur on u16 image, see [gaussian_blur_u16] for more info.
+///
+/// # Safety
+/// All descriptors must satisfy [BlurImageDescriptor] contract.
+#[no_mangle]
+pub unsafe extern "C" fn libblur_gaussian_blur_u16(
+    src: *const BlurImageDescriptor,
+    dst: *const BlurImageDescriptor,
+    params: GaussianBlurParams,
+    edge_mode: u32,
+    threading_policy: u32,
+    hint: u32,
+) -> BlurErrorCode {
+    out_of_place!(
+        src,
+        dst,
+        u16,
+        |s: &BlurImage<u16>, d: &mut BlurImageMut<u16>| {
+            gaussian_blur_u16(
+                s,
+                d,
+                params,
+                to_edge_mode(edge_mode)?,
+                to_threading_policy(threading_policy),
+                to_convolution_mode(hint)?,
+            )
+        }
+    )
+}
+
+/// Performs gaussian blur on f32 image, see [gaussian_blur_f32] for more info.
+///
+/// # Safety
+/// All descriptors must satisfy [BlurImageDescriptor] contract.
+#[no_mangle]
+pub unsafe extern "C" fn libblur_gaussian_blur_f32(
+    src: *const BlurImageDescriptor,
+    dst: *const BlurImageDescriptor,
+    params: GaussianBlurParams,
+    edge_mode: u32,
+    threading_policy: u32,
+    convolution_mode: u32,
+) -> BlurErrorCode {
+    out_of_place!(
+        src,
+        dst,
+        f32,
+        |s: &BlurImage<f32>, d: &mut BlurImageMut<f32>| {
+            gaussian_blur_f32(
+                s,
+                d,
+                params,
+                to_edge_mode(edge_mode)?,
+                to_threading_policy(threading_policy),
+                to_ieee_convolution_mode(convolution_mode)?,
+            )
+        }
+    )
+}
+
+/// Performs in-place stack blur on u8 image, see [stack_blur] for more info.
+///
+/// # Safety
+/// All descriptors must satisfy [BlurImageDescriptor] contract.
+#[no_mangle]
+pub unsafe extern "C" fn libblur_stack_blur_u8(
+    image: *const BlurImageDescriptor,
+    radius: AnisotropicRadius,
+    threading_policy: u32,
+) -> BlurErrorCode {
+    in_place!(image, u8, |i: &mut BlurImageMut<u8>| stack_blur(
+        i,
+        radius,
+        to_threading_policy(threading_policy)
+    ))
+}
+
+/// Performs in-place stack blur on u16 image, see [stack_blur_u16] for more info.
+///
+/// # Safety
+/// All descriptors must satisfy [BlurImageDescriptor] contract.
+#[no_mangle]
+pub unsafe extern "C" fn libblur_stack_blur_u16(
+    image: *const BlurImageDescriptor,
+    radius: AnisotropicRadius,
+    threading_policy: u32,
+) -> BlurErrorCode {
+    in_place!(image, u16, |i: &mut BlurImageMut<u16>| stack_blur_u16(
+        i,
+        radius,
+        to_threading_policy(threading_policy)
+    ))
+}
+
+/// Performs in-place stack blur on f32 image, see [stack_blur_f32] for more info.
+///
+/// # Safety
+/// All descriptors must satisfy [BlurImageDescriptor] contract.
+#[no_mangle]
+pub unsafe extern "C" fn libblur_stack_blur_f32(
+    image: *const BlurImageDescriptor,
+    radius: AnisotropicRadius,
+    threading_policy: u32,
+) -> BlurErrorCode {
+    in_place!(image, f32, |i: &mut BlurImageMut<f32>| stack_blur_f32(
+        i,
+        radius,
+        to_threading_policy(threading_policy)
+    ))
+}
+
+/// Performs in-place fast gaussian on u8 image, see [fast_gaussian] for more info.
+///
+/// # Safety
+/// All descriptors must satisfy [BlurImageDescriptor] contract.
+#[no_mangle]
+pub unsafe extern "C" fn libblur_fast_gaussian_u8(
+    image: *const BlurImageDescriptor,
+    radius: AnisotropicRadius,
+    threading_policy: u32,
+    edge_mode: u32,
+) -> BlurErrorCode {
+    in_place!(image, u8, |i: &mut BlurImageMut<u8>| fast_gaussian(
+        i,
+        radius,
+        to_threading_policy(threading_policy),
+        to_edge_mode(edge_mode)?
+    ))
+}
+
+/// Performs in-place fast gaussian on u16 image, see [fast_gaussian_u16] for more info.
+///
+/// # Safety
+/// All descriptors must satisfy [BlurImageDescriptor] contract.
+#[no_mangle]
+pub unsafe extern "C" fn libblur_fast_gaussian_u16(
+    image: *const BlurImageDescriptor,
+    radius: AnisotropicRadius,
+    threading_policy: u32,
+    edge_mode: u32,
+) -> BlurErrorCode {
+    in_place!(image, u16, |i: &mut BlurImageMut<u16>| fast_gaussian_u16(
+        i,
+        radius,
+        to_threading_policy(threading_policy),
+        to_edge_mode(edge_mode)?
+    ))
+}
+
+/// Performs in-place fast gaussian on f32 image, see [fast_gaussian_f32] for more info.
+///
+/// # Safety
+/// All descriptors must satisfy [BlurImageDescriptor] contract.
+#[no_mangle]
+pub unsafe extern "C" fn libblur_fast_gaussian_f32(
+    image: *const BlurImageDescriptor,
+    radius: AnisotropicRadius,
+    threading_policy: u32,
+    edge_mode: u32,
+) -> BlurErrorCode {
+    in_place!(image, f32, |i: &mut BlurImageMut<f32>| fast_gaussian_f32(
+        i,
+        radius,
+        to_threading_policy(threading_policy),
+        to_edge_mode(edge_mode)?
+    ))
+}
+
+/// Performs box blur on u8 image, see [box_blur] for more info.
+///
+/// # Safety
+/// All descriptors must satisfy [BlurImageDescriptor] contract.
+#[no_mangle]
+pub unsafe extern "C" fn libblur_box_blur_u8(
+    src: *const BlurImageDescriptor,
+    dst: *const BlurImageDescriptor,
+    parameters: BoxBlurParameters,
+    threading_policy: u32,
+) -> BlurErrorCode {
+    out_of_place!(
+        src,
+        dst,
+        u8,
+        |s: &BlurImage<u8>, d: &mut BlurImageMut<u8>| {
+            box_blur(s, d, parameters, to_threading_policy(threading_policy))
+        }
+    )
+}
+
+/// Performs box blur on u16 image, see [box_blur_u16] for more info.
+///
+/// # Safety
+/// All descriptors must satisfy [BlurImageDescriptor] contract.
+#[no_mangle]
+pub unsafe extern "C" fn libblur_box_blur_u16(
+    src: *const BlurImageDescriptor,
+    dst: *const BlurImageDescriptor,
+    parameters: BoxBlurParameters,
+    threading_policy: u32,
+) -> BlurErrorCode {
+    out_of_place!(
+        src,
+        dst,
+        u16,
+        |s: &BlurImage<u16>, d: &mut BlurImageMut<u16>| {
+            box_blur_u16(s, d, parameters, to_threading_policy(threading_policy))
+        }
+    )
+}
+
+/// Performs box blur on f32 image, see [box_blur_f32] for more info.
+///
+/// # Safety
+/// All descriptors must satisfy [BlurImageDescriptor] contract.
+#[no_mangle]
+pub unsafe extern "C" fn libblur_box_blur_f32(
+    src: *const BlurImageDescriptor,
+    dst: *const BlurImageDescriptor,
+    parameters: BoxBlurParameters,
+    threading_policy: u32,
+) -> BlurErrorCode {
+    out_of_place!(
+        src,
+        dst,
+        f32,
+        |s: &BlurImage<f32>, d: &mut BlurImageMut<f32>| {
+            box_blur_f32(s, d, parameters, to_threading_policy(threading_policy))
+        }
+    )
+}
+
+/// Performs median blur on u8 image, see [median_blur] for more info.
+///
+/// # Safety
+/// All descriptors must satisfy [BlurImageDescriptor] contract.
+#[no_mangle]
+pub unsafe extern "C" fn libblur_median_blur_u8(
+    src: *const BlurImageDescriptor,
+    dst: *const BlurImageDescriptor,
+    radius: u32,
+    edge_mode: u32,
+    border_constant: Scalar,
+    threading_policy: u32,
+) -> BlurErrorCode {
+    out_of_place!(
+        src,
+        dst,
+        u8,
+        |s: &BlurImage<u8>, d: &mut BlurImageMut<u8>| {
+            median_blur(
+                s,
+                d,
+                radius,
+                to_edge_mode(edge_mode)?,
+                border_constant,
+                to_threading_policy(threading_policy),
+            )
+        }
+    )
+}
+
+/// Performs median blur on u16 image, see [median_blur_u16] for more info.
+///
+/// # Safety
+/// All descriptors must satisfy [BlurImageDescriptor] contract.
+#[no_mangle]
+pub unsafe extern "C" fn libblur_median_blur_u16(
+    src: *const BlurImageDescriptor,
+    dst: *const BlurImageDescriptor,
+    radius: u32,
+    edge_mode: u32,
+    border_constant: Scalar,
+    threading_policy: u32,
+) -> BlurErrorCode {
+    out_of_place!(
+        src,
+        dst,
+        u16,
+        |s: &BlurImage<u16>, d: &mut BlurImageMut<u16>| {
+            median_blur_u16(
+                s,
+                d,
+                radius,
+                to_edge_mode(edge_mode)?,
+                border_constant,
+                to_threading_policy(threading_policy),
+            )
+        }
+    )
+}
+
+/// Performs median blur on f32 image, see [median_blur_f32] for more info.
+///
+/// # Safety
+/// All descriptors must satisfy [BlurImageDescriptor] contract.
+#[no_mangle]
+pub unsafe extern "C" fn libblur_median_blur_f32(
+    src: *const BlurImageDescriptor,
+    dst: *const BlurImageDescriptor,
+    radius: u32,
+    edge_mode: u32,
+    border_constant: Scalar,
+    threading_policy: u32,
+) -> BlurErrorCode {
+    out_of_place!(
+        src,
+        dst,
+        f32,
+        |s: &BlurImage<f32>, d: &mut BlurImageMut<f32>| {
+            median_blur_f32(
+                s,
+                d,
+                radius,
+                to_edge_mode(edge_mode)?,
+                border_constant,
+                to_threading_policy(threading_policy),
+            )
+        }
+    )
+}
+
+/// Performs rank filter on u8 image, see [rank_filter] for more info.
+///
+/// # Safety
+/// All descriptors must satisfy [BlurImageDescriptor] contract.
+#[no_mangle]
+pub unsafe extern "C" fn libblur_rank_filter_u8(
+    src: *const BlurImageDescriptor,
+    dst: *const BlurImageDescriptor,
+    radius: u32,
+    percentile: f32,
+    edge_mode: u32,
+    border_constant: Scalar,
+    threading_policy: u32,
+) -> BlurErrorCode {
+    out_of_place!(
+        src,
+        dst,
+        u8,
+        |s: &BlurImage<u8>, d: &mut BlurImageMut<u8>| {
+            rank_filter(
+                s,
+                d,
+                radius,
+                percentile,
+                to_edge_mode(edge_mode)?,
+                border_constant,
+                to_threading_policy(threading_policy),
+            )
+        }
+    )
+}
+
+/// Performs rank filter on u16 image, see [rank_filter_u16] for more info.
+///
+/// # Safety
+/// All descriptors must satisfy [BlurImageDescriptor] contract.
+#[no_mangle]
+pub unsafe extern "C" fn libblur_rank_filter_u16(
+    src: *const BlurImageDescriptor,
+    dst: *const BlurImageDescriptor,
+    radius: u32,
+    percentile: f32,
+    edge_mode: u32,
+    border_constant: Scalar,
+    threading_policy: u32,
+) -> BlurErrorCode {
+    out_of_place!(
+        src,
+        dst,
+        u16,
+        |s: &BlurImage<u16>, d: &mut BlurImageMut<u16>| {
+            rank_filter_u16(
+                s,
+                d,
+                radius,
+                percentile,
+                to_edge_mode(edge_mode)?,
+                border_constant,
+                to_threading_policy(threading_policy),
+            )
+        }
+    )
+}
+
+/// Performs rank filter on f32 image, see [rank_filter_f32] for more info.
+///
+/// # Safety
+/// All descriptors must satisfy [BlurImageDescriptor] contract.
+#[no_mangle]
+pub unsafe extern "C" fn libblur_rank_filter_f32(
+    src: *const BlurImageDescriptor,
+    dst: *const BlurImageDescriptor,
+    radius: u32,
+    percentile: f32,
+    edge_mode: u32,
+    border_constant: Scalar,
+    threading_policy: u32,
+) -> BlurErrorCode {
+    out_of_place!(
+        src,
+        dst,
+        f32,
+        |s: &BlurImage<f32>, d: &mut BlurImageMut<f32>| {
+            rank_filter_f32(
+                s,
+                d,
+                radius,
+                percentile,
+                to_edge_mode(edge_mode)?,
+                border_constant,
+                to_threading_policy(threading_policy),
+            )
+        }
+    )
+}
+
+/// Performs bilateral filter on u8 image, see [bilateral_filter] for more info.
+///
+/// # Safety
+/// All descriptors must satisfy [BlurImageDescriptor] contract.
+#[no_mangle]
+pub unsafe extern "C" fn libblur_bilateral_filter_u8(
+    src: *const BlurImageDescriptor,
+    dst: *const BlurImageDescriptor,
+    params: BilateralBlurParams,
+    edge_mode: u32,
+    border_constant: Scalar,
+    threading_policy: u32,
+) -> BlurErrorCode {
+    out_of_place!(
+        src,
+        dst,
+        u8,
+        |s: &BlurImage<u8>, d: &mut BlurImageMut<u8>| {
+            bilateral_filter(
+                s,
+                d,
+                params,
+                to_edge_mode(edge_mode)?,
+                border_constant,
+                to_threading_policy(threading_policy),
+            )
+        }
+    )
+}
+
+unsafe fn make_kernel<'a>(
+    kernel: *const f32,
+    kernel_width: usize,
+    kernel_height: usize,
+) -> Result<&'a [f32], BlurErrorCode> {
+    if kernel.is_null() {
+        return Err(BlurErrorCode::NullPointer);
+    }
+    let length = kernel_width
+        .checked_mul(kernel_height)
+        .ok_or(BlurErrorCode::ExceedingPointerSize)?;
+    Ok(std::slice::from_raw_parts(kernel, length))
+}
+
+macro_rules! define_filter_2d {
+    ($fn_name: ident, $store: ty) => {
+        /// Performs direct 2D convolution with `f32` kernel laid out row by row,
+        /// see [filter_2d] for more info.
+        ///
+        /// # Safety
+        /// All descriptors must satisfy [BlurImageDescriptor] contract.
+        #[no_mangle]
+        pub unsafe extern "C" fn $fn_name(
+            src: *const BlurImageDescriptor,
+            dst: *const BlurImageDescriptor,
+            kernel: *const f32,
+            kernel_width: usize,
+            kernel_height: usize,
+            edge_mode: u32,
+            border_constant: Scalar,
+            threading_policy: u32,
+        ) -> BlurErrorCode {
+            guarded(|| {
+                let kernel = make_kernel(kernel, kernel_width, kernel_height)?;
+                let src = make_image::<$store>(src)?;
+                let mut dst = make_image_mut::<$store>(dst)?;
+                match filter_2d::<$store, f32>(
+                    &src,
+                    &mut dst,
+                    kernel,
+                    KernelShape::new(kernel_width, kernel_height),
+                    to_edge_mode(edge_mode)?,
+                    border_constant,
+                    to_threading_policy(threading_policy),
+                )
+                .into()
+                {
+                    BlurErrorCode::Ok => Ok(()),
+                    code => Err(code),
+                }
+            })
+        }
+    };
+}
+
+define_filter_2d!(libblur_filter_2d_u8, u8);
+define_filter_2d!(libblur_filter_2d_u16, u16);
+define_filter_2d!(libblur_filter_2d_f32, f32);
+
+macro_rules! define_morphology {
+    ($fn_name: ident, $store: ty, $method: ident) => {
+        /// Performs morphological operation with structuring element mask laid out row by row,
+        #[doc = concat!("see [", stringify!($method), "] for more info.")]
+        ///
+        /// # Safety
+        /// All descriptors must satisfy [BlurImageDescriptor] contract,
+        /// `mask` must be valid for `mask_width * mask_height` reads.
+        #[no_mangle]
+        pub unsafe extern "C" fn $fn_name(
+            src: *const BlurImageDescriptor,
+            dst: *const BlurImageDescriptor,
+            op: u32,
+            mask: *const u8,
+            mask_width: usize,
+            mask_height: usize,
+            edge_mode: u32,
+            border_constant: Scalar,
+            threading_policy: u32,
+        ) -> BlurErrorCode {
+            guarded(|| {
+                if mask.is_null() {
+                    return Err(BlurErrorCode::NullPointer);
+                }
+                let length = mask_width
+                    .checked_mul(mask_height)
+                    .ok_or(BlurErrorCode::ExceedingPointerSize)?;
+                let structuring_element = StructuringElement::new(
+                    std::slice::from_raw_parts(mask, length).to_vec(),
+                    KernelShape::new(mask_width, mask_height),
+                );
+                let src = make_image::<$store>(src)?;
+                let mut dst = make_image_mut::<$store>(dst)?;
+                match $method(
+                    &src,
+                    &mut dst,
+                    to_morph_op(op)?,
+                    &structuring_element,
+                    to_edge_mode(edge_mode)?,
+                    border_constant,
+                    to_threading_policy(threading_policy),
+                )
+                .into()
+                {
+                    BlurErrorCode::Ok => Ok(()),
+                    code => Err(code),
+                }
+            })
+        }
+    };
+}
+
+define_morphology!(libblur_morphology_u8, u8, morphology);
+define_morphology!(libblur_morphology_u16, u16, morphology_u16);
+define_morphology!(libblur_morphology_f32, f32, morphology_f32);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capi_gaussian_strided() {
+        let width: u32 = 64;
+        let height: u32 = 64;
+        let stride = width * 3 + 5;
+        let mut src = vec![126u8; (stride * height) as usize];
+        let mut dst = vec![0u8; (stride * height) as usize];
+        let src_desc = BlurImageDescriptor {
+            data: src.as_mut_ptr() as *mut c_void,
+            width,
+            height,
+            stride,
+            channels: 3,
+        };
+        let dst_desc = BlurImageDescriptor {
+            data: dst.as_mut_ptr() as *mut c_void,
+            ..src_desc
+        };
+        let code = unsafe {
+            libblur_gaussian_blur_u8(
+                &src_desc,
+                &dst_desc,
+                GaussianBlurParams::new_from_kernel(5.),
+                EdgeMode::Clamp as u32,
+                1,
+                ConvolutionMode::Exact as u32,
+            )
+        };
+        assert_eq!(code, BlurErrorCode::Ok);
+        for row in dst.chunks_exact(stride as usize) {
+            for &v in row[..(width * 3) as usize].iter() {
+                assert!((v as i32 - 126).abs() <= 3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_capi_reports_errors() {
+        let mut src = vec![0f32; 16];
+        let desc = BlurImageDescriptor {
+            data: src.as_mut_ptr() as *mut c_void,
+            width: 4,
+            height: 4,
+            stride: 0,
+            channels: 1,
+        };
+        let code =
+            unsafe { libblur_box_blur_f32(&desc, std::ptr::null(), BoxBlurParameters::new(3), 1) };
+        assert_eq!(code, BlurErrorCode::NullPointer);
+        let mut dst = vec![0f32; 16];
+        let dst_desc = BlurImageDescriptor {
+            data: dst.as_mut_ptr() as *mut c_void,
+            ..desc
+        };
+        let code = unsafe { libblur_box_blur_f32(&desc, &dst_desc, BoxBlurParameters::new(4), 1) };
+        assert_eq!(code, BlurErrorCode::OddKernel);
+        let wide_desc = BlurImageDescriptor {
+            width: u32::MAX,
+            height: 1,
+            channels: 4,
+            ..desc
+        };
+        let code =
+            unsafe { libblur_box_blur_f32(&wide_desc, &wide_desc, BoxBlurParameters::new(3), 1) };
+        assert_eq!(code, BlurErrorCode::ExceedingPointerSize);
+    }
+
+    #[test]
+    fn test_capi_rejects_invalid_enums() {
+        let mut src = vec![0u8; 16];
+        let mut dst = vec![0u8; 16];
+        let src_desc = BlurImageDescriptor {
+            data: src.as_mut_ptr() as *mut c_void,
+            width: 4,
+            height: 4,
+            stride: 0,
+            channels: 5,
+        };
+        let mut dst_desc = BlurImageDescriptor {
+            data: dst.as_mut_ptr() as *mut c_void,
+            ..src_desc
+        };
+        let call = |src_desc: &BlurImageDescriptor,
+                    dst_desc: &BlurImageDescriptor,
+                    edge_mode: u32| unsafe {
+            libblur_median_blur_u8(src_desc, dst_desc, 1, edge_mode, Scalar::default(), 1)
+        };
+        assert_eq!(
+            call(&src_desc, &dst_desc, 0),
+            BlurErrorCode::ChannelsNotSupported
+        );
+        let src_desc = BlurImageDescriptor {
+            channels: 1,
+            ..src_desc
+        };
+        dst_desc.channels = 1;
+        assert_eq!(
+            call(&src_desc, &dst_desc, 5),
+            BlurErrorCode::InvalidArguments
+        );
+        assert_eq!(
+            call(&src_desc, &dst_desc, EdgeMode::Reflect101 as u32),
+            BlurErrorCode::Ok
+        );
+    }
+}
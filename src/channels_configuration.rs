@@ -25,6 +25,8 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::BlurError;
+
 #[repr(C)]
 #[derive(Clone, Copy, Ord, PartialOrd, Eq, PartialEq)]
 /// Declares channels count
@@ -37,6 +39,8 @@
 pub enum FastBlurChannels {
     /// Single plane image
     Plane = 1,
+    /// Luma with alpha, two components vector fields etc
+    Channels2 = 2,
     /// RGB, BGR etc
     Channels3 = 3,
     /// RGBA, BGRA etc
@@ -48,21 +52,39 @@ impl FastBlurChannels {
     pub const fn channels(&self) -> usize {
         match self {
             FastBlurChannels::Plane => 1,
+            FastBlurChannels::Channels2 => 2,
             FastBlurChannels::Channels3 => 3,
             FastBlurChannels::Channels4 => 4,
         }
     }
 }
 
-impl From<usize> for FastBlurChannels {
-    fn from(value: usize) -> Self {
+impl TryFrom<usize> for FastBlurChannels {
+    type Error = BlurError;
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
         match value {
-            1 => FastBlurChannels::Plane,
-            3 => FastBlurChannels::Channels3,
-            4 => FastBlurChannels::Channels4,
-            _ => {
-                panic!("Unknown value");
-            }
+            1 => Ok(FastBlurChannels::Plane),
+            2 => Ok(FastBlurChannels::Channels2),
+            3 => Ok(FastBlurChannels::Channels3),
+            4 => Ok(FastBlurChannels::Channels4),
+            _ => Err(BlurError::ChannelsNotSupported(value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channels_try_from() {
+        for cn in 1..=4usize {
+            assert_eq!(FastBlurChannels::try_from(cn).unwrap().channels(), cn);
         }
+        assert!(matches!(
+            FastBlurChannels::try_from(6),
+            Err(BlurError::ChannelsNotSupported(6))
+        ));
     }
 }
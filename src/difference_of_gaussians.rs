@@ -0,0 +1,331 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::filter1d::{
+    is_symmetric_1d, make_arena, scan_se_1d, Arena, ArenaPads, Filter1DColumnHandler,
+    Filter1DRowHandler, FilterRegion, ScanPoint1d,
+};
+use crate::gaussian::kernel_size;
+use crate::gradient::{prepare_destination, to_f32_image};
+use crate::{
+    gaussian_kernel_1d, BlurError, BlurImage, BlurImageMut, EdgeMode, FastBlurChannels,
+    GradientStorage, ImageSize, KernelShape, Scalar, ThreadingPolicy,
+};
+use novtb::{ParallelZonedIterator, TbSliceMut};
+use num_traits::AsPrimitive;
+use std::fmt::Debug;
+
+/// Gaussian kernel prepared for the row and column handlers of [crate::filter_1d_exact]
+struct ScannedGaussian {
+    kernel: Vec<ScanPoint1d<f32>>,
+    symmetric: bool,
+    /// Distance from the shared arena pad to the own kernel pad
+    skip: usize,
+}
+
+impl ScannedGaussian {
+    fn new(sigma: f32, size: usize) -> ScannedGaussian {
+        let own_size = (kernel_size(sigma) as usize).min(size);
+        let kernel = gaussian_kernel_1d(own_size as u32, sigma);
+        ScannedGaussian {
+            kernel: scan_se_1d(&kernel),
+            symmetric: is_symmetric_1d(&kernel),
+            skip: (size - own_size) / 2,
+        }
+    }
+}
+
+/// Convolves every arena row, result has image width and arena height
+fn horizontal_pass<const N: usize>(
+    arena_source: &[f32],
+    arena: Arena,
+    image_size: ImageSize,
+    gaussian: &ScannedGaussian,
+    pool: &novtb::ThreadPool,
+) -> Vec<f32> {
+    let arena_stride = arena.width * N;
+    let row_length = image_size.width * N;
+    let row_handler = f32::get_row_handler::<N>(gaussian.symmetric);
+    let row_arena = Arena::new(arena.width, 1, arena.pad_w - gaussian.skip, 0, N);
+    let mut dst = vec![0f32; row_length * arena.height];
+    dst.tb_par_chunks_exact_mut(row_length)
+        .for_each_enumerated(pool, |y, row| {
+            let src = &arena_source[y * arena_stride + gaussian.skip * N..(y + 1) * arena_stride];
+            row_handler(
+                row_arena,
+                src,
+                row,
+                image_size,
+                FilterRegion::new(y, y + 1),
+                &gaussian.kernel,
+            );
+        });
+    dst
+}
+
+/// Convolves single output row out of horizontally filtered arena rows
+fn vertical_pass<const N: usize>(
+    filtered: &[f32],
+    y: usize,
+    image_size: ImageSize,
+    gaussian: &ScannedGaussian,
+    dst: &mut [f32],
+) {
+    let row_length = image_size.width * N;
+    let pad_h = gaussian.kernel.len() / 2;
+    let column_handler = f32::get_column_handler(gaussian.symmetric);
+    let rows = (0..gaussian.kernel.len())
+        .map(|k| {
+            let offset = (y + gaussian.skip + k) * row_length;
+            &filtered[offset..offset + row_length]
+        })
+        .collect::<Vec<_>>();
+    column_handler(
+        Arena::new(image_size.width, pad_h, 0, pad_h, N),
+        &rows,
+        dst,
+        image_size,
+        FilterRegion::new(y, y + 1),
+        &gaussian.kernel,
+    );
+}
+
+fn difference_of_gaussians_impl<D: GradientStorage, const N: usize>(
+    working: &BlurImage<f32>,
+    dst: &mut BlurImageMut<D>,
+    sigma1: f32,
+    sigma2: f32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    let size = kernel_size(sigma1).max(kernel_size(sigma2)) as usize;
+    let first_gaussian = ScannedGaussian::new(sigma1, size);
+    let second_gaussian = ScannedGaussian::new(sigma2, size);
+
+    let image_size = working.size();
+    let pads = ArenaPads::from_kernel_shape(KernelShape::new(size, size));
+    let (arena_source, arena) = make_arena::<f32, N>(
+        working.data.as_ref(),
+        working.row_stride() as usize,
+        image_size,
+        pads,
+        edge_mode,
+        border_constant,
+    )?;
+
+    let thread_count = threading_policy.thread_count(working.width, working.height);
+    let pool = novtb::ThreadPool::new(thread_count);
+
+    let first = horizontal_pass::<N>(&arena_source, arena, image_size, &first_gaussian, &pool);
+    let second = horizontal_pass::<N>(&arena_source, arena, image_size, &second_gaussian, &pool);
+
+    let row_length = image_size.width * N;
+    let dst_stride = dst.row_stride() as usize;
+    dst.data
+        .borrow_mut()
+        .tb_par_chunks_mut(dst_stride)
+        .for_each_enumerated(&pool, |y, row| {
+            let mut first_row = vec![0f32; row_length];
+            let mut second_row = vec![0f32; row_length];
+            vertical_pass::<N>(&first, y, image_size, &first_gaussian, &mut first_row);
+            vertical_pass::<N>(&second, y, image_size, &second_gaussian, &mut second_row);
+            for ((dst, &v1), &v2) in row[..row_length]
+                .iter_mut()
+                .zip(first_row.iter())
+                .zip(second_row.iter())
+            {
+                *dst = D::from_f32(v1 - v2 + D::ZERO_OFFSET);
+            }
+        });
+    Ok(())
+}
+
+/// Computes difference of Gaussians `G(sigma1) * src - G(sigma2) * src`.
+///
+/// Both blurs share single padded source, with `sigma1 < sigma2` this is band-pass filter.
+/// Signed difference is stored as is into `f32` and `i16`, unsigned storages are
+/// offset-encoded, zero is stored as `128` for `u8` and `32768` for `u16`.
+///
+/// # Arguments
+///
+/// * `src` - Source image, `u8`, `u16` or `f32`, see [BlurImage] for more info.
+/// * `dst` - Destination image, see [BlurImageMut] for more info.
+/// * `sigma1` - Sigma of the first Gaussian, must be > 0.
+/// * `sigma2` - Sigma of the subtracted Gaussian, must be > 0.
+/// * `edge_mode` - Border handling mode see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn difference_of_gaussians<T, D>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<D>,
+    sigma1: f32,
+    sigma2: f32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + AsPrimitive<f32>,
+    D: GradientStorage,
+{
+    if !(sigma1 > 0. && sigma2 > 0.) {
+        return Err(BlurError::NegativeOrZeroSigma);
+    }
+    prepare_destination(src, dst)?;
+
+    let working = to_f32_image(src);
+    let executor = match src.channels {
+        FastBlurChannels::Plane => difference_of_gaussians_impl::<D, 1>,
+        FastBlurChannels::Channels2 => difference_of_gaussians_impl::<D, 2>,
+        FastBlurChannels::Channels3 => difference_of_gaussians_impl::<D, 3>,
+        FastBlurChannels::Channels4 => difference_of_gaussians_impl::<D, 4>,
+    };
+    executor(
+        &working.to_immutable_ref(),
+        dst,
+        sigma1,
+        sigma2,
+        edge_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_difference_of_gaussians() {
+        let (width, height) = (24usize, 24usize);
+        let mut src = vec![100u8; width * height * 3];
+        src[(12 * width + 12) * 3 + 1] = 255;
+        let image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let mut dst = BlurImageMut::<f32>::default();
+        difference_of_gaussians(
+            &image,
+            &mut dst,
+            1.,
+            3.,
+            EdgeMode::Reflect101,
+            Scalar::default(),
+            ThreadingPolicy::Adaptive,
+        )
+        .unwrap();
+        let dst_data = dst.data.borrow();
+        // Flat areas cancel out
+        assert!(dst_data[(2 * width + 2) * 3 + 1].abs() < 1e-3);
+        assert!(dst_data[(12 * width + 12) * 3].abs() < 1e-3);
+        // Peak is kept positive and surrounded by negative ring
+        assert!(dst_data[(12 * width + 12) * 3 + 1] > 10.);
+        assert!(dst_data[(12 * width + 16) * 3 + 1] < 0.);
+
+        let mut encoded = BlurImageMut::<u8>::default();
+        difference_of_gaussians(
+            &image,
+            &mut encoded,
+            1.,
+            3.,
+            EdgeMode::Reflect101,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        assert_eq!(encoded.data.borrow()[(2 * width + 2) * 3 + 1], 128);
+        assert!(difference_of_gaussians(
+            &image,
+            &mut encoded,
+            0.,
+            3.,
+            EdgeMode::Reflect101,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_difference_of_gaussians_matches_separate_blurs() {
+        let (width, height) = (40usize, 24usize);
+        let mut src: Vec<f32> = (0..width * height * 2)
+            .map(|i| ((i / 2 % width) * 3 + i % 2 * 50) as f32)
+            .collect();
+        src[(12 * width + 20) * 2] = 255.;
+        src[(12 * width + 20) * 2 + 1] = 255.;
+        let image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels2,
+        );
+        let blur = |sigma: f32| {
+            let kernel = gaussian_kernel_1d(kernel_size(sigma), sigma);
+            let mut dst = BlurImageMut::alloc(width as u32, height as u32, image.channels);
+            crate::filter_1d_exact::<f32, f32, 2>(
+                &image,
+                &mut dst,
+                &kernel,
+                &kernel,
+                EdgeMode::Reflect101,
+                Scalar::default(),
+                ThreadingPolicy::Single,
+            )
+            .unwrap();
+            dst
+        };
+        let (first, second) = (blur(0.8), blur(2.5));
+        let mut dst = BlurImageMut::<f32>::default();
+        difference_of_gaussians(
+            &image,
+            &mut dst,
+            0.8,
+            2.5,
+            EdgeMode::Reflect101,
+            Scalar::default(),
+            ThreadingPolicy::Adaptive,
+        )
+        .unwrap();
+        let (dst, first, second) = (dst.data.borrow(), first.data.borrow(), second.data.borrow());
+        // Compare where the wider kernel does not reach the border
+        let radius = kernel_size(2.5) as usize / 2;
+        for y in radius..height - radius {
+            for x in radius * 2..(width - radius) * 2 {
+                let i = y * width * 2 + x;
+                let expected = first[i] - second[i];
+                assert!((dst[i] - expected).abs() < 1e-3, "{} != {expected}", dst[i]);
+            }
+        }
+        assert!(dst[(12 * width + 20) * 2] > 20.);
+    }
+}
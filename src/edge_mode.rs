@@ -27,6 +27,7 @@
 
 use std::ops::Index;
 
+#[repr(C)]
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default)]
 /// Declares an edge handling mode
 pub enum EdgeMode {
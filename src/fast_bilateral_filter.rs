@@ -677,7 +677,6 @@ fn fast_bilateral_filter_plane_impl<
     })
 }
 
-#[cfg(feature = "image")]
 pub(crate) fn fast_bilateral_filter_gray_alpha_impl<
     V: Copy + Default + 'static + BilinearWorkingItem<V> + Send + Sync + Debug,
 >(
@@ -1049,6 +1048,16 @@ pub fn fast_bilateral_filter(
                 &pool,
             )?;
         }
+        FastBlurChannels::Channels2 => {
+            fast_bilateral_filter_gray_alpha_impl(
+                src,
+                dst,
+                kernel_size,
+                spatial_sigma,
+                range_sigma,
+                threading_policy,
+            )?;
+        }
         FastBlurChannels::Channels3 => {
             fast_bilateral_filter_rgb_impl(
                 src,
@@ -1121,6 +1130,16 @@ pub fn fast_bilateral_filter_u16(
                 &pool,
             )?;
         }
+        FastBlurChannels::Channels2 => {
+            fast_bilateral_filter_gray_alpha_impl(
+                src,
+                dst,
+                kernel_size,
+                spatial_sigma,
+                range_sigma,
+                threading_policy,
+            )?;
+        }
         FastBlurChannels::Channels3 => {
             fast_bilateral_filter_rgb_impl(
                 src,
@@ -1193,6 +1212,16 @@ pub fn fast_bilateral_filter_f32(
                 &pool,
             )?;
         }
+        FastBlurChannels::Channels2 => {
+            fast_bilateral_filter_gray_alpha_impl(
+                src,
+                dst,
+                kernel_size,
+                spatial_sigma,
+                range_sigma,
+                threading_policy,
+            )?;
+        }
         FastBlurChannels::Channels3 => {
             fast_bilateral_filter_rgb_impl(
                 src,
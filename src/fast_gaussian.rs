@@ -98,6 +98,7 @@ macro_rules! impl_generic_call {
         $radius:expr, $threading_policy:expr) => {
         let _dispatch = match $channels_type {
             FastBlurChannels::Plane => fast_gaussian_impl::<$store_type, 1>,
+            FastBlurChannels::Channels2 => fast_gaussian_impl::<$store_type, 2>,
             FastBlurChannels::Channels3 => fast_gaussian_impl::<$store_type, 3>,
             FastBlurChannels::Channels4 => fast_gaussian_impl::<$store_type, 4>,
         };
@@ -131,41 +132,6 @@ macro_rules! impl_margin_call {
     };
 }
 
-trait InitialValue {
-    fn get_initial(radius: usize) -> i64;
-}
-
-impl InitialValue for f32 {
-    fn get_initial(_: usize) -> i64 {
-        0i64
-    }
-}
-
-impl InitialValue for f64 {
-    fn get_initial(_: usize) -> i64 {
-        0i64
-    }
-}
-
-impl InitialValue for u8 {
-    fn get_initial(radius: usize) -> i64 {
-        ((radius * radius) >> 1) as i64
-    }
-}
-
-impl InitialValue for u16 {
-    fn get_initial(radius: usize) -> i64 {
-        ((radius * radius) >> 1) as i64
-    }
-}
-
-#[cfg(feature = "nightly_f16")]
-impl InitialValue for f16 {
-    fn get_initial(_: usize) -> i64 {
-        0i64
-    }
-}
-
 /// # Params
 /// `T` - type of buffer
 /// `J` - accumulator type
@@ -186,8 +152,7 @@ fn fg_vertical_pass<T, J, M, const CN: usize>(
         + Copy
         + FromPrimitive
         + Default
-        + AsPrimitive<J>
-        + InitialValue,
+        + AsPrimitive<J>,
     J: Copy
         + FromPrimitive
         + Default
@@ -206,17 +171,16 @@ fn fg_vertical_pass<T, J, M, const CN: usize>(
     let mut buffer_a = Box::new([0i32.as_(); 1024]);
     let radius_64 = radius as i64;
     let height_wide = height as i64;
-    let initial = J::from_i64(T::get_initial(radius as usize)).unwrap();
     let weight = M::from_f64(1f64 / (radius as f64 * radius as f64)).unwrap();
     for x in start..width.min(end) {
         let mut dif_r: J = 0i32.as_();
-        let mut sum_r: J = initial;
+        let mut sum_r: J = 0i32.as_();
         let mut dif_g: J = 0i32.as_();
-        let mut sum_g: J = initial;
+        let mut sum_g: J = 0i32.as_();
         let mut dif_b: J = 0i32.as_();
-        let mut sum_b: J = initial;
+        let mut sum_b: J = 0i32.as_();
         let mut dif_a: J = 0i32.as_();
-        let mut sum_a: J = initial;
+        let mut sum_a: J = 0i32.as_();
 
         let current_px = (x * CN as u32) as usize;
 
@@ -306,8 +270,7 @@ fn fg_horizontal_pass<T, J, M, const CN: usize>(
         + Copy
         + FromPrimitive
         + Default
-        + AsPrimitive<J>
-        + InitialValue,
+        + AsPrimitive<J>,
     J: Copy
         + FromPrimitive
         + Default
@@ -327,16 +290,15 @@ fn fg_horizontal_pass<T, J, M, const CN: usize>(
     let radius_64 = radius as i64;
     let width_wide = width as i64;
     let weight = M::from_f64(1f64 / (radius as f64 * radius as f64)).unwrap();
-    let initial = J::from_i64(T::get_initial(radius as usize)).unwrap();
     for y in start..height.min(end) {
         let mut dif_r: J = 0i32.as_();
-        let mut sum_r: J = initial;
+        let mut sum_r: J = 0i32.as_();
         let mut dif_g: J = 0i32.as_();
-        let mut sum_g: J = initial;
+        let mut sum_g: J = 0i32.as_();
         let mut dif_b: J = 0i32.as_();
-        let mut sum_b: J = initial;
+        let mut sum_b: J = 0i32.as_();
         let mut dif_a: J = 0i32.as_();
-        let mut sum_a: J = initial;
+        let mut sum_a: J = 0i32.as_();
 
         let current_y = ((y as i64) * (stride as i64)) as usize;
 
@@ -769,7 +731,6 @@ fn fast_gaussian_impl<
         + AsPrimitive<i64>
         + AsPrimitive<f32>
         + AsPrimitive<f64>
-        + InitialValue
         + FastGaussianDispatchProvider<T>,
     const CN: usize,
 >(
@@ -62,6 +62,7 @@ macro_rules! impl_generic_call {
     ($store_type:ty, $channels_type:expr, $edge_mode:expr, $bytes:expr, $stride:expr, $width:expr, $height:expr, $radius:expr, $threading_policy:expr) => {
         let _dispatcher = match $channels_type {
             FastBlurChannels::Plane => fast_gaussian_next_impl::<$store_type, 1>,
+            FastBlurChannels::Channels2 => fast_gaussian_next_impl::<$store_type, 2>,
             FastBlurChannels::Channels3 => fast_gaussian_next_impl::<$store_type, 3>,
             FastBlurChannels::Channels4 => fast_gaussian_next_impl::<$store_type, 4>,
         };
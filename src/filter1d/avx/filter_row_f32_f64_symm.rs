@@ -254,22 +254,22 @@ impl<const FMA: bool, const N: usize> ExecutionUnit<FMA, N> {
             let shifted_src = local_src.get_unchecked(cx..);
 
             let source_0 = _mm_loadu_ps(shifted_src.get_unchecked(half_len * N..).as_ptr());
-            let mut k0 = _mm_mul_pd(_mm_cvtps_pd(source_0), _mm256_castpd256_pd128(coeff));
+            let mut k0 = _mm256_mul_pd(_mm256_cvtps_pd(source_0), coeff);
 
             for i in 0..half_len {
                 let rollback = length - i - 1;
-                let coeff = _mm_set_pd1(scanned_kernel.get_unchecked(i).weight);
+                let coeff = _mm256_set1_pd(scanned_kernel.get_unchecked(i).weight);
                 let v_source0 = _mm_loadu_ps(shifted_src.get_unchecked((i * N)..).as_ptr());
                 let v_source1 = _mm_loadu_ps(shifted_src.get_unchecked((rollback * N)..).as_ptr());
-                k0 = _mm_opt_fmla_pd::<FMA>(
+                k0 = _mm256_opt_fmla_pd::<FMA>(
                     k0,
-                    _mm_add_pd(_mm_cvtps_pd(v_source0), _mm_cvtps_pd(v_source1)),
+                    _mm256_add_pd(_mm256_cvtps_pd(v_source0), _mm256_cvtps_pd(v_source1)),
                     coeff,
                 );
             }
 
             let dst_ptr = dst.get_unchecked_mut(cx..).as_mut_ptr();
-            _mm_storeu_ps(dst_ptr, _mm_cvtpd_ps(k0));
+            _mm_storeu_ps(dst_ptr, _mm256_cvtpd_ps(k0));
             cx += 4;
         }
 
@@ -649,3 +649,191 @@ pub(crate) fn create_brows<'a, T>(
     }
     brows
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FastBlurChannels;
+
+    /// Direct separable convolution with clamped borders
+    fn reference_1d<const N: usize>(
+        src: &[f64],
+        width: usize,
+        height: usize,
+        row_kernel: &[f64],
+        column_kernel: &[f64],
+    ) -> Vec<f64> {
+        let clamp = |v: isize, max: usize| v.max(0).min(max as isize - 1) as usize;
+        let mut horizontal = vec![0f64; src.len()];
+        let rr = row_kernel.len() as isize / 2;
+        for y in 0..height {
+            for x in 0..width {
+                for c in 0..N {
+                    horizontal[(y * width + x) * N + c] = row_kernel
+                        .iter()
+                        .enumerate()
+                        .map(|(i, &w)| {
+                            w * src
+                                [(y * width + clamp(x as isize + i as isize - rr, width)) * N + c]
+                        })
+                        .sum();
+                }
+            }
+        }
+        let cr = column_kernel.len() as isize / 2;
+        let mut dst = vec![0f64; src.len()];
+        for y in 0..height {
+            for x in 0..width * N {
+                dst[y * width * N + x] = column_kernel
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &w)| {
+                        w * horizontal[clamp(y as isize + i as isize - cr, height) * width * N + x]
+                    })
+                    .sum();
+            }
+        }
+        dst
+    }
+
+    #[test]
+    fn test_filter_1d_exact_f32_f64_symmetric() {
+        let (width, height) = (37usize, 9usize);
+        let src = (0..width * height * 3)
+            .map(|i| ((i / 3) % width) as f32 + (i % 3) as f32 * 0.25)
+            .collect::<Vec<f32>>();
+        let kernel = [0.25f64, 0.5, 0.25];
+        let image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let mut dst = BlurImageMut::default();
+        filter_1d_exact::<f32, f64, 3>(
+            &image,
+            &mut dst,
+            &kernel,
+            &kernel,
+            EdgeMode::Clamp,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let src_f64 = src.iter().map(|&x| x as f64).collect::<Vec<_>>();
+        let expected = reference_1d::<3>(&src_f64, width, height, &kernel, &kernel);
+        for (i, (&v, &e)) in dst.data.borrow().iter().zip(expected.iter()).enumerate() {
+            assert!((v as f64 - e).abs() < 1e-4, "{v} != {e} at {i}");
+        }
+    }
+
+    fn filter_u8_i16<const N: usize>(
+        width: usize,
+        height: usize,
+        row_kernel: &[i16],
+        column_kernel: &[i16],
+    ) {
+        let src = (0..width * height * N)
+            .map(|i| (((i / N) % width) + (i % N) * 5) as u8)
+            .collect::<Vec<u8>>();
+        let image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::try_from(N).unwrap(),
+        );
+        let mut dst = BlurImageMut::default();
+        filter_1d_exact::<u8, i16, N>(
+            &image,
+            &mut dst,
+            row_kernel,
+            column_kernel,
+            EdgeMode::Clamp,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let src_f64 = src.iter().map(|&x| x as f64).collect::<Vec<_>>();
+        let expected = reference_1d::<N>(
+            &src_f64,
+            width,
+            height,
+            &row_kernel.iter().map(|&x| x as f64).collect::<Vec<_>>(),
+            &column_kernel.iter().map(|&x| x as f64).collect::<Vec<_>>(),
+        );
+        for (i, (&v, &e)) in dst.data.borrow().iter().zip(expected.iter()).enumerate() {
+            assert_eq!(v as f64, e.min(255.), "at {i}");
+        }
+    }
+
+    #[test]
+    fn test_filter_1d_exact_u8_i16_row_rgba() {
+        filter_u8_i16::<4>(37, 9, &[1, 2, 0], &[0, 1, 0]);
+    }
+
+    #[test]
+    fn test_filter_1d_exact_u8_i16_column_tail() {
+        // Width is not a multiple of vector size, so scalar tail is used
+        filter_u8_i16::<1>(36, 9, &[0, 1, 0], &[0, 1, 1]);
+    }
+
+    #[test]
+    fn test_filter_1d_five_channels() {
+        // Channels count above four is passed only through const generic and stride
+        let (width, height) = (23usize, 7usize);
+        let src = (0..width * height * 5)
+            .map(|i| (((i / 5) % width) * 9 + (i % 5) * 7) as u8)
+            .collect::<Vec<u8>>();
+        let kernel = [0.25f32, 0.5, 0.25];
+        let image = BlurImage {
+            data: std::borrow::Cow::Borrowed(&src),
+            width: width as u32,
+            height: height as u32,
+            stride: width as u32 * 5,
+            channels: FastBlurChannels::Plane,
+        };
+        let src_f64 = src.iter().map(|&x| x as f64).collect::<Vec<_>>();
+        let kernel_f64 = kernel.map(|x| x as f64);
+        let expected = reference_1d::<5>(&src_f64, width, height, &kernel_f64, &kernel_f64);
+
+        for approx in [false, true] {
+            let mut dst = vec![0u8; src.len()];
+            let mut dst_image = BlurImageMut {
+                data: crate::BufferStore::Borrowed(&mut dst),
+                width: width as u32,
+                height: height as u32,
+                stride: width as u32 * 5,
+                channels: FastBlurChannels::Plane,
+            };
+            if approx {
+                crate::filter_1d_approx::<u8, f32, i32, 5>(
+                    &image,
+                    &mut dst_image,
+                    &kernel,
+                    &kernel,
+                    EdgeMode::Clamp,
+                    Scalar::default(),
+                    ThreadingPolicy::Single,
+                )
+                .unwrap();
+            } else {
+                filter_1d_exact::<u8, f32, 5>(
+                    &image,
+                    &mut dst_image,
+                    &kernel,
+                    &kernel,
+                    EdgeMode::Clamp,
+                    Scalar::default(),
+                    ThreadingPolicy::Single,
+                )
+                .unwrap();
+            }
+            for (i, (&v, &e)) in dst.iter().zip(expected.iter()).enumerate() {
+                assert!(
+                    (v as f64 - e).abs() <= 1.,
+                    "{v} != {e} at {i}, approx {approx}"
+                );
+            }
+        }
+    }
+}
@@ -68,9 +68,14 @@ mod to_approx_storage;
 mod to_approx_storage_complex;
 
 pub use arena::{make_arena, Arena, ArenaPads};
+pub(crate) use arena_roi::copy_roi;
 pub use filter::filter_1d_exact;
 pub use filter_1d_approx::filter_1d_approx;
+pub(crate) use filter_1d_column_handler::Filter1DColumnHandler;
+pub(crate) use filter_1d_row_handler::Filter1DRowHandler;
 pub use filter_complex::filter_1d_complex;
 pub use filter_complex_q::filter_1d_complex_fixed_point;
 pub use filter_element::KernelShape;
+pub(crate) use filter_scan::{is_symmetric_1d, scan_se_1d, ScanPoint1d};
+pub(crate) use region::FilterRegion;
 pub use to_approx_storage::ToApproxStorage;
@@ -129,14 +129,14 @@ impl Filter1DRowHandlerBInterpolateApr<u8, i32> for u8 {
             }
             #[cfg(all(target_arch = "aarch64", feature = "neon"))]
             {
-                if _kernel.len() == 3 {
+                if N <= 4 && _kernel.len() == 3 {
                     let all_positive = _kernel.iter().all(|&x| x.weight > 0);
                     if all_positive {
                         use crate::filter1d::neon::filter_row_symm_neon_binter_u8_uq0_7_k3;
                         return Some(filter_row_symm_neon_binter_u8_uq0_7_k3::<N>);
                     }
                 }
-                if _kernel.len() == 5 {
+                if N <= 4 && _kernel.len() == 5 {
                     let all_positive = _kernel.iter().all(|&x| x.weight > 0);
                     if all_positive {
                         use crate::filter1d::neon::filter_row_symm_neon_binter_u8_uq0_7_x5;
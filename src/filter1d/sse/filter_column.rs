@@ -239,7 +239,7 @@ impl ExecutionUnit {
                 let coeff = *scanned_kernel.get_unchecked(i);
                 k0 = mlaf(
                     k0,
-                    (*arena_src.get_unchecked(i).get_unchecked(cx)) as f32,
+                    (*arena_src.get_unchecked(i).get_unchecked(x)) as f32,
                     coeff.weight,
                 );
             }
@@ -248,3 +248,35 @@ impl ExecutionUnit {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter1d::filter_column::filter_column;
+
+    #[test]
+    fn test_filter_column_sse_u8_f32_tail() {
+        if !std::arch::is_x86_feature_detected!("sse4.1") {
+            return;
+        }
+        // 36 elements leave 4 of them to the scalar tail
+        let width = 36usize;
+        let rows = (0..3)
+            .map(|y| {
+                (0..width)
+                    .map(|x| (x * 5 + y * 20) as u8)
+                    .collect::<Vec<u8>>()
+            })
+            .collect::<Vec<_>>();
+        let rows = rows.iter().map(|x| x.as_slice()).collect::<Vec<_>>();
+        let kernel = [0.25f32, 0.25, 0.5].map(ScanPoint1d::new);
+        let arena = Arena::new(width, 3, 0, 1, 1);
+        let image_size = ImageSize::new(width, 1);
+        let region = FilterRegion::new(0, 1);
+        let mut simd = vec![0u8; width];
+        filter_column_sse_u8_f32(arena, &rows, &mut simd, image_size, region, &kernel);
+        let mut scalar = vec![0u8; width];
+        filter_column(arena, &rows, &mut scalar, image_size, region, &kernel);
+        assert_eq!(simd, scalar);
+    }
+}
@@ -234,7 +234,7 @@ unsafe fn filter_column_sse_u8_i16_impl(
             let coeff = *scanned_kernel.get_unchecked(i);
             k0 = mlaf(
                 k0,
-                (*arena_src.get_unchecked(i).get_unchecked(cx)) as i16,
+                (*arena_src.get_unchecked(i).get_unchecked(x)) as i16,
                 coeff.weight,
             );
         }
@@ -67,8 +67,6 @@ unsafe fn filter_rgb_row_sse_u8_i16_impl<const N: usize>(
     _: FilterRegion,
     scanned_kernel: &[ScanPoint1d<i16>],
 ) {
-    const N: usize = 3;
-
     let src = arena_src;
 
     let length = scanned_kernel.len();
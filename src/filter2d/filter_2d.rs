@@ -92,6 +92,15 @@ where
             border_constant,
             threading_policy,
         ),
+        FastBlurChannels::Channels2 => filter_2d_arbitrary::<T, F, 2>(
+            src,
+            dst,
+            kernel,
+            kernel_shape,
+            border_mode,
+            border_constant,
+            threading_policy,
+        ),
         FastBlurChannels::Channels3 => filter_2d_arbitrary::<T, F, 3>(
             src,
             dst,
@@ -230,3 +239,37 @@ where
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_2d_f32_tail() {
+        // 36 elements per row leave 4 of them to the scalar tail
+        let (width, height) = (36usize, 5usize);
+        let src = (0..width * height)
+            .map(|i| (i % width) as f32 + (i / width) as f32 * 0.5)
+            .collect::<Vec<f32>>();
+        let kernel = [0., 0., 0., 0.5, 0., 0., 0., 0., 0.5];
+        let image = BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        filter_2d::<f32, f32>(
+            &image,
+            &mut dst,
+            &kernel,
+            KernelShape::new(3, 3),
+            EdgeMode::Clamp,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let at = |x: usize, y: usize| src[y.min(height - 1) * width + x.min(width - 1)];
+        for y in 0..height {
+            for x in 0..width {
+                let expected = 0.5 * at(x.saturating_sub(1), y) + 0.5 * at(x + 1, y + 1);
+                assert_eq!(dst.data.borrow()[y * width + x], expected, "at {x}, {y}");
+            }
+        }
+    }
+}
@@ -35,6 +35,7 @@ use crate::{
     BlurImage, BlurImageMut, ConvolutionMode, Scalar, ThreadingPolicy,
 };
 
+#[repr(C)]
 #[derive(Copy, Clone, Debug)]
 pub struct GaussianBlurParams {
     /// X-axis kernel size
@@ -189,6 +190,21 @@ impl GaussianBlurParams {
         (vx_kernel, vy_kernel)
     }
 
+    /// Returns kernel sizes that will be actually used for x and y axis.
+    pub(crate) fn kernel_sizes(&self) -> (u32, u32) {
+        let x_kernel = if self.x_kernel == 0 {
+            kernel_size_d(self.x_sigma)
+        } else {
+            self.x_kernel
+        };
+        let y_kernel = if self.y_kernel == 0 {
+            kernel_size_d(self.y_sigma)
+        } else {
+            self.y_kernel
+        };
+        (x_kernel, y_kernel)
+    }
+
     fn validate(&self) -> Result<(), BlurError> {
         if self.x_sigma < 0. || self.y_sigma < 0. {
             return Err(BlurError::NegativeOrZeroSigma);
@@ -245,6 +261,7 @@ pub fn gaussian_blur(
         ConvolutionMode::Exact => {
             let _dispatcher = match src.channels {
                 FastBlurChannels::Plane => filter_1d_exact::<u8, f32, 1>,
+                FastBlurChannels::Channels2 => filter_1d_exact::<u8, f32, 2>,
                 FastBlurChannels::Channels3 => filter_1d_exact::<u8, f32, 3>,
                 FastBlurChannels::Channels4 => filter_1d_exact::<u8, f32, 4>,
             };
@@ -261,6 +278,7 @@ pub fn gaussian_blur(
         ConvolutionMode::FixedPoint => {
             let _dispatcher = match src.channels {
                 FastBlurChannels::Plane => filter_1d_approx::<u8, f32, i32, 1>,
+                FastBlurChannels::Channels2 => filter_1d_approx::<u8, f32, i32, 2>,
                 FastBlurChannels::Channels3 => filter_1d_approx::<u8, f32, i32, 3>,
                 FastBlurChannels::Channels4 => filter_1d_approx::<u8, f32, i32, 4>,
             };
@@ -317,6 +335,7 @@ pub fn gaussian_blur_u16(
         ConvolutionMode::Exact => {
             let _dispatcher = match src.channels {
                 FastBlurChannels::Plane => filter_1d_exact::<u16, f32, 1>,
+                FastBlurChannels::Channels2 => filter_1d_exact::<u16, f32, 2>,
                 FastBlurChannels::Channels3 => filter_1d_exact::<u16, f32, 3>,
                 FastBlurChannels::Channels4 => filter_1d_exact::<u16, f32, 4>,
             };
@@ -334,6 +353,7 @@ pub fn gaussian_blur_u16(
             use crate::filter1d::filter_1d_approx;
             let _dispatcher = match src.channels {
                 FastBlurChannels::Plane => filter_1d_approx::<u16, f32, u32, 1>,
+                FastBlurChannels::Channels2 => filter_1d_approx::<u16, f32, u32, 2>,
                 FastBlurChannels::Channels3 => filter_1d_approx::<u16, f32, u32, 3>,
                 FastBlurChannels::Channels4 => filter_1d_approx::<u16, f32, u32, 4>,
             };
@@ -386,6 +406,7 @@ pub fn gaussian_blur_f32(
             let (x_kernel, y_kernel) = params.make_f32_kernels();
             let _dispatcher = match src.channels {
                 FastBlurChannels::Plane => filter_1d_exact::<f32, f32, 1>,
+                FastBlurChannels::Channels2 => filter_1d_exact::<f32, f32, 2>,
                 FastBlurChannels::Channels3 => filter_1d_exact::<f32, f32, 3>,
                 FastBlurChannels::Channels4 => filter_1d_exact::<f32, f32, 4>,
             };
@@ -403,6 +424,7 @@ pub fn gaussian_blur_f32(
             let (x_kernel, y_kernel) = params.make_f64_kernels();
             let _dispatcher = match src.channels {
                 FastBlurChannels::Plane => filter_1d_exact::<f32, f64, 1>,
+                FastBlurChannels::Channels2 => filter_1d_exact::<f32, f64, 2>,
                 FastBlurChannels::Channels3 => filter_1d_exact::<f32, f64, 3>,
                 FastBlurChannels::Channels4 => filter_1d_exact::<f32, f64, 4>,
             };
@@ -452,6 +474,7 @@ pub fn gaussian_blur_f16(
     let (x_kernel, y_kernel) = params.make_f32_kernels();
     let _dispatcher = match src.channels {
         FastBlurChannels::Plane => filter_1d_exact::<f16, f32, 1>,
+        FastBlurChannels::Channels2 => filter_1d_exact::<f16, f32, 2>,
         FastBlurChannels::Channels3 => filter_1d_exact::<f16, f32, 3>,
         FastBlurChannels::Channels4 => filter_1d_exact::<f16, f32, 4>,
     };
@@ -522,6 +545,41 @@ mod tests {
         compare_u8_stat!(dst);
     }
 
+    #[test]
+    fn test_gauss_u8_two_channels() {
+        let width: usize = 148;
+        let height: usize = 148;
+        let mut src = vec![126; width * height * 2];
+        for dst in src.chunks_exact_mut(2) {
+            dst[0] = 126;
+            dst[1] = 66;
+        }
+        let src_image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels2,
+        );
+        for mode in [ConvolutionMode::Exact, ConvolutionMode::FixedPoint] {
+            let mut dst = BlurImageMut::default();
+            gaussian_blur(
+                &src_image,
+                &mut dst,
+                GaussianBlurParams::new_from_kernel(9.),
+                EdgeMode::Clamp,
+                ThreadingPolicy::Single,
+                mode,
+            )
+            .unwrap();
+            for (i, cn) in dst.data.borrow().chunks_exact(2).enumerate() {
+                let diff0 = (cn[0] as i32 - 126).abs();
+                assert!(diff0 <= 3, "Diff {diff0} at {i} in channel 0");
+                let diff1 = (cn[1] as i32 - 66).abs();
+                assert!(diff1 <= 3, "Diff {diff1} at {i} in channel 1");
+            }
+        }
+    }
+
     #[test]
     fn test_gauss_u8_q_k3() {
         let width: usize = 148;
@@ -0,0 +1,173 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::gradient::{prepare_destination, separable_f32, to_f32_image, write_combined};
+use crate::{
+    gaussian_derivative_kernel_1d, gaussian_kernel_1d, gaussian_second_derivative_kernel_1d,
+    BlurError, BlurImage, BlurImageMut, EdgeMode, Scalar, ThreadingPolicy,
+};
+use num_traits::AsPrimitive;
+use std::fmt::Debug;
+
+fn derivative_kernel(width: u32, sigma: f32, order: u32) -> Result<Vec<f32>, BlurError> {
+    match order {
+        0 => Ok(gaussian_kernel_1d(width, sigma)),
+        1 => gaussian_derivative_kernel_1d(width, sigma),
+        _ => gaussian_second_derivative_kernel_1d(width, sigma),
+    }
+}
+
+/// Computes Gaussian derivative of the image.
+///
+/// Image is smoothed with Gaussian and differentiated `order_x` times along X
+/// and `order_y` times along Y, result is signed.
+/// Kernel radius is `ceil(4 * sigma)`.
+///
+/// # Arguments
+///
+/// * `src` - Source image, `u8`, `u16` or `f32`, see [BlurImage] for more info.
+/// * `dst` - Destination image, see [BlurImageMut] for more info.
+/// * `sigma` - Gaussian sigma, must be > 0.
+/// * `order_x` - Derivative order along X axis, `0`, `1` or `2`.
+/// * `order_y` - Derivative order along Y axis, `0`, `1` or `2`.
+/// * `border_mode` - Border handling mode see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+#[allow(clippy::too_many_arguments)]
+pub fn gaussian_derivative<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<f32>,
+    sigma: f32,
+    order_x: u32,
+    order_y: u32,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + AsPrimitive<f32>,
+{
+    if sigma <= 0. || !sigma.is_finite() {
+        return Err(BlurError::NegativeOrZeroSigma);
+    }
+    if order_x > 2 || order_y > 2 {
+        return Err(BlurError::InvalidArguments);
+    }
+    prepare_destination(src, dst)?;
+    let width = (sigma * 4.).ceil().max(1.) as u32 * 2 + 1;
+    let row_kernel = derivative_kernel(width, sigma, order_x)?;
+    let column_kernel = derivative_kernel(width, sigma, order_y)?;
+    let working = to_f32_image(src);
+    let derivative = separable_f32(
+        &working.to_immutable_ref(),
+        &row_kernel,
+        &column_kernel,
+        border_mode,
+        border_constant,
+        threading_policy,
+    )?;
+    write_combined(&derivative, &derivative, dst, |v, _| v);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FastBlurChannels;
+
+    #[test]
+    fn test_derivative_kernels() {
+        let first = gaussian_derivative_kernel_1d(9, 1.5).unwrap();
+        let second = gaussian_second_derivative_kernel_1d(9, 1.5).unwrap();
+        let ramp: f32 = first
+            .iter()
+            .enumerate()
+            .map(|(i, k)| k * (i as f32 - 4.))
+            .sum();
+        assert!((ramp - 1.).abs() < 1e-5);
+        assert!(first.iter().sum::<f32>().abs() < 1e-6);
+        assert!(second.iter().sum::<f32>().abs() < 1e-6);
+        assert!(second[4] < 0.);
+        assert!(matches!(
+            gaussian_derivative_kernel_1d(8, 1.5),
+            Err(BlurError::OddKernel(8))
+        ));
+        assert!(matches!(
+            gaussian_second_derivative_kernel_1d(0, 1.5),
+            Err(BlurError::OddKernel(0))
+        ));
+        assert!(matches!(
+            gaussian_derivative_kernel_1d(1, 1.5),
+            Err(BlurError::InvalidArguments)
+        ));
+        assert!(matches!(
+            gaussian_second_derivative_kernel_1d(1, 1.5),
+            Err(BlurError::InvalidArguments)
+        ));
+        assert!(matches!(
+            gaussian_derivative_kernel_1d(9, 0.),
+            Err(BlurError::NegativeOrZeroSigma)
+        ));
+        assert!(matches!(
+            gaussian_second_derivative_kernel_1d(9, f32::NAN),
+            Err(BlurError::NegativeOrZeroSigma)
+        ));
+    }
+
+    #[test]
+    fn test_gaussian_derivative_polynomial() {
+        let (width, height) = (40usize, 40usize);
+        // f(x, y) = 3 * x * y + y^2, d/dx = 3 * y, d2/dy2 = 2, d2/dxdy = 3
+        let src: Vec<f32> = (0..width * height)
+            .map(|i| {
+                let (x, y) = ((i % width) as f32, (i / width) as f32);
+                3. * x * y + y * y
+            })
+            .collect();
+        let image = BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        for ((order_x, order_y), expected) in [((1, 0), 60.), ((0, 2), 2.), ((1, 1), 3.)] {
+            let mut dst = BlurImageMut::default();
+            gaussian_derivative(
+                &image,
+                &mut dst,
+                2.,
+                order_x,
+                order_y,
+                EdgeMode::Clamp,
+                Scalar::default(),
+                ThreadingPolicy::Adaptive,
+            )
+            .unwrap();
+            let value = dst.data.borrow()[20 * width + 20];
+            assert!(
+                (value - expected).abs() < 1e-2,
+                "{order_x} {order_y} {value}"
+            );
+        }
+    }
+}
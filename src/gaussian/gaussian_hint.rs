@@ -28,6 +28,7 @@
  */
 
 /// Declares preferred convolution precision mode for integer storage types.
+#[repr(C)]
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default)]
 pub enum ConvolutionMode {
     /// Exact precision, f32 accumulator and weights will be used.
@@ -41,6 +42,7 @@ pub enum ConvolutionMode {
 }
 
 /// Specifies the preferred convolution precision mode for IEEE 754 binary32 (`f32`) data.
+#[repr(C)]
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default)]
 pub enum IeeeBinaryConvolutionMode {
     /// Exact precision, f32 accumulator and weights will be used.
@@ -25,6 +25,7 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::BlurError;
 use num_complex::Complex;
 
 pub fn gaussian_kernel_1d(width: u32, sigma: f32) -> Vec<f32> {
@@ -73,6 +74,82 @@ pub fn gaussian_kernel_1d_f64(width: u32, sigma: f64) -> Vec<f64> {
     kernel
 }
 
+fn check_derivative_kernel(width: u32, sigma: f32) -> Result<(), BlurError> {
+    if width % 2 == 0 {
+        return Err(BlurError::OddKernel(width as usize));
+    }
+    // Single tap has no neighbours to differentiate over
+    if width < 3 {
+        return Err(BlurError::InvalidArguments);
+    }
+    if sigma <= 0. || !sigma.is_finite() {
+        return Err(BlurError::NegativeOrZeroSigma);
+    }
+    Ok(())
+}
+
+/// First derivative of Gaussian.
+///
+/// Kernel is antisymmetric and scaled so correlation with linear ramp `x` gives exactly `1`.
+/// Width must be odd and at least 3, sigma must be positive and finite.
+pub fn gaussian_derivative_kernel_1d(width: u32, sigma: f32) -> Result<Vec<f32>, BlurError> {
+    check_derivative_kernel(width, sigma)?;
+    let gaussian = gaussian_kernel_1d_f64(width, sigma as f64);
+    let mean = (width / 2) as f64;
+    let mut kernel = gaussian
+        .iter()
+        .enumerate()
+        .map(|(x, &g)| (x as f64 - mean) * g)
+        .collect::<Vec<_>>();
+    let moment: f64 = kernel
+        .iter()
+        .enumerate()
+        .map(|(x, &k)| k * (x as f64 - mean))
+        .sum();
+    if moment != 0. {
+        let scale = 1. / moment;
+        kernel.iter_mut().for_each(|x| *x *= scale);
+    }
+    Ok(kernel.iter().map(|&x| x as f32).collect())
+}
+
+/// Second derivative of Gaussian.
+///
+/// Kernel sums to zero and scaled so correlation with `x^2` gives exactly `2`.
+/// Width must be odd and at least 3, sigma must be positive and finite.
+pub fn gaussian_second_derivative_kernel_1d(width: u32, sigma: f32) -> Result<Vec<f32>, BlurError> {
+    check_derivative_kernel(width, sigma)?;
+    let sigma = sigma as f64;
+    let gaussian = gaussian_kernel_1d_f64(width, sigma);
+    let mean = (width / 2) as f64;
+    let sigma_sq = sigma * sigma;
+    let mut kernel = gaussian
+        .iter()
+        .enumerate()
+        .map(|(x, &g)| {
+            let dx = x as f64 - mean;
+            (dx * dx - sigma_sq) / (sigma_sq * sigma_sq) * g
+        })
+        .collect::<Vec<_>>();
+    let drift: f64 = kernel.iter().sum();
+    for (k, &g) in kernel.iter_mut().zip(gaussian.iter()) {
+        *k -= drift * g;
+    }
+    let moment: f64 = kernel
+        .iter()
+        .enumerate()
+        .map(|(x, &k)| {
+            let dx = x as f64 - mean;
+            k * dx * dx
+        })
+        .sum();
+    if moment != 0. {
+        let scale = 2. / moment;
+        kernel.iter_mut().for_each(|x| *x *= scale);
+    }
+    Ok(kernel.iter().map(|&x| x as f32).collect())
+}
+
 /// Regular gaussian kernel with phase shift.
 pub fn complex_gaussian_kernel(radius: f64, scale: f64, distortion: f64) -> Vec<Complex<f32>> {
     if radius < 1. {
@@ -26,13 +26,26 @@
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 mod declaration;
+mod gaussian_derivative;
 mod gaussian_hint;
+mod gaussian_iir;
 mod gaussian_kernel;
 mod gaussian_util;
+mod oriented_gaussian;
 
 #[cfg(feature = "nightly_f16")]
 pub use declaration::gaussian_blur_f16;
 pub use declaration::{gaussian_blur, gaussian_blur_f32, gaussian_blur_u16, GaussianBlurParams};
+pub use gaussian_derivative::gaussian_derivative;
 pub use gaussian_hint::{ConvolutionMode, IeeeBinaryConvolutionMode};
-pub use gaussian_kernel::{complex_gaussian_kernel, gaussian_kernel_1d, gaussian_kernel_1d_f64};
+pub use gaussian_iir::{gaussian_blur_iir, gaussian_blur_iir_f32, gaussian_blur_iir_u16};
+pub use gaussian_kernel::{
+    complex_gaussian_kernel, gaussian_derivative_kernel_1d, gaussian_kernel_1d,
+    gaussian_kernel_1d_f64, gaussian_second_derivative_kernel_1d,
+};
+pub(crate) use gaussian_util::kernel_size;
 pub use gaussian_util::{sigma_size, sigma_size_d};
+pub use oriented_gaussian::{
+    oriented_gaussian_blur, oriented_gaussian_blur_f32, oriented_gaussian_blur_u16,
+    OrientedGaussianParams,
+};
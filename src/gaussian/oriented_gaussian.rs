@@ -0,0 +1,386 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::filter1d::{make_arena, ArenaPads};
+use crate::gradient::{separable_f32, to_f32_image};
+use crate::{
+    gaussian_kernel_1d, BlurError, BlurImage, BlurImageMut, EdgeMode, FastBlurChannels,
+    GradientStorage, ImageSize, Scalar, ThreadingPolicy,
+};
+use novtb::{ParallelZonedIterator, TbSliceMut};
+use num_traits::AsPrimitive;
+
+/// Rotated elliptical Gaussian parameters
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OrientedGaussianParams {
+    /// Sigma along `u` axis
+    pub sigma_u: f32,
+    /// Sigma along `v` axis, perpendicular to `u`
+    pub sigma_v: f32,
+    /// Angle of `u` axis in radians, measured from X axis towards Y axis
+    pub angle: f32,
+}
+
+impl OrientedGaussianParams {
+    pub fn new(sigma_u: f32, sigma_v: f32, angle: f32) -> OrientedGaussianParams {
+        OrientedGaussianParams {
+            sigma_u,
+            sigma_v,
+            angle,
+        }
+    }
+
+    fn validate(&self) -> Result<(), BlurError> {
+        if self.sigma_u <= 0.
+            || self.sigma_v <= 0.
+            || !self.sigma_u.is_finite()
+            || !self.sigma_v.is_finite()
+        {
+            return Err(BlurError::NegativeOrZeroSigma);
+        }
+        if !self.angle.is_finite() {
+            return Err(BlurError::InvalidArguments);
+        }
+        Ok(())
+    }
+}
+
+/// Gaussian decomposed into axis aligned pass and pass along skewed direction
+struct SkewedDecomposition {
+    /// Sigma of axis aligned pass
+    axis_sigma: f32,
+    /// Sigma of skewed pass, measured in steps along major axis
+    skew_sigma: f32,
+    /// Offset along aligned axis per step of skewed pass
+    slope: f32,
+    /// Aligned pass is horizontal and skewed pass steps along Y
+    steps_along_y: bool,
+}
+
+impl SkewedDecomposition {
+    /// Covariance `C` is split into `s1^2 * e * e^T + s2^2 * d * d^T` where `e` is axis
+    /// and `d = (slope, 1)` for stepping along Y, the axis with larger variance is chosen
+    /// for steps so `|slope| <= 1`.
+    fn new(params: OrientedGaussianParams) -> SkewedDecomposition {
+        let (sin, cos) = (params.angle as f64).sin_cos();
+        let su2 = params.sigma_u as f64 * params.sigma_u as f64;
+        let sv2 = params.sigma_v as f64 * params.sigma_v as f64;
+        let cxx = su2 * cos * cos + sv2 * sin * sin;
+        let cyy = su2 * sin * sin + sv2 * cos * cos;
+        let cxy = (su2 - sv2) * cos * sin;
+        let det = su2 * sv2;
+        if cyy >= cxx {
+            SkewedDecomposition {
+                axis_sigma: (det / cyy).sqrt() as f32,
+                skew_sigma: cyy.sqrt() as f32,
+                slope: (cxy / cyy) as f32,
+                steps_along_y: true,
+            }
+        } else {
+            SkewedDecomposition {
+                axis_sigma: (det / cxx).sqrt() as f32,
+                skew_sigma: cxx.sqrt() as f32,
+                slope: (cxy / cxx) as f32,
+                steps_along_y: false,
+            }
+        }
+    }
+}
+
+fn gaussian_weights(sigma: f32) -> Vec<f32> {
+    let radius = (sigma * 4.).ceil().max(1.) as u32;
+    gaussian_kernel_1d(radius * 2 + 1, sigma)
+}
+
+fn oriented_gaussian_impl<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    params: OrientedGaussianParams,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: GradientStorage + AsPrimitive<f32>,
+{
+    params.validate()?;
+    src.check_layout()?;
+    dst.check_layout(Some(src))?;
+    src.size_matches_mut(dst)?;
+
+    let decomposition = SkewedDecomposition::new(params);
+    let identity = [1f32];
+    let axis_weights = gaussian_weights(decomposition.axis_sigma);
+    let (row_kernel, column_kernel) = if decomposition.steps_along_y {
+        (axis_weights.as_slice(), identity.as_slice())
+    } else {
+        (identity.as_slice(), axis_weights.as_slice())
+    };
+    let working = to_f32_image(src);
+    let aligned = separable_f32(
+        &working.to_immutable_ref(),
+        row_kernel,
+        column_kernel,
+        edge_mode,
+        border_constant,
+        threading_policy,
+    )?;
+
+    let skew_weights = gaussian_weights(decomposition.skew_sigma);
+    let radius = skew_weights.len() / 2;
+    let slope = decomposition.slope;
+    let shift_pad = (slope.abs() * radius as f32).ceil() as usize + 1;
+    let pads = if decomposition.steps_along_y {
+        ArenaPads::new(shift_pad, radius, shift_pad, radius)
+    } else {
+        ArenaPads::new(radius, shift_pad, radius, shift_pad)
+    };
+
+    let width = src.width as usize;
+    let height = src.height as usize;
+    let cn = src.channels.channels();
+    let (arena_source, arena) = match src.channels {
+        FastBlurChannels::Plane => make_arena::<f32, 1>,
+        FastBlurChannels::Channels2 => make_arena::<f32, 2>,
+        FastBlurChannels::Channels3 => make_arena::<f32, 3>,
+        FastBlurChannels::Channels4 => make_arena::<f32, 4>,
+    }(
+        aligned.data.borrow(),
+        aligned.row_stride() as usize,
+        ImageSize::new(width, height),
+        pads,
+        edge_mode,
+        border_constant,
+    )?;
+    let arena_stride = arena.width * cn;
+    let row_length = width * cn;
+
+    let thread_count = threading_policy.thread_count(src.width, src.height);
+    let pool = novtb::ThreadPool::new(thread_count);
+
+    let dst_stride = dst.row_stride() as usize;
+    dst.data
+        .borrow_mut()
+        .tb_par_chunks_mut(dst_stride)
+        .for_each_enumerated(&pool, |y, row| {
+            let mut accumulator = vec![0f32; row_length];
+            for (k, &weight) in skew_weights.iter().enumerate() {
+                let step = k as isize - radius as isize;
+                let shift = slope * step as f32;
+                let whole = shift.floor();
+                let fraction = shift - whole;
+                let whole = whole as isize;
+                // Two neighbouring samples along the aligned axis
+                let (first, second) = if decomposition.steps_along_y {
+                    let arena_row = &arena_source[(y + k) * arena_stride..];
+                    let start = ((arena.pad_w as isize + whole) as usize) * cn;
+                    (
+                        &arena_row[start..start + row_length],
+                        &arena_row[start + cn..start + cn + row_length],
+                    )
+                } else {
+                    let first_row = (y as isize + arena.pad_h as isize + whole) as usize;
+                    let start = (arena.pad_w as isize + step) as usize * cn;
+                    (
+                        &arena_source[first_row * arena_stride + start..][..row_length],
+                        &arena_source[(first_row + 1) * arena_stride + start..][..row_length],
+                    )
+                };
+                let w0 = weight * (1. - fraction);
+                let w1 = weight * fraction;
+                for ((dst, &a), &b) in accumulator.iter_mut().zip(first.iter()).zip(second.iter()) {
+                    *dst += a * w0 + b * w1;
+                }
+            }
+            for (dst, &src) in row[..row_length].iter_mut().zip(accumulator.iter()) {
+                *dst = T::from_f32(src);
+            }
+        });
+    Ok(())
+}
+
+/// Performs rotated elliptical Gaussian blur.
+///
+/// Gaussian is decomposed into axis aligned pass followed by pass along skewed
+/// direction with linear interpolation, as in Geusebroek et al. "Fast anisotropic Gauss filtering".
+/// Complexity is O(sigma) per pixel for any angle.
+///
+/// # Arguments
+///
+/// * `src` - Source image, see [BlurImage] for more info.
+/// * `dst` - Destination image, see [BlurImageMut] for more info.
+/// * `params` - See [OrientedGaussianParams] for more info.
+/// * `edge_mode` - Border handling mode see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn oriented_gaussian_blur(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    params: OrientedGaussianParams,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    oriented_gaussian_impl(
+        src,
+        dst,
+        params,
+        edge_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs rotated elliptical Gaussian blur.
+///
+/// See [oriented_gaussian_blur] for more info.
+///
+/// # Arguments
+///
+/// * `src` - Source image, see [BlurImage] for more info.
+/// * `dst` - Destination image, see [BlurImageMut] for more info.
+/// * `params` - See [OrientedGaussianParams] for more info.
+/// * `edge_mode` - Border handling mode see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn oriented_gaussian_blur_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    params: OrientedGaussianParams,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    oriented_gaussian_impl(
+        src,
+        dst,
+        params,
+        edge_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs rotated elliptical Gaussian blur.
+///
+/// See [oriented_gaussian_blur] for more info.
+///
+/// # Arguments
+///
+/// * `src` - Source image, see [BlurImage] for more info.
+/// * `dst` - Destination image, see [BlurImageMut] for more info.
+/// * `params` - See [OrientedGaussianParams] for more info.
+/// * `edge_mode` - Border handling mode see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn oriented_gaussian_blur_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    params: OrientedGaussianParams,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    oriented_gaussian_impl(
+        src,
+        dst,
+        params,
+        edge_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Second moments of blurred impulse
+    fn impulse_moments(params: OrientedGaussianParams) -> (f32, f32, f32, f32) {
+        let size = 81usize;
+        let center = 40usize;
+        let mut src = vec![0f32; size * size];
+        src[center * size + center] = 1.;
+        let image = BlurImage::borrow(&src, size as u32, size as u32, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        oriented_gaussian_blur_f32(
+            &image,
+            &mut dst,
+            params,
+            EdgeMode::Constant,
+            Scalar::default(),
+            ThreadingPolicy::Adaptive,
+        )
+        .unwrap();
+        let (mut sum, mut xx, mut yy, mut xy) = (0f32, 0f32, 0f32, 0f32);
+        for (i, &v) in dst.data.borrow().iter().enumerate() {
+            let x = (i % size) as f32 - center as f32;
+            let y = (i / size) as f32 - center as f32;
+            sum += v;
+            xx += v * x * x;
+            yy += v * y * y;
+            xy += v * x * y;
+        }
+        (sum, xx, yy, xy)
+    }
+
+    #[test]
+    fn test_oriented_gaussian_covariance() {
+        for angle in [0f32, 0.4, 1.2, 2.5] {
+            let params = OrientedGaussianParams::new(6., 2., angle);
+            let (sum, xx, yy, xy) = impulse_moments(params);
+            let (sin, cos) = angle.sin_cos();
+            let expected_xx = 36. * cos * cos + 4. * sin * sin;
+            let expected_yy = 36. * sin * sin + 4. * cos * cos;
+            let expected_xy = 32. * cos * sin;
+            assert!((sum - 1.).abs() < 1e-3);
+            // Linear interpolation adds up to a quarter pixel of variance
+            assert!((xx - expected_xx).abs() < 0.6, "{angle} {xx} {expected_xx}");
+            assert!((yy - expected_yy).abs() < 0.6, "{angle} {yy} {expected_yy}");
+            assert!((xy - expected_xy).abs() < 0.6, "{angle} {xy} {expected_xy}");
+        }
+    }
+
+    #[test]
+    fn test_oriented_gaussian_flat_u8() {
+        let src = vec![120u8; 30 * 20 * 4];
+        let image = BlurImage::borrow(&src, 30, 20, FastBlurChannels::Channels4);
+        let mut dst = BlurImageMut::default();
+        oriented_gaussian_blur(
+            &image,
+            &mut dst,
+            OrientedGaussianParams::new(5., 1., 0.7),
+            EdgeMode::Reflect101,
+            Scalar::default(),
+            ThreadingPolicy::Adaptive,
+        )
+        .unwrap();
+        assert!(dst.data.borrow().iter().all(|&x| x == 120));
+    }
+}
@@ -79,56 +79,21 @@ pub fn gaussian_blur_image(
             Some(DynamicImage::ImageLuma8(new_gray_image))
         }
         DynamicImage::ImageLumaA8(luma_alpha_image) => {
-            let mut intensity_plane = BlurImageMut::alloc(
+            let gray_image = BlurImage::borrow(
+                &luma_alpha_image,
                 luma_alpha_image.width(),
                 luma_alpha_image.height(),
-                FastBlurChannels::Plane,
+                FastBlurChannels::Channels2,
             );
-            let mut alpha_plane = BlurImageMut::alloc(
+            let mut new_image = BlurImageMut::alloc(
                 luma_alpha_image.width(),
                 luma_alpha_image.height(),
-                FastBlurChannels::Plane,
+                FastBlurChannels::Channels2,
             );
-            let raw_buffer = luma_alpha_image.as_raw();
-
-            for ((intensity, alpha), raw_buffer) in intensity_plane
-                .data
-                .borrow_mut()
-                .iter_mut()
-                .zip(alpha_plane.data.borrow_mut().iter_mut())
-                .zip(raw_buffer.chunks_exact(2))
-            {
-                *intensity = raw_buffer[0];
-                *alpha = raw_buffer[1];
-            }
-
-            let int = intensity_plane.to_immutable_ref();
-            let alp = alpha_plane.to_immutable_ref();
-
-            let mut new_intensity_plane = BlurImageMut::alloc(
-                luma_alpha_image.width(),
-                luma_alpha_image.height(),
-                FastBlurChannels::Plane,
-            );
-            let mut new_alpha_plane = BlurImageMut::alloc(
-                luma_alpha_image.width(),
-                luma_alpha_image.height(),
-                FastBlurChannels::Plane,
-            );
-
-            gaussian_blur(
-                &int,
-                &mut new_intensity_plane,
-                params,
-                edge_mode,
-                threading_policy,
-                precise_level,
-            )
-            .unwrap();
 
             gaussian_blur(
-                &alp,
-                &mut new_alpha_plane,
+                &gray_image,
+                &mut new_image,
                 params,
                 edge_mode,
                 threading_policy,
@@ -136,27 +101,10 @@ pub fn gaussian_blur_image(
             )
             .unwrap();
 
-            let mut new_raw_buffer =
-                vec![
-                    0u8;
-                    luma_alpha_image.width() as usize * luma_alpha_image.height() as usize * 2
-                ];
-
-            for ((intensity, alpha), raw_buffer) in new_intensity_plane
-                .data
-                .borrow()
-                .iter()
-                .zip(new_alpha_plane.data.borrow().iter())
-                .zip(new_raw_buffer.chunks_exact_mut(2))
-            {
-                raw_buffer[0] = *intensity;
-                raw_buffer[1] = *alpha;
-            }
-
             let new_gray_image = GrayAlphaImage::from_raw(
                 luma_alpha_image.width(),
                 luma_alpha_image.height(),
-                new_raw_buffer,
+                new_image.data.borrow().to_vec(),
             )?;
             Some(DynamicImage::ImageLumaA8(new_gray_image))
         }
@@ -222,56 +170,21 @@ pub fn gaussian_blur_image(
             Some(DynamicImage::ImageLuma16(new_rgb_image))
         }
         DynamicImage::ImageLumaA16(luma_alpha_image) => {
-            let mut intensity_plane = BlurImageMut::alloc(
+            let gray_image = BlurImage::borrow(
+                &luma_alpha_image,
                 luma_alpha_image.width(),
                 luma_alpha_image.height(),
-                FastBlurChannels::Plane,
+                FastBlurChannels::Channels2,
             );
-            let mut alpha_plane = BlurImageMut::alloc(
+            let mut new_image = BlurImageMut::alloc(
                 luma_alpha_image.width(),
                 luma_alpha_image.height(),
-                FastBlurChannels::Plane,
+                FastBlurChannels::Channels2,
             );
-            let raw_buffer = luma_alpha_image.as_raw();
-
-            for ((intensity, alpha), raw_buffer) in intensity_plane
-                .data
-                .borrow_mut()
-                .iter_mut()
-                .zip(alpha_plane.data.borrow_mut().iter_mut())
-                .zip(raw_buffer.chunks_exact(2))
-            {
-                *intensity = raw_buffer[0];
-                *alpha = raw_buffer[1];
-            }
-
-            let int = intensity_plane.to_immutable_ref();
-            let alp = alpha_plane.to_immutable_ref();
-
-            let mut new_intensity_plane = BlurImageMut::alloc(
-                luma_alpha_image.width(),
-                luma_alpha_image.height(),
-                FastBlurChannels::Plane,
-            );
-            let mut new_alpha_plane = BlurImageMut::alloc(
-                luma_alpha_image.width(),
-                luma_alpha_image.height(),
-                FastBlurChannels::Plane,
-            );
-
-            gaussian_blur_u16(
-                &int,
-                &mut new_intensity_plane,
-                params,
-                edge_mode,
-                threading_policy,
-                precise_level,
-            )
-            .unwrap();
 
             gaussian_blur_u16(
-                &alp,
-                &mut new_alpha_plane,
+                &gray_image,
+                &mut new_image,
                 params,
                 edge_mode,
                 threading_policy,
@@ -279,27 +192,10 @@ pub fn gaussian_blur_image(
             )
             .unwrap();
 
-            let mut new_raw_buffer =
-                vec![
-                    0u16;
-                    luma_alpha_image.width() as usize * luma_alpha_image.height() as usize * 2
-                ];
-
-            for ((intensity, alpha), raw_buffer) in new_intensity_plane
-                .data
-                .borrow()
-                .iter()
-                .zip(new_alpha_plane.data.borrow().iter())
-                .zip(new_raw_buffer.chunks_exact_mut(2))
-            {
-                raw_buffer[0] = *intensity;
-                raw_buffer[1] = *alpha;
-            }
-
             let new_gray_image = ImageBuffer::<LumaA<u16>, Vec<u16>>::from_raw(
                 luma_alpha_image.width(),
                 luma_alpha_image.height(),
-                new_raw_buffer,
+                new_image.data.borrow().to_vec(),
             )?;
             Some(DynamicImage::ImageLumaA16(new_gray_image))
         }
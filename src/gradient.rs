@@ -0,0 +1,501 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::{
+    filter_1d_exact, BlurError, BlurImage, BlurImageMut, BufferStore, EdgeMode, FastBlurChannels,
+    Scalar, ThreadingPolicy,
+};
+use num_traits::AsPrimitive;
+use std::fmt::Debug;
+
+/// Derivative kernel
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default)]
+pub enum GradientKernel {
+    /// Sobel 3x3
+    #[default]
+    Sobel3 = 0,
+    /// Sobel 5x5
+    Sobel5 = 1,
+    /// Sobel 7x7
+    Sobel7 = 2,
+    /// Scharr 3x3, supports only first order derivative along one axis
+    Scharr = 3,
+}
+
+/// Norm used for gradient magnitude
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default)]
+pub enum GradientNorm {
+    /// `|dx| + |dy|`
+    L1 = 0,
+    /// `sqrt(dx^2 + dy^2)`
+    #[default]
+    L2 = 1,
+}
+
+/// Derivative parameters, order along each axis and kernel.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct GradientParams {
+    pub x_order: u32,
+    pub y_order: u32,
+    pub kernel: GradientKernel,
+}
+
+impl GradientParams {
+    pub fn new(x_order: u32, y_order: u32, kernel: GradientKernel) -> GradientParams {
+        GradientParams {
+            x_order,
+            y_order,
+            kernel,
+        }
+    }
+
+    /// First order derivative along X axis
+    pub fn dx(kernel: GradientKernel) -> GradientParams {
+        GradientParams::new(1, 0, kernel)
+    }
+
+    /// First order derivative along Y axis
+    pub fn dy(kernel: GradientKernel) -> GradientParams {
+        GradientParams::new(0, 1, kernel)
+    }
+
+    /// Returns row and column kernels
+    fn kernels(&self) -> Result<(Vec<f32>, Vec<f32>), BlurError> {
+        if self.x_order + self.y_order == 0 {
+            return Err(BlurError::InvalidArguments);
+        }
+        let size = match self.kernel {
+            GradientKernel::Sobel3 => 3,
+            GradientKernel::Sobel5 => 5,
+            GradientKernel::Sobel7 => 7,
+            GradientKernel::Scharr => {
+                return match (self.x_order, self.y_order) {
+                    (1, 0) => Ok((vec![-1., 0., 1.], vec![3., 10., 3.])),
+                    (0, 1) => Ok((vec![3., 10., 3.], vec![-1., 0., 1.])),
+                    _ => Err(BlurError::InvalidArguments),
+                };
+            }
+        };
+        if self.x_order >= size || self.y_order >= size {
+            return Err(BlurError::InvalidArguments);
+        }
+        Ok((
+            derivative_kernel(size as usize, self.x_order as usize),
+            derivative_kernel(size as usize, self.y_order as usize),
+        ))
+    }
+}
+
+/// Binomial smoothing convolved with central differences, matches OpenCV `getDerivKernels`.
+pub(crate) fn derivative_kernel(size: usize, order: usize) -> Vec<f32> {
+    let mut kernel = vec![1f32];
+    let mut convolve = |taps: [f32; 2]| {
+        let mut next = vec![0f32; kernel.len() + 1];
+        for (i, &v) in kernel.iter().enumerate() {
+            next[i] += v * taps[0];
+            next[i + 1] += v * taps[1];
+        }
+        kernel = next;
+    };
+    for _ in 0..size - order - 1 {
+        convolve([1., 1.]);
+    }
+    for _ in 0..order {
+        convolve([-1., 1.]);
+    }
+    kernel
+}
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for u8 {}
+    impl Sealed for u16 {}
+    impl Sealed for i16 {}
+    impl Sealed for f32 {}
+}
+
+/// Storage for derivatives, integer storages round and saturate.
+///
+/// Sealed, implemented for `u8`, `u16`, `i16` and `f32`.
+pub trait GradientStorage:
+    private::Sealed + Copy + Default + Debug + Send + Sync + 'static
+{
+    /// Value representing zero when signed result is offset-encoded,
+    /// middle of the range for unsigned storages.
+    const ZERO_OFFSET: f32;
+
+    fn from_f32(value: f32) -> Self;
+}
+
+impl GradientStorage for u8 {
+    const ZERO_OFFSET: f32 = 128.;
+
+    #[inline(always)]
+    fn from_f32(value: f32) -> Self {
+        value.round() as u8
+    }
+}
+
+impl GradientStorage for u16 {
+    const ZERO_OFFSET: f32 = 32768.;
+
+    #[inline(always)]
+    fn from_f32(value: f32) -> Self {
+        value.round() as u16
+    }
+}
+
+impl GradientStorage for i16 {
+    const ZERO_OFFSET: f32 = 0.;
+
+    #[inline(always)]
+    fn from_f32(value: f32) -> Self {
+        value.round() as i16
+    }
+}
+
+impl GradientStorage for f32 {
+    const ZERO_OFFSET: f32 = 0.;
+
+    #[inline(always)]
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+}
+
+pub(crate) fn to_f32_image<T: Copy + Default + Debug + AsPrimitive<f32>>(
+    src: &BlurImage<T>,
+) -> BlurImageMut<'static, f32> {
+    let mut dst = BlurImageMut::alloc(src.width, src.height, src.channels);
+    let row_length = src.width as usize * src.channels.channels();
+    let dst_stride = dst.row_stride() as usize;
+    for (src, dst) in src
+        .data
+        .chunks(src.row_stride() as usize)
+        .zip(dst.data.borrow_mut().chunks_exact_mut(dst_stride))
+    {
+        for (&src, dst) in src[..row_length].iter().zip(dst.iter_mut()) {
+            *dst = src.as_();
+        }
+    }
+    dst
+}
+
+/// Separable convolution of `f32` image into newly allocated image
+pub(crate) fn separable_f32(
+    src: &BlurImage<f32>,
+    row_kernel: &[f32],
+    column_kernel: &[f32],
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<BlurImageMut<'static, f32>, BlurError> {
+    let mut dst = BlurImageMut::alloc(src.width, src.height, src.channels);
+    let executor = match src.channels {
+        FastBlurChannels::Plane => filter_1d_exact::<f32, f32, 1>,
+        FastBlurChannels::Channels2 => filter_1d_exact::<f32, f32, 2>,
+        FastBlurChannels::Channels3 => filter_1d_exact::<f32, f32, 3>,
+        FastBlurChannels::Channels4 => filter_1d_exact::<f32, f32, 4>,
+    };
+    executor(
+        src,
+        &mut dst,
+        row_kernel,
+        column_kernel,
+        border_mode,
+        border_constant,
+        threading_policy,
+    )?;
+    Ok(dst)
+}
+
+fn derivative_of_f32(
+    src: &BlurImage<f32>,
+    params: GradientParams,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<BlurImageMut<'static, f32>, BlurError> {
+    let (row_kernel, column_kernel) = params.kernels()?;
+    separable_f32(
+        src,
+        &row_kernel,
+        &column_kernel,
+        border_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Computes first order derivatives along both axes in `f32`
+pub(crate) fn gradient_xy<T: Copy + Default + Debug + AsPrimitive<f32>>(
+    src: &BlurImage<T>,
+    kernel: GradientKernel,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(BlurImageMut<'static, f32>, BlurImageMut<'static, f32>), BlurError> {
+    src.check_layout()?;
+    let working = to_f32_image(src);
+    let working = working.to_immutable_ref();
+    let dx = derivative_of_f32(
+        &working,
+        GradientParams::dx(kernel),
+        border_mode,
+        border_constant,
+        threading_policy,
+    )?;
+    let dy = derivative_of_f32(
+        &working,
+        GradientParams::dy(kernel),
+        border_mode,
+        border_constant,
+        threading_policy,
+    )?;
+    Ok((dx, dy))
+}
+
+/// Writes `op(dx, dy)` into destination
+pub(crate) fn write_combined<D: Copy + Default + Debug>(
+    dx: &BlurImageMut<f32>,
+    dy: &BlurImageMut<f32>,
+    dst: &mut BlurImageMut<D>,
+    op: impl Fn(f32, f32) -> D,
+) {
+    let row_length = dx.width as usize * dx.channels.channels();
+    let dst_stride = dst.row_stride() as usize;
+    for ((dx, dy), dst) in dx
+        .data
+        .borrow()
+        .chunks_exact(dx.row_stride() as usize)
+        .zip(dy.data.borrow().chunks_exact(dy.row_stride() as usize))
+        .zip(dst.data.borrow_mut().chunks_mut(dst_stride))
+    {
+        for ((&dx, &dy), dst) in dx.iter().zip(dy.iter()).zip(dst[..row_length].iter_mut()) {
+            *dst = op(dx, dy);
+        }
+    }
+}
+
+pub(crate) fn prepare_destination<T: Copy + Default + Debug, D: Copy + Default + Debug>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<D>,
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    if matches!(dst.data, BufferStore::Owned(_)) {
+        dst.resize(src.width, src.height, src.channels);
+    }
+    dst.check_layout(None)?;
+    if dst.width != src.width || dst.height != src.height || dst.channels != src.channels {
+        return Err(BlurError::ImagesMustMatch);
+    }
+    Ok(())
+}
+
+/// Computes signed image derivative.
+///
+/// Unlike [crate::sobel] negative responses are preserved, destination
+/// could be `i16` or `f32`, `i16` saturates on overflow.
+/// Unsigned destinations store derivative offset by [GradientStorage::ZERO_OFFSET],
+/// so zero is `128` for `u8` and `32768` for `u16`.
+///
+/// # Arguments
+///
+/// * `src` - Source image, `u8`, `u16` or `f32`, see [BlurImage] for more info.
+/// * `dst` - Signed destination image, see [BlurImageMut] for more info.
+/// * `params` - Derivative order and kernel, see [GradientParams] for more info.
+/// * `border_mode` - Border handling mode see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn gradient_derivative<T, D>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<D>,
+    params: GradientParams,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + AsPrimitive<f32>,
+    D: GradientStorage,
+{
+    prepare_destination(src, dst)?;
+    let working = to_f32_image(src);
+    let derivative = derivative_of_f32(
+        &working.to_immutable_ref(),
+        params,
+        border_mode,
+        border_constant,
+        threading_policy,
+    )?;
+    write_combined(&derivative, &derivative, dst, |v, _| {
+        D::from_f32(v + D::ZERO_OFFSET)
+    });
+    Ok(())
+}
+
+/// Computes gradient magnitude from first order derivatives.
+///
+/// # Arguments
+///
+/// * `src` - Source image, `u8`, `u16` or `f32`, see [BlurImage] for more info.
+/// * `dst` - Destination image, see [BlurImageMut] for more info.
+/// * `kernel` - Derivative kernel, see [GradientKernel] for more info.
+/// * `norm` - See [GradientNorm] for more info.
+/// * `border_mode` - Border handling mode see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn gradient_magnitude<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<f32>,
+    kernel: GradientKernel,
+    norm: GradientNorm,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + AsPrimitive<f32>,
+{
+    prepare_destination(src, dst)?;
+    let (dx, dy) = gradient_xy(src, kernel, border_mode, border_constant, threading_policy)?;
+    match norm {
+        GradientNorm::L1 => write_combined(&dx, &dy, dst, |dx, dy| dx.abs() + dy.abs()),
+        GradientNorm::L2 => write_combined(&dx, &dy, dst, |dx, dy| dx.hypot(dy)),
+    }
+    Ok(())
+}
+
+/// Computes gradient orientation from first order derivatives.
+///
+/// Orientation is `atan2(dy, dx)` in radians in range `[-PI, PI]`.
+///
+/// # Arguments
+///
+/// * `src` - Source image, `u8`, `u16` or `f32`, see [BlurImage] for more info.
+/// * `dst` - Destination image, see [BlurImageMut] for more info.
+/// * `kernel` - Derivative kernel, see [GradientKernel] for more info.
+/// * `border_mode` - Border handling mode see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn gradient_orientation<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<f32>,
+    kernel: GradientKernel,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + AsPrimitive<f32>,
+{
+    prepare_destination(src, dst)?;
+    let (dx, dy) = gradient_xy(src, kernel, border_mode, border_constant, threading_policy)?;
+    write_combined(&dx, &dy, dst, |dx, dy| dy.atan2(dx));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derivative_kernels() {
+        assert_eq!(derivative_kernel(3, 1), [-1., 0., 1.]);
+        assert_eq!(derivative_kernel(3, 2), [1., -2., 1.]);
+        assert_eq!(derivative_kernel(5, 0), [1., 4., 6., 4., 1.]);
+        assert_eq!(derivative_kernel(5, 1), [-1., -2., 0., 2., 1.]);
+        assert!(GradientParams::new(1, 1, GradientKernel::Scharr)
+            .kernels()
+            .is_err());
+        assert!(GradientParams::new(3, 0, GradientKernel::Sobel3)
+            .kernels()
+            .is_err());
+    }
+
+    #[test]
+    fn test_gradient_signed_ramp() {
+        let (width, height) = (16usize, 8usize);
+        let src: Vec<u8> = (0..width * height)
+            .map(|i| (255 - i % width * 10) as u8)
+            .collect();
+        let src_image =
+            BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        let mut dx = BlurImageMut::<i16>::default();
+        gradient_derivative(
+            &src_image,
+            &mut dx,
+            GradientParams::dx(GradientKernel::Sobel3),
+            EdgeMode::Reflect101,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        // Decreasing ramp, 10 per pixel, Sobel 3x3 scales difference by 8
+        assert_eq!(dx.data.borrow()[3 * width + 5], -80);
+        let mut offset_dx = BlurImageMut::<u8>::default();
+        gradient_derivative(
+            &src_image,
+            &mut offset_dx,
+            GradientParams::dx(GradientKernel::Sobel3),
+            EdgeMode::Reflect101,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        assert_eq!(offset_dx.data.borrow()[3 * width + 5], 128 - 80);
+        let mut magnitude = BlurImageMut::default();
+        gradient_magnitude(
+            &src_image,
+            &mut magnitude,
+            GradientKernel::Scharr,
+            GradientNorm::L2,
+            EdgeMode::Reflect101,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        assert_eq!(magnitude.data.borrow()[3 * width + 5], 320.);
+        let mut orientation = BlurImageMut::default();
+        gradient_orientation(
+            &src_image,
+            &mut orientation,
+            GradientKernel::Sobel5,
+            EdgeMode::Reflect101,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let angle = orientation.data.borrow()[3 * width + 7];
+        assert!((angle - std::f32::consts::PI).abs() < 1e-5);
+    }
+}
@@ -429,6 +429,8 @@ impl<'a, T: Clone + Copy + Default + Debug> BlurImageMut<'a, T> {
             FastBlurChannels::Channels4
         } else if cn == 3 {
             FastBlurChannels::Channels3
+        } else if cn == 2 {
+            FastBlurChannels::Channels2
         } else {
             FastBlurChannels::Plane
         };
@@ -27,46 +27,186 @@
  * OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
 
+use crate::gaussian::kernel_size;
+use crate::gradient::{
+    derivative_kernel, prepare_destination, separable_f32, to_f32_image, write_combined,
+};
 use crate::{
-    filter_2d, sigma_size, BlurError, BlurImage, BlurImageMut, EdgeMode, KernelShape, Scalar,
-    ThreadingPolicy,
+    filter_2d, gaussian_kernel_1d, gaussian_second_derivative_kernel_1d, sigma_size, BlurError,
+    BlurImage, BlurImageMut, EdgeMode, GradientStorage, KernelShape, Scalar, ThreadingPolicy,
 };
+use num_traits::AsPrimitive;
+use std::fmt::Debug;
 
-pub fn laplacian_kernel(size: usize) -> Vec<f32> {
-    if size & 1 == 0 {
-        panic!("Kernel size must be odd in 'get_laplacian_kernel'!");
-    }
-    let center_x = (size / 2) as f32;
-    let center_y = (size / 2) as f32;
-    let sigma = sigma_size(size as f32);
-
-    let mut kernel = vec![f32::default(); size * size];
+/// Laplacian kernel
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LaplacianKernel {
+    /// Discrete Laplacian with odd aperture size up to 31,
+    /// `1` is 4-neighbour cross, larger apertures are sum of second order Sobel derivatives.
+    Aperture(u32),
+    /// Laplacian of Gaussian. If kernel size is `0` it is computed from sigma,
+    /// if sigma is `<= 0` it is computed from kernel size. Explicit kernel size must be at least 3.
+    Gaussian { kernel_size: u32, sigma: f32 },
+}
 
-    let sigma_p_2 = sigma * sigma;
+/// Laplacian filter parameters
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LaplacianParams {
+    pub kernel: LaplacianKernel,
+    /// Result is multiplied by scale.
+    pub scale: f32,
+    /// Added to result after scaling.
+    pub delta: f32,
+    /// Stores absolute value of scaled result.
+    pub absolute: bool,
+}
 
-    let scale = -1. / (std::f32::consts::PI * sigma_p_2 * sigma_p_2);
+impl LaplacianParams {
+    pub fn new(kernel: LaplacianKernel) -> LaplacianParams {
+        LaplacianParams {
+            kernel,
+            scale: 1.,
+            delta: 0.,
+            absolute: false,
+        }
+    }
 
-    let mut sum = 0f32;
+    /// Laplacian of Gaussian with kernel size derived from sigma
+    pub fn gaussian(sigma: f32) -> LaplacianParams {
+        LaplacianParams::new(LaplacianKernel::Gaussian {
+            kernel_size: 0,
+            sigma,
+        })
+    }
 
-    for y in 0..size {
-        for x in 0..size {
-            let dx = x as f32 - center_x;
-            let dy = y as f32 - center_y;
-            let der = -((dx * dx + dy * dy) / (2. * sigma_p_2));
-            let v = scale * (1. + der) * f32::exp(der);
-            kernel[y * size + x] = v;
-            sum += v;
+    /// Row and column kernels for `d2/dx2` term, `d2/dy2` uses them swapped.
+    fn kernels(&self) -> Result<(Vec<f32>, Vec<f32>), BlurError> {
+        match self.kernel {
+            LaplacianKernel::Aperture(size) => {
+                if size % 2 == 0 {
+                    return Err(BlurError::OddKernel(size as usize));
+                }
+                if size > 31 {
+                    return Err(BlurError::InvalidArguments);
+                }
+                if size == 1 {
+                    Ok((vec![1., -2., 1.], vec![0., 1., 0.]))
+                } else {
+                    Ok((
+                        derivative_kernel(size as usize, 2),
+                        derivative_kernel(size as usize, 0),
+                    ))
+                }
+            }
+            LaplacianKernel::Gaussian { kernel_size, sigma } => {
+                let (size, sigma) = log_size_sigma(kernel_size as usize, sigma)?;
+                log_kernels_1d(size, sigma)
+            }
         }
     }
+}
 
-    if sum != 0. {
-        let scale = 1. / sum;
+fn log_size_sigma(size: usize, sigma: f32) -> Result<(usize, f32), BlurError> {
+    if size == 0 && sigma <= 0. {
+        return Err(BlurError::NegativeOrZeroSigma);
+    }
+    let size = if size == 0 {
+        kernel_size(sigma) as usize
+    } else {
+        size
+    };
+    if size % 2 == 0 {
+        return Err(BlurError::OddKernel(size));
+    }
+    let sigma = if sigma <= 0. {
+        sigma_size(size as f32)
+    } else {
+        sigma
+    };
+    Ok((size, sigma))
+}
 
-        for item in kernel.iter_mut() {
-            *item *= scale;
+/// Second derivative of Gaussian and Gaussian itself
+fn log_kernels_1d(size: usize, sigma: f32) -> Result<(Vec<f32>, Vec<f32>), BlurError> {
+    Ok((
+        gaussian_second_derivative_kernel_1d(size as u32, sigma)?,
+        gaussian_kernel_1d(size as u32, sigma),
+    ))
+}
+
+/// Builds 2D Laplacian of Gaussian kernel, kernel sums to zero.
+///
+/// Sigma is derived from kernel size, size must be odd and at least 3.
+pub fn laplacian_kernel(size: usize) -> Result<Vec<f32>, BlurError> {
+    let (size, sigma) = log_size_sigma(size, 0.)?;
+    let (second, gaussian) = log_kernels_1d(size, sigma)?;
+    let mut kernel = vec![0f32; size * size];
+    for (y, row) in kernel.chunks_exact_mut(size).enumerate() {
+        for (x, dst) in row.iter_mut().enumerate() {
+            *dst = second[x] * gaussian[y] + gaussian[x] * second[y];
         }
     }
-    kernel
+    Ok(kernel)
+}
+
+/// Applies Laplacian or Laplacian of Gaussian.
+///
+/// Result is `|laplacian * scale + delta|` when absolute is set and
+/// `laplacian * scale + delta` otherwise, use `i16` or `f32` destination to keep sign.
+/// Without absolute `u8` and `u16` destinations store result offset by
+/// [GradientStorage::ZERO_OFFSET], so zero is `128` for `u8` and `32768` for `u16`.
+///
+/// # Arguments
+///
+/// * `src` - Source image, `u8`, `u16` or `f32`, see [BlurImage] for more info.
+/// * `dst` - Destination image `i16`, `f32`, `u8` or `u16`, see [BlurImageMut] for more info.
+/// * `params` - See [LaplacianParams] for more info.
+/// * `border_mode` - Border handling mode see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn laplacian_filter<T, D>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<D>,
+    params: LaplacianParams,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + AsPrimitive<f32>,
+    D: GradientStorage,
+{
+    let (second, smoothing) = params.kernels()?;
+    prepare_destination(src, dst)?;
+    let working = to_f32_image(src);
+    let working = working.to_immutable_ref();
+    let dxx = separable_f32(
+        &working,
+        &second,
+        &smoothing,
+        border_mode,
+        border_constant,
+        threading_policy,
+    )?;
+    let dyy = separable_f32(
+        &working,
+        &smoothing,
+        &second,
+        border_mode,
+        border_constant,
+        threading_policy,
+    )?;
+    let (scale, delta) = (params.scale, params.delta);
+    if params.absolute {
+        write_combined(&dxx, &dyy, dst, |a, b| {
+            D::from_f32(((a + b) * scale + delta).abs())
+        });
+    } else {
+        write_combined(&dxx, &dyy, dst, |a, b| {
+            D::from_f32((a + b) * scale + delta + D::ZERO_OFFSET)
+        });
+    }
+    Ok(())
 }
 
 /// Performs laplacian of gaussian on the image
@@ -102,3 +242,99 @@ pub fn laplacian(
         threading_policy,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FastBlurChannels;
+
+    #[test]
+    fn test_laplacian_kernel() {
+        assert!(matches!(laplacian_kernel(4), Err(BlurError::OddKernel(4))));
+        let kernel = laplacian_kernel(7).unwrap();
+        assert!(kernel.iter().sum::<f32>().abs() < 1e-5);
+        assert!(kernel[3 * 7 + 3] < 0.);
+    }
+
+    #[test]
+    fn test_laplacian_filter_signed() {
+        let (width, height) = (16usize, 16usize);
+        // f(x, y) = x^2 + y^2, Laplacian is 4 everywhere
+        let src: Vec<f32> = (0..width * height)
+            .map(|i| ((i % width).pow(2) + (i / width).pow(2)) as f32)
+            .collect();
+        let image = BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        for kernel in [
+            LaplacianKernel::Aperture(1),
+            LaplacianKernel::Gaussian {
+                kernel_size: 5,
+                sigma: 1.,
+            },
+        ] {
+            let mut dst = BlurImageMut::<f32>::default();
+            laplacian_filter(
+                &image,
+                &mut dst,
+                LaplacianParams::new(kernel),
+                EdgeMode::Clamp,
+                Scalar::default(),
+                ThreadingPolicy::Single,
+            )
+            .unwrap();
+            let value = dst.data.borrow()[8 * width + 8];
+            assert!((value - 4.).abs() < 1e-3, "{kernel:?} gave {value}");
+        }
+        let negated: Vec<u8> = src.iter().map(|&x| (255. - x.min(255.)) as u8).collect();
+        let image = BlurImage::borrow(
+            &negated,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Plane,
+        );
+        let mut dst = BlurImageMut::<i16>::default();
+        laplacian_filter(
+            &image,
+            &mut dst,
+            LaplacianParams::new(LaplacianKernel::Aperture(3)),
+            EdgeMode::Clamp,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        // Aperture 3 scales Laplacian by 4
+        assert_eq!(dst.data.borrow()[5 * width + 5], -16);
+        let mut offset_dst = BlurImageMut::<u8>::default();
+        laplacian_filter(
+            &image,
+            &mut offset_dst,
+            LaplacianParams::new(LaplacianKernel::Aperture(3)),
+            EdgeMode::Clamp,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        assert_eq!(offset_dst.data.borrow()[5 * width + 5], 128 - 16);
+        assert!(matches!(
+            laplacian_filter(
+                &image,
+                &mut dst,
+                LaplacianParams::new(LaplacianKernel::Aperture(2)),
+                EdgeMode::Clamp,
+                Scalar::default(),
+                ThreadingPolicy::Single,
+            ),
+            Err(BlurError::OddKernel(2))
+        ));
+        assert!(matches!(
+            laplacian_filter(
+                &image,
+                &mut dst,
+                LaplacianParams::new(LaplacianKernel::Aperture(33)),
+                EdgeMode::Clamp,
+                Scalar::default(),
+                ThreadingPolicy::Single,
+            ),
+            Err(BlurError::InvalidArguments)
+        ));
+    }
+}
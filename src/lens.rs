@@ -28,14 +28,26 @@
  */
 use crate::{BlurError, KernelShape};
 
+/// Number of coverage samples per pixel on each axis
+const COVERAGE_SAMPLES: usize = 8;
+
 /// Creates lens kernel
 ///
+/// Aperture is inscribed into the kernel shape, so non-square shapes produce elliptic apertures.
+/// Edges are anti-aliased, each weight is the pixel coverage estimated
+/// on 8x8 subpixel grid. Kernel is normalized to sum 1.
+///
+/// Aperture radius is `width / 2` and `height / 2`, earlier versions used
+/// `(width - 1) / 2 - 1` on both axes with binary edges, so kernels are not identical
+/// to those produced before.
+///
 /// # Arguments
 ///
 /// * `shape`: Kernel shape must be always odd.
-/// * `n`: n is number of diaphragm blades
+/// * `n`: n is number of diaphragm blades, must be positive
 /// * `m`: is the concavity, aka the number of vertices on straight lines
 /// * `k`: is the roundness vs. linearity factor; should be in [-1; 1]
+/// * `rotation`: Aperture rotation in radians.
 pub fn lens_kernel(
     shape: KernelShape,
     n: f32,
@@ -52,23 +64,46 @@ pub fn lens_kernel(
     if shape.height % 2 == 0 {
         return Err(BlurError::OddKernel(shape.height));
     }
-    assert!(k.abs() <= 1., "Roundness contract is not satisfied");
-    let eps = 1f32 / shape.width as f32;
-    let radius = (shape.width as f32 - 1.) / 2. - 1.;
+    if !n.is_finite() || n <= 0. || !m.is_finite() || !rotation.is_finite() {
+        return Err(BlurError::InvalidArguments);
+    }
+    if k.is_nan() || k.abs() > 1. {
+        return Err(BlurError::InvalidArguments);
+    }
+
+    let radius_x = shape.width as f32 / 2.;
+    let radius_y = shape.height as f32 / 2.;
+    let center_x = (shape.width - 1) as f32 / 2.;
+    let center_y = (shape.height - 1) as f32 / 2.;
+    let numerator = f32::cos((2. * f32::asin(k) + std::f32::consts::PI * m) / (2. * n));
+
+    // Aperture boundary radius in normalized coordinates for the given direction
+    let boundary = |theta: f32| -> f32 {
+        let denominator = f32::cos(
+            (2. * f32::asin(k * f32::cos(n * (theta + rotation))) + std::f32::consts::PI * m)
+                / (2. * n),
+        );
+        numerator / denominator
+    };
+
+    let step = 1. / COVERAGE_SAMPLES as f32;
+    let sample_weight = step * step;
     let mut new_buffer = vec![0f32; shape.width * shape.height];
 
     for (j, row) in new_buffer.chunks_exact_mut(shape.width).enumerate() {
         for (i, dst) in row.iter_mut().enumerate() {
-            let x = (i as f32 - 1.) / radius - 1.;
-            let y = (j as f32 - 1.) / radius - 1.;
-            let r = x.hypot(y);
-            let m = f32::cos((2f32 * f32::asin(k) + std::f32::consts::PI * m) / (2. * n))
-                / f32::cos(
-                    (2. * f32::asin(k * f32::cos(n * (f32::atan2(y, x) + rotation)))
-                        + std::f32::consts::PI * m)
-                        / (2. * n),
-                );
-            *dst = if m >= r + eps { 1. } else { 0. };
+            let mut coverage = 0f32;
+            for sy in 0..COVERAGE_SAMPLES {
+                let y = (j as f32 - center_y - 0.5 + (sy as f32 + 0.5) * step) / radius_y;
+                for sx in 0..COVERAGE_SAMPLES {
+                    let x = (i as f32 - center_x - 0.5 + (sx as f32 + 0.5) * step) / radius_x;
+                    // Vanishing denominator gives infinite boundary, sample is inside
+                    if boundary(f32::atan2(y, x)) >= x.hypot(y) {
+                        coverage += sample_weight;
+                    }
+                }
+            }
+            *dst = coverage;
         }
     }
     let sum = new_buffer.iter().sum::<f32>();
@@ -78,3 +113,32 @@ pub fn lens_kernel(
     }
     Ok(new_buffer)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lens_kernel_non_square() {
+        let (width, height) = (31usize, 15usize);
+        let kernel = lens_kernel(KernelShape::new(width, height), 6., 0., 0., 0.).unwrap();
+        assert!((kernel.iter().sum::<f32>() - 1.).abs() < 1e-4);
+        let at = |x: usize, y: usize| kernel[y * width + x];
+        // Elliptic aperture spans full width and height, edges are partially covered
+        let full = at(15, 7);
+        assert!(at(0, 7) > 0. && at(15, 0) > 0.);
+        assert_eq!(at(0, 0), 0.);
+        assert!(kernel.iter().any(|&v| v > 0. && v < full * 0.5));
+        assert!((at(3, 7) - at(27, 7)).abs() < 1e-6);
+        assert!((at(15, 2) - at(15, 12)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_lens_kernel_invalid() {
+        let shape = KernelShape::new(15, 15);
+        assert!(lens_kernel(shape, 6., 0., 1.5, 0.).is_err());
+        assert!(lens_kernel(shape, 0., 0., 0.5, 0.).is_err());
+        assert!(lens_kernel(shape, f32::NAN, 0., 0.5, 0.).is_err());
+        assert!(lens_kernel(KernelShape::new(15, 14), 6., 0., 0.5, 0.).is_err());
+    }
+}
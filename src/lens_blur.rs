@@ -0,0 +1,421 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::{
+    filter_2d_rgb_fft, filter_2d_rgba_fft, lens_kernel, BlurError, BlurImage, BlurImageMut,
+    EdgeMode, FastBlurChannels, KernelShape, Scalar, ThreadingPolicy, TransferFunction,
+};
+use novtb::{ParallelZonedIterator, TbSliceMut};
+use std::fmt::Debug;
+
+/// Lens blur parameters
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LensBlurParams {
+    /// Aperture radius, kernel size is `2 * radius + 1`.
+    pub radius: u32,
+    /// Number of diaphragm blades, see [crate::lens_kernel].
+    pub blades: f32,
+    /// Concavity of the blades, see [crate::lens_kernel].
+    pub concavity: f32,
+    /// Roundness vs. linearity factor, should be in [-1; 1], `0` gives circular aperture.
+    pub roundness: f32,
+    /// Aperture rotation in radians.
+    pub rotation: f32,
+    /// Exposure in stops, applied to linear light before highlight detection and reverted afterwards.
+    pub exposure: f32,
+    /// Linear luminance where highlight boost starts, should be in [0; 1).
+    pub highlight_threshold: f32,
+    /// Gain of the brightest highlights, `1` disables bokeh bloom.
+    pub highlight_boost: f32,
+    /// Transfer function of the image, see [TransferFunction] for more info.
+    pub transfer_function: TransferFunction,
+}
+
+impl LensBlurParams {
+    pub fn new(radius: u32) -> LensBlurParams {
+        LensBlurParams {
+            radius,
+            blades: 6.,
+            concavity: 0.,
+            roundness: 0.,
+            rotation: 0.,
+            exposure: 0.,
+            highlight_threshold: 0.8,
+            highlight_boost: 4.,
+            transfer_function: TransferFunction::Srgb,
+        }
+    }
+
+    fn validate(&self) -> Result<(), BlurError> {
+        // Aperture parameters are validated by lens kernel
+        if self.radius == 0
+            || !self.exposure.is_finite()
+            || !self.highlight_boost.is_finite()
+            || self.highlight_threshold.is_nan()
+            || self.highlight_threshold < 0.
+            || self.highlight_threshold >= 1.
+            || self.highlight_boost < 1.
+        {
+            return Err(BlurError::InvalidArguments);
+        }
+        Ok(())
+    }
+}
+
+/// Highlight boost curve applied to linear luminance.
+///
+/// Gain grows linearly from `1` at threshold to `boost` at `1`, so curve is continuous and
+/// strictly increasing, that makes it invertible.
+#[derive(Copy, Clone)]
+struct HighlightCurve {
+    threshold: f32,
+    boost: f32,
+}
+
+impl HighlightCurve {
+    #[inline]
+    fn gain(&self, luma: f32) -> f32 {
+        if luma <= self.threshold {
+            return 1.;
+        }
+        let t = ((luma - self.threshold) / (1. - self.threshold)).min(1.);
+        1. + (self.boost - 1.) * t
+    }
+
+    /// Returns luminance `l` such as `l * gain(l) == boosted`.
+    #[inline]
+    fn inverse(&self, boosted: f32) -> f32 {
+        if boosted <= self.threshold || self.boost == 1. {
+            return boosted;
+        }
+        if boosted >= self.boost {
+            return boosted / self.boost;
+        }
+        // l + a * l * (l - threshold) = boosted
+        let a = (self.boost - 1.) / (1. - self.threshold);
+        let b = 1. - a * self.threshold;
+        (-b + (b * b + 4. * a * boosted).sqrt()) / (2. * a)
+    }
+}
+
+#[inline]
+fn linear_luma(px: &[f32]) -> f32 {
+    0.2126 * px[0] + 0.7152 * px[1] + 0.0722 * px[2]
+}
+
+trait LensSample: Copy + Default + Debug + Send + Sync + 'static {
+    fn normalize(self) -> f32;
+
+    fn denormalize(value: f32) -> Self;
+}
+
+impl LensSample for u8 {
+    #[inline(always)]
+    fn normalize(self) -> f32 {
+        self as f32 * (1. / 255.)
+    }
+
+    #[inline(always)]
+    fn denormalize(value: f32) -> Self {
+        (value * 255.).round().clamp(0., 255.) as u8
+    }
+}
+
+impl LensSample for u16 {
+    #[inline(always)]
+    fn normalize(self) -> f32 {
+        self as f32 * (1. / 65535.)
+    }
+
+    #[inline(always)]
+    fn denormalize(value: f32) -> Self {
+        (value * 65535.).round().clamp(0., 65535.) as u16
+    }
+}
+
+impl LensSample for f32 {
+    #[inline(always)]
+    fn normalize(self) -> f32 {
+        self
+    }
+
+    #[inline(always)]
+    fn denormalize(value: f32) -> Self {
+        value
+    }
+}
+
+fn lens_blur_impl<T: LensSample>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    params: LensBlurParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    params.validate()?;
+    src.check_layout()?;
+    dst.check_layout(Some(src))?;
+    src.size_matches_mut(dst)?;
+    let cn = src.channels.channels();
+    if src.channels == FastBlurChannels::Plane || src.channels == FastBlurChannels::Channels2 {
+        return Err(BlurError::ChannelsNotSupported(cn));
+    }
+
+    let size = params.radius as usize * 2 + 1;
+    let kernel_shape = KernelShape::new(size, size);
+    let kernel = lens_kernel(
+        kernel_shape,
+        params.blades,
+        params.concavity,
+        params.roundness,
+        params.rotation,
+    )?;
+
+    let curve = HighlightCurve {
+        threshold: params.highlight_threshold,
+        boost: params.highlight_boost,
+    };
+    let exposure = params.exposure.exp2();
+    let transfer_function = params.transfer_function;
+    let has_alpha = cn == 4;
+
+    let thread_count = threading_policy.thread_count(src.width, src.height);
+    let pool = novtb::ThreadPool::new(thread_count);
+
+    let row_length = src.width as usize * cn;
+    let src_stride = src.row_stride() as usize;
+
+    let mut linear = BlurImageMut::<f32>::alloc(src.width, src.height, src.channels);
+    linear
+        .data
+        .borrow_mut()
+        .tb_par_chunks_exact_mut(row_length)
+        .for_each_enumerated(&pool, |y, row| {
+            let src = &src.data[y * src_stride..y * src_stride + row_length];
+            for (dst, src) in row.chunks_exact_mut(cn).zip(src.chunks_exact(cn)) {
+                for (dst, &src) in dst[..3].iter_mut().zip(src.iter()) {
+                    *dst = transfer_function.linearize(src.normalize()) * exposure;
+                }
+                let gain = curve.gain(linear_luma(dst));
+                // Color is premultiplied so transparent pixels do not bleed into the result
+                let alpha = if has_alpha { src[3].normalize() } else { 1. };
+                for v in dst[..3].iter_mut() {
+                    *v *= gain * alpha;
+                }
+                if has_alpha {
+                    dst[3] = alpha;
+                }
+            }
+        });
+
+    let mut blurred = BlurImageMut::<f32>::alloc(src.width, src.height, src.channels);
+    let linear = linear.to_immutable_ref();
+    if has_alpha {
+        filter_2d_rgba_fft::<f32, f32, f32>(
+            &linear,
+            &mut blurred,
+            &kernel,
+            kernel_shape,
+            edge_mode,
+            Scalar::default(),
+            threading_policy,
+        )?;
+    } else {
+        filter_2d_rgb_fft::<f32, f32, f32>(
+            &linear,
+            &mut blurred,
+            &kernel,
+            kernel_shape,
+            edge_mode,
+            Scalar::default(),
+            threading_policy,
+        )?;
+    }
+
+    let blurred_stride = blurred.row_stride() as usize;
+    let blurred = blurred.data.borrow();
+    let dst_stride = dst.row_stride() as usize;
+    let exposure_recip = 1. / exposure;
+
+    dst.data
+        .borrow_mut()
+        .tb_par_chunks_mut(dst_stride)
+        .for_each_enumerated(&pool, |y, row| {
+            let blurred = &blurred[y * blurred_stride..y * blurred_stride + row_length];
+            for (dst, src) in row[..row_length]
+                .chunks_exact_mut(cn)
+                .zip(blurred.chunks_exact(cn))
+            {
+                let mut px = [src[0], src[1], src[2]];
+                if has_alpha {
+                    let alpha = src[3];
+                    let recip = if alpha > 0. { 1. / alpha } else { 0. };
+                    px.iter_mut().for_each(|v| *v *= recip);
+                    dst[3] = T::denormalize(alpha.clamp(0., 1.));
+                }
+                let boosted = linear_luma(&px);
+                let scale = if boosted > 0. {
+                    curve.inverse(boosted) / boosted * exposure_recip
+                } else {
+                    exposure_recip
+                };
+                for (dst, &v) in dst[..3].iter_mut().zip(px.iter()) {
+                    *dst = T::denormalize(transfer_function.gamma((v * scale).max(0.)));
+                }
+            }
+        });
+    Ok(())
+}
+
+/// Simulates out-of-focus camera lens.
+///
+/// Image is linearized with [TransferFunction], highlights above threshold are boosted,
+/// then convolved with [crate::lens_kernel] aperture using FFT, boost is inverted
+/// and result is encoded back.
+/// Bright spots therefore produce visible aperture-shaped bokeh instead of dim smudges.
+/// RGBA images are convolved with premultiplied alpha.
+///
+/// # Arguments
+///
+/// * `src` - Source image, supports [FastBlurChannels::Channels3] and [FastBlurChannels::Channels4],
+///   see [BlurImage] for more info.
+/// * `dst` - Destination image, see [BlurImageMut] for more info.
+/// * `params` - See [LensBlurParams] for more info.
+/// * `edge_mode` - Border handling mode see [EdgeMode] for more info, constant border is transparent black.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn lens_blur(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    params: LensBlurParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    lens_blur_impl(src, dst, params, edge_mode, threading_policy)
+}
+
+/// Simulates out-of-focus camera lens on the 16-bit image.
+///
+/// See [lens_blur] for more info.
+pub fn lens_blur_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    params: LensBlurParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    lens_blur_impl(src, dst, params, edge_mode, threading_policy)
+}
+
+/// Simulates out-of-focus camera lens on the f32 image.
+///
+/// Values are expected to be in [0; 1] range.
+///
+/// See [lens_blur] for more info.
+pub fn lens_blur_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    params: LensBlurParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    lens_blur_impl(src, dst, params, edge_mode, threading_policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_curve_inverse() {
+        let curve = HighlightCurve {
+            threshold: 0.6,
+            boost: 5.,
+        };
+        for i in 0..=40 {
+            let luma = i as f32 / 20.;
+            let boosted = luma * curve.gain(luma);
+            assert!((curve.inverse(boosted) - luma).abs() < 1e-4, "{luma}");
+        }
+    }
+
+    #[test]
+    fn test_lens_blur_flat_and_bokeh() {
+        let (width, height) = (48usize, 48usize);
+        let mut src = vec![40u8; width * height * 3];
+        let mut params = LensBlurParams::new(6);
+        let image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let mut dst = BlurImageMut::default();
+        lens_blur(
+            &image,
+            &mut dst,
+            params,
+            EdgeMode::Clamp,
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        assert!(dst.data.borrow().iter().all(|&v| v.abs_diff(40) <= 1));
+
+        // Single highlight spreads to a disk, boosted one keeps more energy than plain blur
+        src[(24 * width + 24) * 3..][..3].copy_from_slice(&[255, 255, 255]);
+        let image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let mut boosted = BlurImageMut::default();
+        lens_blur(
+            &image,
+            &mut boosted,
+            params,
+            EdgeMode::Clamp,
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        params.highlight_boost = 1.;
+        let mut plain = BlurImageMut::default();
+        lens_blur(
+            &image,
+            &mut plain,
+            params,
+            EdgeMode::Clamp,
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let at =
+            |img: &BlurImageMut<u8>, x: usize, y: usize| img.data.borrow()[(y * width + x) * 3];
+        assert!(at(&boosted, 28, 24) > at(&plain, 28, 24));
+        assert_eq!(at(&boosted, 24, 28), at(&boosted, 24, 20));
+        assert!(at(&boosted, 24, 40).abs_diff(40) <= 1);
+    }
+}
@@ -69,11 +69,18 @@
 #[cfg(feature = "fft")]
 #[cfg_attr(docsrs, doc(cfg(feature = "fft")))]
 mod adaptive_blur;
+mod alpha;
+mod arbitrary_channels;
 #[cfg(all(target_arch = "x86_64", feature = "avx"))]
 mod avx;
 mod bilateral;
 mod box_filter;
+mod canny;
+#[cfg(feature = "capi")]
+#[cfg_attr(docsrs, doc(cfg(feature = "capi")))]
+pub mod capi;
 mod channels_configuration;
+mod difference_of_gaussians;
 mod edge_mode;
 mod fast_bilateral_filter;
 #[cfg(feature = "image")]
@@ -94,16 +101,27 @@ mod gaussian;
 #[cfg(feature = "image")]
 #[cfg_attr(docsrs, doc(cfg(feature = "image")))]
 mod gaussian_blur_image;
+mod gradient;
 mod image;
 mod image_linearization;
 mod img_size;
 mod laplacian;
 mod lens;
+#[cfg(feature = "fft")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fft")))]
+mod lens_blur;
 mod median_blur;
+mod median_blur_f32;
+mod median_blur_u16;
 mod mlaf;
+mod morphology;
 mod motion_blur;
 #[cfg(all(target_arch = "aarch64", feature = "neon"))]
 mod neon;
+mod planar;
+mod radial_blur;
+mod rank_filter;
+mod roi;
 mod safe_math;
 mod sobel;
 #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
@@ -112,23 +130,39 @@ mod sse;
 #[cfg_attr(docsrs, doc(cfg(feature = "image")))]
 mod stack_blur_image;
 mod stackblur;
+mod structure_tensor;
 mod threading_policy;
 mod to_storage;
 mod unsafe_slice;
+mod unsharp_mask;
 mod util;
+mod variable_blur;
 #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
 mod wasm32;
+mod yuv;
 
 #[cfg(feature = "fft")]
 #[cfg_attr(docsrs, doc(cfg(feature = "fft")))]
 pub use adaptive_blur::adaptive_blur;
+pub use alpha::{
+    box_blur_alpha, box_blur_alpha_f32, box_blur_alpha_u16, fast_gaussian_alpha,
+    fast_gaussian_alpha_f32, fast_gaussian_alpha_u16, filter_2d_rgba_alpha, gaussian_blur_alpha,
+    gaussian_blur_alpha_f32, gaussian_blur_alpha_u16, stack_blur_alpha, stack_blur_alpha_f32,
+    stack_blur_alpha_u16, AlphaMode,
+};
+pub use arbitrary_channels::{
+    box_blur_arbitrary, box_blur_arbitrary_f32, box_blur_arbitrary_u16, stack_blur_arbitrary,
+    stack_blur_arbitrary_f32, stack_blur_arbitrary_u16,
+};
 pub use bilateral::{bilateral_filter, BilateralBlurParams};
 pub use box_filter::{
     box_blur, box_blur_f32, box_blur_u16, gaussian_box_blur, gaussian_box_blur_f32,
-    gaussian_box_blur_u16, tent_blur, tent_blur_f32, tent_blur_u16, BoxBlurParameters,
-    CLTParameters,
+    gaussian_box_blur_u16, max_filter, max_filter_f32, max_filter_u16, min_filter, min_filter_f32,
+    min_filter_u16, tent_blur, tent_blur_f32, tent_blur_u16, BoxBlurParameters, CLTParameters,
 };
+pub use canny::{canny, CannyParams};
 pub use channels_configuration::FastBlurChannels;
+pub use difference_of_gaussians::difference_of_gaussians;
 pub use edge_mode::*;
 pub use fast_bilateral_filter::{
     fast_bilateral_filter, fast_bilateral_filter_f32, fast_bilateral_filter_u16,
@@ -163,19 +197,54 @@ pub use gamma_curves::TransferFunction;
 #[cfg(feature = "nightly_f16")]
 pub use gaussian::gaussian_blur_f16;
 pub use gaussian::{
-    complex_gaussian_kernel, gaussian_blur, gaussian_blur_f32, gaussian_blur_u16,
-    gaussian_kernel_1d, gaussian_kernel_1d_f64, sigma_size, sigma_size_d, ConvolutionMode,
-    GaussianBlurParams, IeeeBinaryConvolutionMode,
+    complex_gaussian_kernel, gaussian_blur, gaussian_blur_f32, gaussian_blur_iir,
+    gaussian_blur_iir_f32, gaussian_blur_iir_u16, gaussian_blur_u16, gaussian_derivative,
+    gaussian_derivative_kernel_1d, gaussian_kernel_1d, gaussian_kernel_1d_f64,
+    gaussian_second_derivative_kernel_1d, oriented_gaussian_blur, oriented_gaussian_blur_f32,
+    oriented_gaussian_blur_u16, sigma_size, sigma_size_d, ConvolutionMode, GaussianBlurParams,
+    IeeeBinaryConvolutionMode, OrientedGaussianParams,
 };
 #[cfg(feature = "image")]
 #[cfg_attr(docsrs, doc(cfg(feature = "image")))]
 pub use gaussian_blur_image::gaussian_blur_image;
+pub use gradient::{
+    gradient_derivative, gradient_magnitude, gradient_orientation, GradientKernel, GradientNorm,
+    GradientParams, GradientStorage,
+};
 pub use image::{BlurImage, BlurImageMut, BufferStore};
 pub use img_size::ImageSize;
-pub use laplacian::{laplacian, laplacian_kernel};
+pub use laplacian::{
+    laplacian, laplacian_filter, laplacian_kernel, LaplacianKernel, LaplacianParams,
+};
 pub use lens::lens_kernel;
+#[cfg(feature = "fft")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fft")))]
+pub use lens_blur::{lens_blur, lens_blur_f32, lens_blur_u16, LensBlurParams};
 pub use median_blur::median_blur;
-pub use motion_blur::{generate_motion_kernel, motion_blur};
+pub use median_blur_f32::median_blur_f32;
+pub use median_blur_u16::median_blur_u16;
+pub use morphology::{morphology, morphology_f32, morphology_u16, MorphOp, StructuringElement};
+pub use motion_blur::{
+    generate_motion_kernel, generate_motion_kernel_antialiased, motion_blur, motion_blur_f32,
+    motion_blur_u16, motion_blur_with_params, motion_blur_with_params_f32,
+    motion_blur_with_params_u16, MotionBlurParams, MotionFalloff,
+};
+pub use planar::{
+    box_blur_planar, box_blur_planar_f32, box_blur_planar_u16, fast_gaussian_planar,
+    fast_gaussian_planar_f32, fast_gaussian_planar_u16, gaussian_blur_planar,
+    gaussian_blur_planar_f32, gaussian_blur_planar_u16, stack_blur_planar, stack_blur_planar_f32,
+    stack_blur_planar_u16, BlurPlanarImage, BlurPlanarImageMut,
+};
+pub use radial_blur::{
+    spin_blur, spin_blur_f32, spin_blur_u16, zoom_blur, zoom_blur_f32, zoom_blur_u16,
+    SpinBlurParams, ZoomBlurParams,
+};
+pub use rank_filter::{rank_filter, rank_filter_f32, rank_filter_u16};
+pub use roi::{
+    box_blur_roi, box_blur_roi_f32, box_blur_roi_u16, fast_gaussian_roi, fast_gaussian_roi_f32,
+    fast_gaussian_roi_u16, filter_2d_roi, gaussian_blur_roi, gaussian_blur_roi_f32,
+    gaussian_blur_roi_u16, stack_blur_roi, stack_blur_roi_f32, stack_blur_roi_u16, Rect,
+};
 pub use sobel::sobel;
 #[cfg(feature = "image")]
 #[cfg_attr(docsrs, doc(cfg(feature = "image")))]
@@ -185,10 +254,24 @@ pub use stackblur::stack_blur::stack_blur;
 pub use stackblur::stack_blur_f16::stack_blur_f16;
 pub use stackblur::stack_blur_f32::stack_blur_f32;
 pub use stackblur::stack_blur_u16;
+pub use structure_tensor::{
+    corner_harris, corner_min_eigenvalue, structure_tensor, StructureTensor,
+};
 pub use threading_policy::ThreadingPolicy;
+pub use unsharp_mask::{
+    unsharp_mask, unsharp_mask_f32, unsharp_mask_u16, UnsharpLowPass, UnsharpMaskParams,
+};
 pub use util::{BlurError, MismatchedSize};
+pub use variable_blur::{
+    variable_blur, variable_blur_f32, variable_blur_u16, VariableBlurMap, VariableBlurParams,
+};
+pub use yuv::{
+    fast_gaussian_yuv, fast_gaussian_yuv_u16, gaussian_blur_yuv, gaussian_blur_yuv_u16,
+    stack_blur_yuv, stack_blur_yuv_u16, YuvChromaSubsampling, YuvImageMut,
+};
 
 /// Asymmetric radius container
+#[repr(C)]
 #[derive(Copy, Clone, Default, PartialOrd, PartialEq, Debug)]
 pub struct AnisotropicRadius {
     pub x_axis: u32,
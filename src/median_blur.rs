@@ -26,157 +26,93 @@
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::channels_configuration::FastBlurChannels;
+use crate::edge_mode::clamp_edge;
 use crate::unsafe_slice::UnsafeSlice;
-use crate::{BlurError, BlurImage, BlurImageMut, ThreadingPolicy};
+use crate::{BlurError, BlurImage, BlurImageMut, EdgeMode, Scalar, ThreadingPolicy};
 
-struct MedianHistogram {
-    r: [i32; 256],
-    g: [i32; 256],
-    b: [i32; 256],
-    a: [i32; 256],
-    n: i32,
-}
-
-#[allow(clippy::too_many_arguments)]
+/// Maps window coordinate into the image, `None` means pixel is taken from constant border.
 #[inline(always)]
-fn add_rgb_pixels<const CN: usize>(
-    src: &[u8],
-    src_stride: u32,
-    y: i64,
-    x: i64,
-    width: u32,
-    height: u32,
-    size: u32,
-    histogram: &mut MedianHistogram,
-) {
-    if x < 0 || x >= width as i64 {
-        return;
-    }
-    let px = x as usize * CN;
-    let cap = (y + size as i64).min(height as i64 - 1);
-    let start = y - size as i64;
-    for i in start..=cap {
-        if i < 0 {
-            continue;
-        }
-        let y_shift = i as usize * src_stride as usize;
-        let bytes_offset = y_shift + px;
-        let v0 = unsafe { *src.get_unchecked(bytes_offset) };
-        unsafe {
-            *histogram.r.get_unchecked_mut(v0 as usize) += 1;
-        }
-        if CN > 1 {
-            let v1 = unsafe { *src.get_unchecked(bytes_offset + 1) };
-            unsafe {
-                *histogram.g.get_unchecked_mut(v1 as usize) += 1;
-            }
-        }
-        if CN > 2 {
-            let v2 = unsafe { *src.get_unchecked(bytes_offset + 2) };
-            unsafe {
-                *histogram.b.get_unchecked_mut(v2 as usize) += 1;
-            }
-        }
-        if CN == 4 {
-            unsafe {
-                let v3 = *src.get_unchecked(bytes_offset + 3);
-                *histogram.a.get_unchecked_mut(v3 as usize) += 1;
-            }
-        }
-        histogram.n += 1;
+pub(crate) fn median_edge_index(edge_mode: EdgeMode, i: i64, n: i64) -> Option<usize> {
+    if edge_mode == EdgeMode::Constant && (i < 0 || i >= n) {
+        return None;
     }
+    Some(clamp_edge!(edge_mode, i, 0, n))
 }
 
+/// Fills mapped rows of the window centered at `y`
 #[inline(always)]
-fn remove_rgb_pixels<const CN: usize>(
-    src: &[u8],
-    src_stride: u32,
-    y: i64,
-    x: i64,
-    width: u32,
-    height: u32,
-    size: u32,
-    histogram: &mut MedianHistogram,
+pub(crate) fn median_window_rows(
+    rows: &mut Vec<Option<usize>>,
+    edge_mode: EdgeMode,
+    y: usize,
+    radius: usize,
+    height: usize,
 ) {
-    if x < 0 || x >= width as i64 {
-        return;
-    }
-    let px = x as usize * CN;
-    let cap = (y + size as i64).min(height as i64 - 1);
-    let start = y - size as i64;
-    for i in start..=cap {
-        if i < 0 {
-            continue;
-        }
-        let y_shift = i as usize * src_stride as usize;
-        let bytes_offset = y_shift + px;
-        let v0 = unsafe { *src.get_unchecked(bytes_offset) };
-        unsafe {
-            let k = histogram.r.get_unchecked_mut(v0 as usize);
-            let x = *k - 1;
-            *k = x;
-        }
-        if CN > 1 {
-            let v1 = unsafe { *src.get_unchecked(bytes_offset + 1) };
-            unsafe {
-                *histogram.g.get_unchecked_mut(v1 as usize) -= 1;
-            }
-        }
-        if CN > 2 {
-            let v2 = unsafe { *src.get_unchecked(bytes_offset + 2) };
-            unsafe {
-                *histogram.b.get_unchecked_mut(v2 as usize) -= 1;
-            }
-        }
-        if CN == 4 {
-            let v3 = unsafe { *src.get_unchecked(bytes_offset + 3) };
-            *unsafe { histogram.a.get_unchecked_mut(v3 as usize) } -= 1;
-        }
-        histogram.n -= 1;
-    }
+    rows.clear();
+    rows.extend(
+        (y as i64 - radius as i64..=y as i64 + radius as i64)
+            .map(|i| median_edge_index(edge_mode, i, height as i64)),
+    );
 }
 
-#[inline(always)]
-fn init_histogram<const CN: usize>(
-    src: &[u8],
-    src_stride: u32,
-    y: u32,
-    width: u32,
-    height: u32,
-    radius: u32,
-    histogram: &mut MedianHistogram,
-) {
-    histogram.r = [0; 256];
-    histogram.g = [0; 256];
-    histogram.b = [0; 256];
-    histogram.a = [0; 256];
-    histogram.n = 0;
-
-    for j in 0..radius.min(width) {
-        add_rgb_pixels::<CN>(
-            src, src_stride, y as i64, j as i64, width, height, radius, histogram,
-        );
-    }
+struct MedianHistogram<const CN: usize> {
+    bins: [[u32; 256]; CN],
+    n: u32,
 }
 
-#[inline(always)]
-fn median_filter(x: [i32; 256], n: i32) -> i32 {
-    let mut n = n / 2;
-    let mut i = 0i64;
-    #[allow(clippy::manual_range_contains)]
-    while i < 256 && i >= 0 {
-        unsafe {
-            n -= *x.get_unchecked(i as usize);
+impl<const CN: usize> MedianHistogram<CN> {
+    #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
+    fn update_column<const ADD: bool>(
+        &mut self,
+        src: &[u8],
+        src_stride: usize,
+        rows: &[Option<usize>],
+        x: i64,
+        width: usize,
+        edge_mode: EdgeMode,
+        border: [u8; CN],
+    ) {
+        let cx = median_edge_index(edge_mode, x, width as i64);
+        for row in rows.iter() {
+            let px: [u8; CN] = match (row, cx) {
+                (&Some(row), Some(cx)) => {
+                    let offset = row * src_stride + cx * CN;
+                    std::array::from_fn(|c| unsafe { *src.get_unchecked(offset + c) })
+                }
+                _ => border,
+            };
+            for (bins, &v) in self.bins.iter_mut().zip(px.iter()) {
+                let bin = unsafe { bins.get_unchecked_mut(v as usize) };
+                if ADD {
+                    *bin += 1;
+                } else {
+                    *bin -= 1;
+                }
+            }
         }
-        if n > 0 {
-            i += 1;
+        if ADD {
+            self.n += rows.len() as u32;
         } else {
-            break;
+            self.n -= rows.len() as u32;
+        }
+    }
+
+    /// Finds value with the given rank counting from 0 in channel `c`
+    #[inline(always)]
+    fn select(&self, c: usize, rank: u32) -> u8 {
+        let mut remaining = rank;
+        for (i, &count) in self.bins[c].iter().enumerate() {
+            if remaining < count {
+                return i as u8;
+            }
+            remaining -= count;
         }
+        u8::MAX
     }
-    i as i32
 }
 
+#[allow(clippy::too_many_arguments)]
 fn median_blur_impl<const CN: usize>(
     src: &[u8],
     src_stride: u32,
@@ -185,6 +121,9 @@ fn median_blur_impl<const CN: usize>(
     width: u32,
     height: u32,
     radius: u32,
+    rank: u32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
     start_y: u32,
     end_y: u32,
 ) {
@@ -193,7 +132,18 @@ fn median_blur_impl<const CN: usize>(
         if std::arch::is_x86_feature_detected!("avx2") {
             return unsafe {
                 median_blur_impl_avx2::<CN>(
-                    src, src_stride, unsafe_dst, dst_stride, width, height, radius, start_y, end_y,
+                    src,
+                    src_stride,
+                    unsafe_dst,
+                    dst_stride,
+                    width,
+                    height,
+                    radius,
+                    rank,
+                    edge_mode,
+                    border_constant,
+                    start_y,
+                    end_y,
                 )
             };
         }
@@ -203,18 +153,41 @@ fn median_blur_impl<const CN: usize>(
         if std::arch::is_x86_feature_detected!("sse4.1") {
             return unsafe {
                 median_blur_impl_sse_4_1::<CN>(
-                    src, src_stride, unsafe_dst, dst_stride, width, height, radius, start_y, end_y,
+                    src,
+                    src_stride,
+                    unsafe_dst,
+                    dst_stride,
+                    width,
+                    height,
+                    radius,
+                    rank,
+                    edge_mode,
+                    border_constant,
+                    start_y,
+                    end_y,
                 )
             };
         }
     }
     median_blur_impl_exec::<CN>(
-        src, src_stride, unsafe_dst, dst_stride, width, height, radius, start_y, end_y,
+        src,
+        src_stride,
+        unsafe_dst,
+        dst_stride,
+        width,
+        height,
+        radius,
+        rank,
+        edge_mode,
+        border_constant,
+        start_y,
+        end_y,
     );
 }
 
 #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "avx"))]
 #[target_feature(enable = "avx2")]
+#[allow(clippy::too_many_arguments)]
 unsafe fn median_blur_impl_avx2<const CN: usize>(
     src: &[u8],
     src_stride: u32,
@@ -223,16 +196,31 @@ unsafe fn median_blur_impl_avx2<const CN: usize>(
     width: u32,
     height: u32,
     radius: u32,
+    rank: u32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
     start_y: u32,
     end_y: u32,
 ) {
     median_blur_impl_exec::<CN>(
-        src, src_stride, unsafe_dst, dst_stride, width, height, radius, start_y, end_y,
+        src,
+        src_stride,
+        unsafe_dst,
+        dst_stride,
+        width,
+        height,
+        radius,
+        rank,
+        edge_mode,
+        border_constant,
+        start_y,
+        end_y,
     );
 }
 
 #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
 #[target_feature(enable = "sse4.1")]
+#[allow(clippy::too_many_arguments)]
 unsafe fn median_blur_impl_sse_4_1<const CN: usize>(
     src: &[u8],
     src_stride: u32,
@@ -241,15 +229,30 @@ unsafe fn median_blur_impl_sse_4_1<const CN: usize>(
     width: u32,
     height: u32,
     radius: u32,
+    rank: u32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
     start_y: u32,
     end_y: u32,
 ) {
     median_blur_impl_exec::<CN>(
-        src, src_stride, unsafe_dst, dst_stride, width, height, radius, start_y, end_y,
+        src,
+        src_stride,
+        unsafe_dst,
+        dst_stride,
+        width,
+        height,
+        radius,
+        rank,
+        edge_mode,
+        border_constant,
+        start_y,
+        end_y,
     );
 }
 
 #[inline(always)]
+#[allow(clippy::too_many_arguments)]
 fn median_blur_impl_exec<const CN: usize>(
     src: &[u8],
     src_stride: u32,
@@ -258,97 +261,57 @@ fn median_blur_impl_exec<const CN: usize>(
     width: u32,
     height: u32,
     radius: u32,
+    rank: u32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
     start_y: u32,
     end_y: u32,
 ) {
-    for y in start_y..end_y {
-        let mut histogram = MedianHistogram {
-            r: [0; 256],
-            g: [0; 256],
-            b: [0; 256],
-            a: [0; 256],
-            n: 0,
-        };
+    let width = width as usize;
+    let radius = radius as usize;
+    let src_stride = src_stride as usize;
+    let border: [u8; CN] =
+        std::array::from_fn(|c| border_constant[c].round().clamp(0., 255.) as u8);
+    let mut rows = Vec::with_capacity(2 * radius + 1);
+    let mut histogram = MedianHistogram::<CN> {
+        bins: [[0; 256]; CN],
+        n: 0,
+    };
+    for y in start_y as usize..end_y as usize {
+        median_window_rows(&mut rows, edge_mode, y, radius, height as usize);
+        histogram.bins = [[0; 256]; CN];
+        histogram.n = 0;
+        for x in -(radius as i64)..=radius as i64 {
+            histogram.update_column::<true>(src, src_stride, &rows, x, width, edge_mode, border);
+        }
+        let y_dst_offset = y * dst_stride as usize;
         for x in 0..width {
-            let px = x as usize * CN;
-            if x == 0 {
-                init_histogram::<CN>(src, src_stride, y, width, height, radius, &mut histogram);
-            } else {
-                remove_rgb_pixels::<CN>(
+            if x > 0 {
+                let x = x as i64;
+                let r = radius as i64;
+                histogram.update_column::<false>(
                     src,
                     src_stride,
-                    y as i64,
-                    x as i64 - radius as i64,
+                    &rows,
+                    x - r - 1,
                     width,
-                    height,
-                    radius,
-                    &mut histogram,
+                    edge_mode,
+                    border,
                 );
-                add_rgb_pixels::<CN>(
+                histogram.update_column::<true>(
                     src,
                     src_stride,
-                    y as i64,
-                    x as i64 + radius as i64,
+                    &rows,
+                    x + r,
                     width,
-                    height,
-                    radius,
-                    &mut histogram,
-                );
-            }
-
-            let y_src_offset = y as usize * src_stride as usize;
-            let y_dst_offset = y as usize * dst_stride as usize;
-
-            push_hist::<CN>(src, unsafe_dst, y_src_offset, y_dst_offset, &histogram, px);
-        }
-    }
-}
-
-#[inline(always)]
-fn push_hist<const CN: usize>(
-    src: &[u8],
-    unsafe_dst: &UnsafeSlice<u8>,
-    y_src_offset: usize,
-    y_dst_offset: usize,
-    histogram: &MedianHistogram,
-    px: usize,
-) {
-    if histogram.n > 0 {
-        unsafe {
-            let bytes_offset = y_dst_offset + px;
-            unsafe_dst.write(bytes_offset, median_filter(histogram.r, histogram.n) as u8);
-            if CN > 1 {
-                unsafe_dst.write(
-                    bytes_offset + 1,
-                    median_filter(histogram.g, histogram.n) as u8,
-                );
-            }
-            if CN > 2 {
-                unsafe_dst.write(
-                    bytes_offset + 2,
-                    median_filter(histogram.b, histogram.n) as u8,
+                    edge_mode,
+                    border,
                 );
             }
-            if CN == 4 {
-                unsafe_dst.write(
-                    bytes_offset + 3,
-                    median_filter(histogram.a, histogram.n) as u8,
-                );
-            }
-        }
-    } else {
-        unsafe {
-            let bytes_offset = y_dst_offset + px;
-            let src_offset = y_src_offset + px;
-            unsafe_dst.write(bytes_offset, *src.get_unchecked(src_offset));
-            if CN > 1 {
-                unsafe_dst.write(bytes_offset + 1, *src.get_unchecked(src_offset + 1));
-            }
-            if CN > 2 {
-                unsafe_dst.write(bytes_offset + 2, *src.get_unchecked(src_offset + 2));
-            }
-            if CN == 4 {
-                unsafe_dst.write(bytes_offset + 3, *src.get_unchecked(src_offset + 3));
+            for c in 0..CN {
+                unsafe {
+                    unsafe_dst.write(y_dst_offset + x * CN + c, histogram.select(c, rank));
+                }
             }
         }
     }
@@ -365,22 +328,54 @@ fn push_hist<const CN: usize>(
 /// * `src_image` - Src image, see [BlurImage] for more info
 /// * `dst_image` - Destination image, see [BlurImageMut] for more info
 /// * `radius` - Radius of kernel
-/// * `channels` - Count of channels in the image, see [FastBlurChannels] for more info
-///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
-#[allow(clippy::too_many_arguments)]
+/// * `edge_mode` - Rule to handle edge mode, sse [EdgeMode] for more info,
+///   [EdgeMode::Clamp] matches OpenCV `medianBlur`
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy]
 pub fn median_blur(
     src_image: &BlurImage<u8>,
     dst_image: &mut BlurImageMut<u8>,
     radius: u32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
     threading_policy: ThreadingPolicy,
 ) -> Result<(), BlurError> {
+    median_blur_rank(
+        src_image,
+        dst_image,
+        radius,
+        50.,
+        edge_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs rank filter, `percentile` in range [0, 100] selects value from sorted window.
+pub(crate) fn median_blur_rank(
+    src_image: &BlurImage<u8>,
+    dst_image: &mut BlurImageMut<u8>,
+    radius: u32,
+    percentile: f32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    if !(0. ..=100.).contains(&percentile) {
+        return Err(BlurError::InvalidArguments);
+    }
+    let diameter = 2 * radius as u64 + 1;
+    let window_size = diameter
+        .checked_mul(diameter)
+        .filter(|&x| x <= u32::MAX as u64)
+        .ok_or(BlurError::ExceedingPointerSize)?;
+    let rank = ((window_size - 1) as f64 * percentile as f64 / 100.).round() as u32;
     src_image.check_layout()?;
     dst_image.check_layout(Some(src_image))?;
     src_image.size_matches_mut(dst_image)?;
     let _dispatcher = match src_image.channels {
         FastBlurChannels::Plane => median_blur_impl::<1>,
+        FastBlurChannels::Channels2 => median_blur_impl::<2>,
         FastBlurChannels::Channels3 => median_blur_impl::<3>,
         FastBlurChannels::Channels4 => median_blur_impl::<4>,
     };
@@ -411,9 +406,93 @@ pub fn median_blur(
             width,
             height,
             radius,
+            rank,
+            edge_mode,
+            border_constant,
             start_y,
             end_y,
         );
     });
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference_median(
+        src: &[u8],
+        width: usize,
+        height: usize,
+        radius: usize,
+        edge_mode: EdgeMode,
+        border: u8,
+    ) -> Vec<u8> {
+        let mut dst = vec![0u8; width * height];
+        for y in 0..height {
+            for x in 0..width {
+                let mut window = Vec::new();
+                for wy in y as i64 - radius as i64..=y as i64 + radius as i64 {
+                    for wx in x as i64 - radius as i64..=x as i64 + radius as i64 {
+                        let row = median_edge_index(edge_mode, wy, height as i64);
+                        let col = median_edge_index(edge_mode, wx, width as i64);
+                        window.push(match (row, col) {
+                            (Some(row), Some(col)) => src[row * width + col],
+                            _ => border,
+                        });
+                    }
+                }
+                window.sort_unstable();
+                dst[y * width + x] = window[window.len() / 2];
+            }
+        }
+        dst
+    }
+
+    #[test]
+    fn test_median_blur_edge_modes() {
+        let (width, height, radius) = (23usize, 17usize, 3usize);
+        // Ramps along one axis, so away from the corners the first column (or row)
+        // of the output is the median of the samples each edge mode pulls in.
+        let horizontal: Vec<u8> = (0..width * height)
+            .map(|i| (i % width * 10) as u8)
+            .collect();
+        let vertical: Vec<u8> = (0..width * height)
+            .map(|i| (i / width * 10) as u8)
+            .collect();
+        for (edge_mode, expected_border) in [
+            (EdgeMode::Clamp, 0u8),
+            (EdgeMode::Wrap, 30),
+            (EdgeMode::Reflect, 10),
+            (EdgeMode::Reflect101, 20),
+            (EdgeMode::Constant, 30),
+        ] {
+            for (src, is_horizontal) in [(&horizontal, true), (&vertical, false)] {
+                let src_image =
+                    BlurImage::borrow(src, width as u32, height as u32, FastBlurChannels::Plane);
+                let mut dst_image = BlurImageMut::default();
+                median_blur(
+                    &src_image,
+                    &mut dst_image,
+                    radius as u32,
+                    edge_mode,
+                    Scalar::dup(255.),
+                    ThreadingPolicy::Adaptive,
+                )
+                .unwrap();
+                let dst = dst_image.data.borrow();
+                if is_horizontal {
+                    for y in radius..height - radius {
+                        assert_eq!(dst[y * width], expected_border, "{edge_mode:?}");
+                    }
+                } else {
+                    for &v in &dst[radius..width - radius] {
+                        assert_eq!(v, expected_border, "{edge_mode:?}");
+                    }
+                }
+                let reference = reference_median(src, width, height, radius, edge_mode, 255);
+                assert_eq!(dst, reference.as_slice(), "{edge_mode:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,403 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::channels_configuration::FastBlurChannels;
+use crate::median_blur::median_window_rows;
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{BlurError, BlurImage, BlurImageMut, EdgeMode, Scalar, ThreadingPolicy};
+
+#[allow(clippy::too_many_arguments)]
+fn median_blur_f32_impl<const CN: usize>(
+    src: &[f32],
+    src_stride: u32,
+    unsafe_dst: &UnsafeSlice<f32>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    rank: u32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    start_y: u32,
+    end_y: u32,
+) {
+    #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "avx"))]
+    {
+        if std::arch::is_x86_feature_detected!("avx2") {
+            return unsafe {
+                median_blur_f32_impl_avx2::<CN>(
+                    src,
+                    src_stride,
+                    unsafe_dst,
+                    dst_stride,
+                    width,
+                    height,
+                    radius,
+                    rank,
+                    edge_mode,
+                    border_constant,
+                    start_y,
+                    end_y,
+                )
+            };
+        }
+    }
+    #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
+    {
+        if std::arch::is_x86_feature_detected!("sse4.1") {
+            return unsafe {
+                median_blur_f32_impl_sse_4_1::<CN>(
+                    src,
+                    src_stride,
+                    unsafe_dst,
+                    dst_stride,
+                    width,
+                    height,
+                    radius,
+                    rank,
+                    edge_mode,
+                    border_constant,
+                    start_y,
+                    end_y,
+                )
+            };
+        }
+    }
+    median_blur_f32_impl_exec::<CN>(
+        src,
+        src_stride,
+        unsafe_dst,
+        dst_stride,
+        width,
+        height,
+        radius,
+        rank,
+        edge_mode,
+        border_constant,
+        start_y,
+        end_y,
+    );
+}
+
+#[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "avx"))]
+#[target_feature(enable = "avx2")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn median_blur_f32_impl_avx2<const CN: usize>(
+    src: &[f32],
+    src_stride: u32,
+    unsafe_dst: &UnsafeSlice<f32>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    rank: u32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    start_y: u32,
+    end_y: u32,
+) {
+    median_blur_f32_impl_exec::<CN>(
+        src,
+        src_stride,
+        unsafe_dst,
+        dst_stride,
+        width,
+        height,
+        radius,
+        rank,
+        edge_mode,
+        border_constant,
+        start_y,
+        end_y,
+    );
+}
+
+#[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
+#[target_feature(enable = "sse4.1")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn median_blur_f32_impl_sse_4_1<const CN: usize>(
+    src: &[f32],
+    src_stride: u32,
+    unsafe_dst: &UnsafeSlice<f32>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    rank: u32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    start_y: u32,
+    end_y: u32,
+) {
+    median_blur_f32_impl_exec::<CN>(
+        src,
+        src_stride,
+        unsafe_dst,
+        dst_stride,
+        width,
+        height,
+        radius,
+        rank,
+        edge_mode,
+        border_constant,
+        start_y,
+        end_y,
+    );
+}
+
+/// Sorting network for 9 items, returns median of 3x3 window
+#[inline(always)]
+fn median_9(mut p: [f32; 9]) -> f32 {
+    #[inline(always)]
+    fn sort2(p: &mut [f32; 9], a: usize, b: usize) {
+        let (x, y) = (p[a], p[b]);
+        p[a] = x.min(y);
+        p[b] = x.max(y);
+    }
+    sort2(&mut p, 1, 2);
+    sort2(&mut p, 4, 5);
+    sort2(&mut p, 7, 8);
+    sort2(&mut p, 0, 1);
+    sort2(&mut p, 3, 4);
+    sort2(&mut p, 6, 7);
+    sort2(&mut p, 1, 2);
+    sort2(&mut p, 4, 5);
+    sort2(&mut p, 7, 8);
+    sort2(&mut p, 0, 3);
+    sort2(&mut p, 5, 8);
+    sort2(&mut p, 4, 7);
+    sort2(&mut p, 3, 6);
+    sort2(&mut p, 1, 4);
+    sort2(&mut p, 2, 5);
+    sort2(&mut p, 4, 7);
+    sort2(&mut p, 4, 2);
+    sort2(&mut p, 6, 4);
+    sort2(&mut p, 4, 2);
+    p[4]
+}
+
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn median_blur_f32_impl_exec<const CN: usize>(
+    src: &[f32],
+    src_stride: u32,
+    unsafe_dst: &UnsafeSlice<f32>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    rank: u32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    start_y: u32,
+    end_y: u32,
+) {
+    let width = width as usize;
+    let radius = radius as usize;
+    let src_stride = src_stride as usize;
+    let diameter = 2 * radius + 1;
+    let mut window = vec![0f32; diameter * diameter];
+    let mut rows = Vec::with_capacity(diameter);
+    let mut columns = Vec::with_capacity(diameter);
+    for y in start_y as usize..end_y as usize {
+        median_window_rows(&mut rows, edge_mode, y, radius, height as usize);
+        let y_dst_offset = y * dst_stride as usize;
+        for x in 0..width {
+            median_window_rows(&mut columns, edge_mode, x, radius, width);
+            for c in 0..CN {
+                let border = border_constant[c] as f32;
+                let mut n = 0usize;
+                for row in rows.iter() {
+                    for column in columns.iter() {
+                        let v = match (row, column) {
+                            (&Some(row), &Some(column)) => unsafe {
+                                *src.get_unchecked(row * src_stride + column * CN + c)
+                            },
+                            _ => border,
+                        };
+                        unsafe {
+                            *window.get_unchecked_mut(n) = v;
+                        }
+                        n += 1;
+                    }
+                }
+                let rank = (rank as usize).min(n - 1);
+                let value = if n == 9 && rank == n / 2 {
+                    median_9([
+                        window[0], window[1], window[2], window[3], window[4], window[5],
+                        window[6], window[7], window[8],
+                    ])
+                } else {
+                    *window.select_nth_unstable_by(rank, |a, b| a.total_cmp(b)).1
+                };
+                unsafe {
+                    unsafe_dst.write(y_dst_offset + x * CN + c, value);
+                }
+            }
+        }
+    }
+}
+
+/// Performs median blur on the f32 image.
+///
+/// This performs a median kernel filter on the image producing edge preserving blur result.
+/// 3x3 window uses sorting network, larger windows use quickselect.
+/// O(R^2) complexity.
+///
+/// # Arguments
+///
+/// * `src_image` - Src image, see [BlurImage] for more info
+/// * `dst_image` - Destination image, see [BlurImageMut] for more info
+/// * `radius` - Radius of kernel
+/// * `edge_mode` - Rule to handle edge mode, sse [EdgeMode] for more info
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy]
+pub fn median_blur_f32(
+    src_image: &BlurImage<f32>,
+    dst_image: &mut BlurImageMut<f32>,
+    radius: u32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    median_blur_f32_rank(
+        src_image,
+        dst_image,
+        radius,
+        50.,
+        edge_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs rank filter, `percentile` in range [0, 100] selects value from sorted window.
+pub(crate) fn median_blur_f32_rank(
+    src_image: &BlurImage<f32>,
+    dst_image: &mut BlurImageMut<f32>,
+    radius: u32,
+    percentile: f32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    if !(0. ..=100.).contains(&percentile) {
+        return Err(BlurError::InvalidArguments);
+    }
+    let diameter = 2 * radius as u64 + 1;
+    let window_size = diameter
+        .checked_mul(diameter)
+        .filter(|&x| x <= u32::MAX as u64)
+        .ok_or(BlurError::ExceedingPointerSize)?;
+    let rank = ((window_size - 1) as f64 * percentile as f64 / 100.).round() as u32;
+    src_image.check_layout()?;
+    dst_image.check_layout(Some(src_image))?;
+    src_image.size_matches_mut(dst_image)?;
+    let dispatcher = match src_image.channels {
+        FastBlurChannels::Plane => median_blur_f32_impl::<1>,
+        FastBlurChannels::Channels2 => median_blur_f32_impl::<2>,
+        FastBlurChannels::Channels3 => median_blur_f32_impl::<3>,
+        FastBlurChannels::Channels4 => median_blur_f32_impl::<4>,
+    };
+    let width = src_image.width;
+    let height = src_image.height;
+    let src_stride = src_image.row_stride();
+    let dst_stride = dst_image.row_stride();
+
+    let thread_count = threading_policy.thread_count(width, height) as u32;
+
+    let pool = novtb::ThreadPool::new(thread_count as usize);
+
+    let unsafe_dst = UnsafeSlice::new(dst_image.data.borrow_mut());
+
+    let src = src_image.data.as_ref();
+    pool.parallel_for(|thread_index| {
+        let segment_size = height / thread_count;
+        let start_y = thread_index as u32 * segment_size;
+        let mut end_y = (thread_index as u32 + 1) * segment_size;
+        if thread_index as u32 == thread_count - 1 {
+            end_y = height;
+        }
+        dispatcher(
+            src,
+            src_stride,
+            &unsafe_dst,
+            dst_stride,
+            width,
+            height,
+            radius,
+            rank,
+            edge_mode,
+            border_constant,
+            start_y,
+            end_y,
+        );
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_median_blur_f32_removes_impulses_and_keeps_edges() {
+        let (width, height) = (17usize, 11usize);
+        let clean: Vec<f32> = (0..width * height)
+            .flat_map(|i| {
+                if i % width < 8 {
+                    [0.2f32, 0.7, 0.4]
+                } else {
+                    [0.9, 0.1, 0.4]
+                }
+            })
+            .collect();
+        let mut src = clean.clone();
+        src[(2 * width + 2) * 3..(2 * width + 3) * 3].fill(1.);
+        src[(8 * width + 13) * 3 + 2] = 0.;
+        for radius in [1u32, 3] {
+            let src_image = BlurImage::borrow(
+                &src,
+                width as u32,
+                height as u32,
+                FastBlurChannels::Channels3,
+            );
+            let mut dst_image = BlurImageMut::default();
+            median_blur_f32(
+                &src_image,
+                &mut dst_image,
+                radius,
+                EdgeMode::Reflect101,
+                Scalar::default(),
+                ThreadingPolicy::Single,
+            )
+            .unwrap();
+            assert_eq!(dst_image.data.borrow(), clean.as_slice(), "radius {radius}");
+        }
+    }
+}
@@ -0,0 +1,459 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::channels_configuration::FastBlurChannels;
+use crate::median_blur::{median_edge_index, median_window_rows};
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{BlurError, BlurImage, BlurImageMut, EdgeMode, Scalar, ThreadingPolicy};
+
+/// Two level histogram, coarse level counts high byte of the value,
+/// fine level counts full value, so median search is at most 256 + 256 steps.
+struct MedianHistogramU16<const CN: usize> {
+    coarse: Vec<u32>,
+    fine: Vec<u32>,
+    n: u32,
+}
+
+impl<const CN: usize> MedianHistogramU16<CN> {
+    fn new() -> Self {
+        Self {
+            coarse: vec![0; 256 * CN],
+            fine: vec![0; 65536 * CN],
+            n: 0,
+        }
+    }
+
+    #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
+    fn update_column<const ADD: bool>(
+        &mut self,
+        src: &[u16],
+        src_stride: usize,
+        rows: &[Option<usize>],
+        x: i64,
+        width: usize,
+        edge_mode: EdgeMode,
+        border: [u16; CN],
+    ) {
+        let cx = median_edge_index(edge_mode, x, width as i64);
+        for row in rows.iter() {
+            let px: [u16; CN] = match (row, cx) {
+                (&Some(row), Some(cx)) => {
+                    let offset = row * src_stride + cx * CN;
+                    std::array::from_fn(|c| unsafe { *src.get_unchecked(offset + c) })
+                }
+                _ => border,
+            };
+            for (c, &v) in px.iter().enumerate() {
+                let v = v as usize;
+                unsafe {
+                    let fine = self.fine.get_unchecked_mut(c * 65536 + v);
+                    let coarse = self.coarse.get_unchecked_mut(c * 256 + (v >> 8));
+                    if ADD {
+                        *fine += 1;
+                        *coarse += 1;
+                    } else {
+                        *fine -= 1;
+                        *coarse -= 1;
+                    }
+                }
+            }
+        }
+        if ADD {
+            self.n += rows.len() as u32;
+        } else {
+            self.n -= rows.len() as u32;
+        }
+    }
+
+    /// Finds value with the given rank counting from 0 in channel `c`
+    #[inline(always)]
+    fn select(&self, c: usize, rank: u32) -> u16 {
+        let mut remaining = rank;
+        let coarse = &self.coarse[c * 256..(c + 1) * 256];
+        let mut bucket = 0usize;
+        for (i, &count) in coarse.iter().enumerate() {
+            if remaining < count {
+                bucket = i;
+                break;
+            }
+            remaining -= count;
+        }
+        let fine = &self.fine[c * 65536 + bucket * 256..c * 65536 + (bucket + 1) * 256];
+        for (i, &count) in fine.iter().enumerate() {
+            if remaining < count {
+                return (bucket * 256 + i) as u16;
+            }
+            remaining -= count;
+        }
+        u16::MAX
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn median_blur_u16_impl<const CN: usize>(
+    src: &[u16],
+    src_stride: u32,
+    unsafe_dst: &UnsafeSlice<u16>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    rank: u32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    start_y: u32,
+    end_y: u32,
+) {
+    #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "avx"))]
+    {
+        if std::arch::is_x86_feature_detected!("avx2") {
+            return unsafe {
+                median_blur_u16_impl_avx2::<CN>(
+                    src,
+                    src_stride,
+                    unsafe_dst,
+                    dst_stride,
+                    width,
+                    height,
+                    radius,
+                    rank,
+                    edge_mode,
+                    border_constant,
+                    start_y,
+                    end_y,
+                )
+            };
+        }
+    }
+    #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
+    {
+        if std::arch::is_x86_feature_detected!("sse4.1") {
+            return unsafe {
+                median_blur_u16_impl_sse_4_1::<CN>(
+                    src,
+                    src_stride,
+                    unsafe_dst,
+                    dst_stride,
+                    width,
+                    height,
+                    radius,
+                    rank,
+                    edge_mode,
+                    border_constant,
+                    start_y,
+                    end_y,
+                )
+            };
+        }
+    }
+    median_blur_u16_impl_exec::<CN>(
+        src,
+        src_stride,
+        unsafe_dst,
+        dst_stride,
+        width,
+        height,
+        radius,
+        rank,
+        edge_mode,
+        border_constant,
+        start_y,
+        end_y,
+    );
+}
+
+#[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "avx"))]
+#[target_feature(enable = "avx2")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn median_blur_u16_impl_avx2<const CN: usize>(
+    src: &[u16],
+    src_stride: u32,
+    unsafe_dst: &UnsafeSlice<u16>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    rank: u32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    start_y: u32,
+    end_y: u32,
+) {
+    median_blur_u16_impl_exec::<CN>(
+        src,
+        src_stride,
+        unsafe_dst,
+        dst_stride,
+        width,
+        height,
+        radius,
+        rank,
+        edge_mode,
+        border_constant,
+        start_y,
+        end_y,
+    );
+}
+
+#[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
+#[target_feature(enable = "sse4.1")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn median_blur_u16_impl_sse_4_1<const CN: usize>(
+    src: &[u16],
+    src_stride: u32,
+    unsafe_dst: &UnsafeSlice<u16>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    rank: u32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    start_y: u32,
+    end_y: u32,
+) {
+    median_blur_u16_impl_exec::<CN>(
+        src,
+        src_stride,
+        unsafe_dst,
+        dst_stride,
+        width,
+        height,
+        radius,
+        rank,
+        edge_mode,
+        border_constant,
+        start_y,
+        end_y,
+    );
+}
+
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn median_blur_u16_impl_exec<const CN: usize>(
+    src: &[u16],
+    src_stride: u32,
+    unsafe_dst: &UnsafeSlice<u16>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    rank: u32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    start_y: u32,
+    end_y: u32,
+) {
+    let width = width as usize;
+    let radius = radius as usize;
+    let src_stride = src_stride as usize;
+    let border: [u16; CN] =
+        std::array::from_fn(|c| border_constant[c].round().clamp(0., 65535.) as u16);
+    let mut rows = Vec::with_capacity(2 * radius + 1);
+    let mut histogram = MedianHistogramU16::<CN>::new();
+    let r = radius as i64;
+    for y in start_y as usize..end_y as usize {
+        median_window_rows(&mut rows, edge_mode, y, radius, height as usize);
+        for x in -r..=r {
+            histogram.update_column::<true>(src, src_stride, &rows, x, width, edge_mode, border);
+        }
+        let y_dst_offset = y * dst_stride as usize;
+        for x in 0..width {
+            if x > 0 {
+                let x = x as i64;
+                histogram.update_column::<false>(
+                    src,
+                    src_stride,
+                    &rows,
+                    x - r - 1,
+                    width,
+                    edge_mode,
+                    border,
+                );
+                histogram.update_column::<true>(
+                    src,
+                    src_stride,
+                    &rows,
+                    x + r,
+                    width,
+                    edge_mode,
+                    border,
+                );
+            }
+            for c in 0..CN {
+                unsafe {
+                    unsafe_dst.write(y_dst_offset + x * CN + c, histogram.select(c, rank));
+                }
+            }
+        }
+        // Removing what is left in the window is cheaper than clearing full histogram
+        let last = width as i64 - 1;
+        for x in last - r..=last + r {
+            histogram.update_column::<false>(src, src_stride, &rows, x, width, edge_mode, border);
+        }
+    }
+}
+
+/// Performs median blur on the 16-bit image.
+///
+/// This performs a median kernel filter on the image producing edge preserving blur result.
+/// Uses two level histogram, so it works with full 16-bit range.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src_image` - Src image, see [BlurImage] for more info
+/// * `dst_image` - Destination image, see [BlurImageMut] for more info
+/// * `radius` - Radius of kernel
+/// * `edge_mode` - Rule to handle edge mode, sse [EdgeMode] for more info
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy]
+pub fn median_blur_u16(
+    src_image: &BlurImage<u16>,
+    dst_image: &mut BlurImageMut<u16>,
+    radius: u32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    median_blur_u16_rank(
+        src_image,
+        dst_image,
+        radius,
+        50.,
+        edge_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs rank filter, `percentile` in range [0, 100] selects value from sorted window.
+pub(crate) fn median_blur_u16_rank(
+    src_image: &BlurImage<u16>,
+    dst_image: &mut BlurImageMut<u16>,
+    radius: u32,
+    percentile: f32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    if !(0. ..=100.).contains(&percentile) {
+        return Err(BlurError::InvalidArguments);
+    }
+    let diameter = 2 * radius as u64 + 1;
+    let window_size = diameter
+        .checked_mul(diameter)
+        .filter(|&x| x <= u32::MAX as u64)
+        .ok_or(BlurError::ExceedingPointerSize)?;
+    let rank = ((window_size - 1) as f64 * percentile as f64 / 100.).round() as u32;
+    src_image.check_layout()?;
+    dst_image.check_layout(Some(src_image))?;
+    src_image.size_matches_mut(dst_image)?;
+    let dispatcher = match src_image.channels {
+        FastBlurChannels::Plane => median_blur_u16_impl::<1>,
+        FastBlurChannels::Channels2 => median_blur_u16_impl::<2>,
+        FastBlurChannels::Channels3 => median_blur_u16_impl::<3>,
+        FastBlurChannels::Channels4 => median_blur_u16_impl::<4>,
+    };
+    let width = src_image.width;
+    let height = src_image.height;
+    let src_stride = src_image.row_stride();
+    let dst_stride = dst_image.row_stride();
+
+    let thread_count = threading_policy.thread_count(width, height) as u32;
+
+    let pool = novtb::ThreadPool::new(thread_count as usize);
+
+    let unsafe_dst = UnsafeSlice::new(dst_image.data.borrow_mut());
+
+    let src = src_image.data.as_ref();
+    pool.parallel_for(|thread_index| {
+        let segment_size = height / thread_count;
+        let start_y = thread_index as u32 * segment_size;
+        let mut end_y = (thread_index as u32 + 1) * segment_size;
+        if thread_index as u32 == thread_count - 1 {
+            end_y = height;
+        }
+        dispatcher(
+            src,
+            src_stride,
+            &unsafe_dst,
+            dst_stride,
+            width,
+            height,
+            radius,
+            rank,
+            edge_mode,
+            border_constant,
+            start_y,
+            end_y,
+        );
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_median_blur_u16_removes_impulses_and_keeps_edges() {
+        let (width, height) = (19usize, 13usize);
+        let clean: Vec<u16> = (0..width * height)
+            .flat_map(|i| {
+                if i % width < 9 {
+                    [10000u16, 30000]
+                } else {
+                    [50000, 5000]
+                }
+            })
+            .collect();
+        let mut src = clean.clone();
+        src[(3 * width + 3) * 2] = 65535;
+        src[(9 * width + 14) * 2 + 1] = 0;
+        src[(10 * width + 4) * 2..(10 * width + 5) * 2].fill(0);
+        let src_image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels2,
+        );
+        let mut dst_image = BlurImageMut::default();
+        median_blur_u16(
+            &src_image,
+            &mut dst_image,
+            2,
+            EdgeMode::Clamp,
+            Scalar::default(),
+            ThreadingPolicy::Adaptive,
+        )
+        .unwrap();
+        assert_eq!(dst_image.data.borrow(), clean.as_slice());
+    }
+}
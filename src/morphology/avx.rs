@@ -0,0 +1,138 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use std::arch::x86_64::*;
+
+macro_rules! define_morph_row_avx {
+    (
+        $name: ident,
+        $impl_name: ident,
+        $ty: ty,
+        $lanes: expr,
+        $load: ident,
+        $store: ident,
+        $op: ident,
+        $ptr: ty,
+        $scalar_op: ident
+    ) => {
+        pub(crate) fn $name(dst: &mut [$ty], rows: &[&[$ty]]) {
+            unsafe {
+                $impl_name(dst, rows);
+            }
+        }
+
+        #[target_feature(enable = "avx2")]
+        unsafe fn $impl_name(dst: &mut [$ty], rows: &[&[$ty]]) {
+            let width = dst.len();
+            let mut cx = 0usize;
+            while cx + $lanes <= width {
+                let mut acc =
+                    $load(rows.get_unchecked(0).get_unchecked(cx..).as_ptr() as *const $ptr);
+                for row in rows.iter().skip(1) {
+                    acc = $op(acc, $load(row.get_unchecked(cx..).as_ptr() as *const $ptr));
+                }
+                $store(dst.get_unchecked_mut(cx..).as_mut_ptr() as *mut $ptr, acc);
+                cx += $lanes;
+            }
+            for x in cx..width {
+                let mut acc = *rows.get_unchecked(0).get_unchecked(x);
+                for row in rows.iter().skip(1) {
+                    acc = acc.$scalar_op(*row.get_unchecked(x));
+                }
+                *dst.get_unchecked_mut(x) = acc;
+            }
+        }
+    };
+}
+
+define_morph_row_avx!(
+    erode_row_avx_u8,
+    erode_row_avx_u8_impl,
+    u8,
+    32,
+    _mm256_loadu_si256,
+    _mm256_storeu_si256,
+    _mm256_min_epu8,
+    __m256i,
+    min
+);
+define_morph_row_avx!(
+    dilate_row_avx_u8,
+    dilate_row_avx_u8_impl,
+    u8,
+    32,
+    _mm256_loadu_si256,
+    _mm256_storeu_si256,
+    _mm256_max_epu8,
+    __m256i,
+    max
+);
+define_morph_row_avx!(
+    erode_row_avx_u16,
+    erode_row_avx_u16_impl,
+    u16,
+    16,
+    _mm256_loadu_si256,
+    _mm256_storeu_si256,
+    _mm256_min_epu16,
+    __m256i,
+    min
+);
+define_morph_row_avx!(
+    dilate_row_avx_u16,
+    dilate_row_avx_u16_impl,
+    u16,
+    16,
+    _mm256_loadu_si256,
+    _mm256_storeu_si256,
+    _mm256_max_epu16,
+    __m256i,
+    max
+);
+define_morph_row_avx!(
+    erode_row_avx_f32,
+    erode_row_avx_f32_impl,
+    f32,
+    8,
+    _mm256_loadu_ps,
+    _mm256_storeu_ps,
+    _mm256_min_ps,
+    f32,
+    min
+);
+define_morph_row_avx!(
+    dilate_row_avx_f32,
+    dilate_row_avx_f32_impl,
+    f32,
+    8,
+    _mm256_loadu_ps,
+    _mm256_storeu_ps,
+    _mm256_max_ps,
+    f32,
+    max
+);
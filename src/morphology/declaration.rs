@@ -0,0 +1,430 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::filter1d::{make_arena, ArenaPads};
+use crate::filter2d::{scan_se_2d, ScanPoint2d};
+use crate::morphology::morph_op::MorphologyHandler;
+use crate::morphology::structuring_element::StructuringElement;
+use crate::{
+    BlurError, BlurImage, BlurImageMut, EdgeMode, FastBlurChannels, ImageSize, KernelShape, Scalar,
+    ThreadingPolicy,
+};
+use novtb::{ParallelZonedIterator, TbSliceMut};
+use num_traits::AsPrimitive;
+
+/// Morphological operation
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default)]
+pub enum MorphOp {
+    /// Minimum over structuring element
+    #[default]
+    Erode = 0,
+    /// Maximum over structuring element
+    Dilate = 1,
+    /// Erosion followed by dilation
+    Open = 2,
+    /// Dilation followed by erosion
+    Close = 3,
+    /// Dilation minus erosion
+    Gradient = 4,
+    /// Source minus opening
+    TopHat = 5,
+    /// Closing minus source
+    BlackHat = 6,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn morph_pass_impl<T, const CN: usize>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    points: &[ScanPoint2d<u8>],
+    shape: KernelShape,
+    erode: bool,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: MorphologyHandler,
+    f64: AsPrimitive<T>,
+{
+    let image_size = ImageSize::new(src.width as usize, src.height as usize);
+    let dst_stride = dst.row_stride() as usize;
+    let row_length = image_size.width * CN;
+
+    if points.is_empty() {
+        for (src, dst) in src
+            .data
+            .chunks(src.row_stride() as usize)
+            .zip(dst.data.borrow_mut().chunks_mut(dst_stride))
+        {
+            dst[..row_length].copy_from_slice(&src[..row_length]);
+        }
+        return Ok(());
+    }
+
+    let (arena_source, arena) = make_arena::<T, CN>(
+        src.data.as_ref(),
+        src.row_stride() as usize,
+        image_size,
+        ArenaPads::from_kernel_shape(shape),
+        border_mode,
+        border_constant,
+    )?;
+
+    let handler = if erode {
+        T::erode_row()
+    } else {
+        T::dilate_row()
+    };
+
+    let thread_count =
+        threading_policy.thread_count(image_size.width as u32, image_size.height as u32);
+    let pool = novtb::ThreadPool::new(thread_count);
+
+    let arena_stride = arena.width * arena.components;
+    let arena_source = arena_source.as_slice();
+
+    dst.data
+        .borrow_mut()
+        .tb_par_chunks_mut(dst_stride)
+        .for_each_enumerated(&pool, |y, row| {
+            let rows = points
+                .iter()
+                .map(|point| {
+                    let source_y = (point.y + arena.pad_h as i64 + y as i64) as usize;
+                    let source_x = (point.x + arena.pad_w as i64) as usize;
+                    &arena_source[source_y * arena_stride + source_x * CN..]
+                })
+                .collect::<Vec<_>>();
+            handler(&mut row[..row_length], &rows);
+        });
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn morph_pass<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    points: &[ScanPoint2d<u8>],
+    shape: KernelShape,
+    erode: bool,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: MorphologyHandler,
+    f64: AsPrimitive<T>,
+{
+    let executor = match src.channels {
+        FastBlurChannels::Plane => morph_pass_impl::<T, 1>,
+        FastBlurChannels::Channels2 => morph_pass_impl::<T, 2>,
+        FastBlurChannels::Channels3 => morph_pass_impl::<T, 3>,
+        FastBlurChannels::Channels4 => morph_pass_impl::<T, 4>,
+    };
+    executor(
+        src,
+        dst,
+        points,
+        shape,
+        erode,
+        border_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Replaces `dst` with `op(src, dst)` for every item
+fn apply_difference<T: MorphologyHandler>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    op: impl Fn(T, T) -> T,
+) {
+    let row_length = src.width as usize * src.channels.channels();
+    let dst_stride = dst.row_stride() as usize;
+    for (src, dst) in src
+        .data
+        .chunks(src.row_stride() as usize)
+        .zip(dst.data.borrow_mut().chunks_mut(dst_stride))
+    {
+        for (&src, dst) in src[..row_length].iter().zip(dst[..row_length].iter_mut()) {
+            *dst = op(src, *dst);
+        }
+    }
+}
+
+fn morphology_impl<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    op: MorphOp,
+    structuring_element: &StructuringElement,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: MorphologyHandler,
+    f64: AsPrimitive<T>,
+{
+    src.check_layout()?;
+    dst.check_layout(Some(src))?;
+    src.size_matches_mut(dst)?;
+    structuring_element.validate()?;
+
+    let shape = structuring_element.shape;
+    let points = scan_se_2d(&structuring_element.mask, shape);
+    let pass = |src: &BlurImage<T>, dst: &mut BlurImageMut<T>, erode: bool| {
+        morph_pass(
+            src,
+            dst,
+            &points,
+            shape,
+            erode,
+            border_mode,
+            border_constant,
+            threading_policy,
+        )
+    };
+    let compose = |src: &BlurImage<T>, dst: &mut BlurImageMut<T>, erode_first: bool| {
+        let mut intermediate = BlurImageMut::alloc(src.width, src.height, src.channels);
+        pass(src, &mut intermediate, erode_first)?;
+        pass(&intermediate.to_immutable_ref(), dst, !erode_first)
+    };
+
+    match op {
+        MorphOp::Erode => pass(src, dst, true),
+        MorphOp::Dilate => pass(src, dst, false),
+        MorphOp::Open => compose(src, dst, true),
+        MorphOp::Close => compose(src, dst, false),
+        MorphOp::Gradient => {
+            let mut eroded = BlurImageMut::alloc(src.width, src.height, src.channels);
+            pass(src, &mut eroded, true)?;
+            pass(src, dst, false)?;
+            apply_difference(&eroded.to_immutable_ref(), dst, |eroded, dilated| {
+                T::difference(dilated, eroded)
+            });
+            Ok(())
+        }
+        MorphOp::TopHat => {
+            compose(src, dst, true)?;
+            apply_difference(src, dst, |src, opened| T::difference(src, opened));
+            Ok(())
+        }
+        MorphOp::BlackHat => {
+            compose(src, dst, false)?;
+            apply_difference(src, dst, |src, closed| T::difference(closed, src));
+            Ok(())
+        }
+    }
+}
+
+/// Performs morphological operation on the image.
+///
+/// Erosion and dilation take minimum and maximum over the structuring element,
+/// other operations are composed from them, see [MorphOp] for more info.
+///
+/// # Arguments
+///
+/// * `src` - Source image, see [BlurImage] for more info.
+/// * `dst` - Destination image, see [BlurImageMut] for more info.
+/// * `op` - Morphological operation, see [MorphOp] for more info.
+/// * `structuring_element` - See [StructuringElement] for more info.
+/// * `border_mode` - Border handling mode see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn morphology(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    op: MorphOp,
+    structuring_element: &StructuringElement,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    morphology_impl(
+        src,
+        dst,
+        op,
+        structuring_element,
+        border_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs morphological operation on the 16-bit image.
+///
+/// See [morphology] for more info.
+pub fn morphology_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    op: MorphOp,
+    structuring_element: &StructuringElement,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    morphology_impl(
+        src,
+        dst,
+        op,
+        structuring_element,
+        border_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs morphological operation on the f32 image.
+///
+/// NaN values are not supported, SIMD paths and scalar fallback
+/// may propagate them differently.
+///
+/// See [morphology] for more info.
+pub fn morphology_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    op: MorphOp,
+    structuring_element: &StructuringElement,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    morphology_impl(
+        src,
+        dst,
+        op,
+        structuring_element,
+        border_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_erode_dilate_stamp_structuring_element() {
+        let (width, height) = (37usize, 23usize);
+        let (bright, dark) = ((10usize, 11usize), (26usize, 11usize));
+        let mut src = vec![126u8; width * height * 3];
+        src[(bright.1 * width + bright.0) * 3..(bright.1 * width + bright.0 + 1) * 3].fill(250);
+        src[(dark.1 * width + dark.0) * 3..(dark.1 * width + dark.0 + 1) * 3].fill(3);
+        let src_image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        for se in [
+            StructuringElement::rect(3, 3),
+            StructuringElement::ellipse(7, 5),
+            StructuringElement::cross(5, 5),
+        ] {
+            let (kw, kh) = (se.shape.width as i64, se.shape.height as i64);
+            // Dilation stamps the element around the bright pixel and drops the dark one,
+            // erosion does the opposite.
+            for (op, (sx, sy), value) in [
+                (MorphOp::Erode, dark, 3u8),
+                (MorphOp::Dilate, bright, 250u8),
+            ] {
+                let mut dst_image = BlurImageMut::default();
+                morphology(
+                    &src_image,
+                    &mut dst_image,
+                    op,
+                    &se,
+                    EdgeMode::Clamp,
+                    Scalar::default(),
+                    ThreadingPolicy::Adaptive,
+                )
+                .unwrap();
+                let dst = dst_image.data.borrow();
+                for y in 0..height as i64 {
+                    for x in 0..width as i64 {
+                        let ky = sy as i64 - y + kh / 2;
+                        let kx = sx as i64 - x + kw / 2;
+                        let covered = (0..kh).contains(&ky)
+                            && (0..kw).contains(&kx)
+                            && se.mask[(ky * kw + kx) as usize] != 0;
+                        let expected = if covered { value } else { 126 };
+                        let offset = (y as usize * width + x as usize) * 3;
+                        assert_eq!(dst[offset..offset + 3], [expected; 3], "{op:?} at {x}, {y}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_morphology_f32_composites() {
+        let (width, height) = (29usize, 19usize);
+        let (bright, dark) = (9 * width + 7, 9 * width + 21);
+        let mut src = vec![0.5f32; width * height];
+        src[bright] = 0.9;
+        src[dark] = 0.1;
+        let src_image =
+            BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        let se = StructuringElement::ellipse(5, 5);
+        let run = |op: MorphOp| {
+            let mut dst_image = BlurImageMut::default();
+            morphology_f32(
+                &src_image,
+                &mut dst_image,
+                op,
+                &se,
+                EdgeMode::Reflect101,
+                Scalar::default(),
+                ThreadingPolicy::Single,
+            )
+            .unwrap();
+            dst_image.data.borrow().to_vec()
+        };
+        let eroded = run(MorphOp::Erode);
+        let dilated = run(MorphOp::Dilate);
+        let opened = run(MorphOp::Open);
+        let closed = run(MorphOp::Close);
+        let gradient = run(MorphOp::Gradient);
+        let top_hat = run(MorphOp::TopHat);
+        let black_hat = run(MorphOp::BlackHat);
+        for i in 0..src.len() {
+            // Opening removes the bright speck smaller than the element and keeps the pit,
+            // closing fills the pit and keeps the speck.
+            let expected_open = if i == bright { 0.5 } else { src[i] };
+            let expected_close = if i == dark { 0.5 } else { src[i] };
+            assert_eq!(opened[i], expected_open);
+            assert_eq!(closed[i], expected_close);
+            assert_eq!(top_hat[i], src[i] - expected_open);
+            assert_eq!(black_hat[i], expected_close - src[i]);
+            assert_eq!(gradient[i], dilated[i] - eroded[i]);
+        }
+    }
+}
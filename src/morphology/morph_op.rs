@@ -0,0 +1,166 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+#[cfg(all(target_arch = "x86_64", feature = "avx"))]
+use crate::morphology::avx::*;
+#[cfg(all(target_arch = "aarch64", feature = "neon"))]
+use crate::morphology::neon::*;
+#[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
+use crate::morphology::sse::*;
+use std::fmt::Debug;
+
+/// Reduces `rows` into `dst` with minimum or maximum, every row must be at least `dst` length.
+pub(crate) type MorphRowHandler<T> = fn(&mut [T], &[&[T]]);
+
+pub(crate) trait MorphologyHandler: Copy + Default + Send + Sync + Debug + 'static {
+    fn erode_row() -> MorphRowHandler<Self>;
+    fn dilate_row() -> MorphRowHandler<Self>;
+    /// `a - b`, saturating at zero for integer types
+    fn difference(a: Self, b: Self) -> Self;
+}
+
+pub(crate) fn erode_row_scalar<T: Copy + PartialOrd>(dst: &mut [T], rows: &[&[T]]) {
+    for (x, dst) in dst.iter_mut().enumerate() {
+        let mut acc = rows[0][x];
+        for row in rows.iter().skip(1) {
+            let v = row[x];
+            if v < acc {
+                acc = v;
+            }
+        }
+        *dst = acc;
+    }
+}
+
+pub(crate) fn dilate_row_scalar<T: Copy + PartialOrd>(dst: &mut [T], rows: &[&[T]]) {
+    for (x, dst) in dst.iter_mut().enumerate() {
+        let mut acc = rows[0][x];
+        for row in rows.iter().skip(1) {
+            let v = row[x];
+            if v > acc {
+                acc = v;
+            }
+        }
+        *dst = acc;
+    }
+}
+
+macro_rules! impl_morphology_handler {
+    (
+        $ty: ty,
+        $erode_avx: ident,
+        $dilate_avx: ident,
+        $erode_sse: ident,
+        $dilate_sse: ident,
+        $erode_neon: ident,
+        $dilate_neon: ident,
+        $difference: expr
+    ) => {
+        impl MorphologyHandler for $ty {
+            fn erode_row() -> MorphRowHandler<$ty> {
+                #[cfg(all(target_arch = "x86_64", feature = "avx"))]
+                {
+                    if std::arch::is_x86_feature_detected!("avx2") {
+                        return $erode_avx;
+                    }
+                }
+                #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
+                {
+                    if std::arch::is_x86_feature_detected!("sse4.1") {
+                        return $erode_sse;
+                    }
+                }
+                #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+                {
+                    return $erode_neon;
+                }
+                #[allow(unreachable_code)]
+                erode_row_scalar::<$ty>
+            }
+
+            fn dilate_row() -> MorphRowHandler<$ty> {
+                #[cfg(all(target_arch = "x86_64", feature = "avx"))]
+                {
+                    if std::arch::is_x86_feature_detected!("avx2") {
+                        return $dilate_avx;
+                    }
+                }
+                #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
+                {
+                    if std::arch::is_x86_feature_detected!("sse4.1") {
+                        return $dilate_sse;
+                    }
+                }
+                #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+                {
+                    return $dilate_neon;
+                }
+                #[allow(unreachable_code)]
+                dilate_row_scalar::<$ty>
+            }
+
+            #[inline(always)]
+            fn difference(a: $ty, b: $ty) -> $ty {
+                $difference(a, b)
+            }
+        }
+    };
+}
+
+impl_morphology_handler!(
+    u8,
+    erode_row_avx_u8,
+    dilate_row_avx_u8,
+    erode_row_sse_u8,
+    dilate_row_sse_u8,
+    erode_row_neon_u8,
+    dilate_row_neon_u8,
+    u8::saturating_sub
+);
+
+impl_morphology_handler!(
+    u16,
+    erode_row_avx_u16,
+    dilate_row_avx_u16,
+    erode_row_sse_u16,
+    dilate_row_sse_u16,
+    erode_row_neon_u16,
+    dilate_row_neon_u16,
+    u16::saturating_sub
+);
+
+impl_morphology_handler!(
+    f32,
+    erode_row_avx_f32,
+    dilate_row_avx_f32,
+    erode_row_sse_f32,
+    dilate_row_sse_f32,
+    erode_row_neon_f32,
+    dilate_row_neon_f32,
+    |a: f32, b: f32| a - b
+);
@@ -0,0 +1,110 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use std::arch::aarch64::*;
+
+macro_rules! define_morph_row_neon {
+    (
+        $name: ident,
+        $ty: ty,
+        $lanes: expr,
+        $load: ident,
+        $store: ident,
+        $op: ident,
+        $scalar_op: ident
+    ) => {
+        pub(crate) fn $name(dst: &mut [$ty], rows: &[&[$ty]]) {
+            unsafe {
+                let width = dst.len();
+                let mut cx = 0usize;
+                while cx + $lanes <= width {
+                    let mut acc = $load(rows.get_unchecked(0).get_unchecked(cx..).as_ptr());
+                    for row in rows.iter().skip(1) {
+                        acc = $op(acc, $load(row.get_unchecked(cx..).as_ptr()));
+                    }
+                    $store(dst.get_unchecked_mut(cx..).as_mut_ptr(), acc);
+                    cx += $lanes;
+                }
+                for x in cx..width {
+                    let mut acc = *rows.get_unchecked(0).get_unchecked(x);
+                    for row in rows.iter().skip(1) {
+                        acc = acc.$scalar_op(*row.get_unchecked(x));
+                    }
+                    *dst.get_unchecked_mut(x) = acc;
+                }
+            }
+        }
+    };
+}
+
+define_morph_row_neon!(erode_row_neon_u8, u8, 16, vld1q_u8, vst1q_u8, vminq_u8, min);
+define_morph_row_neon!(
+    dilate_row_neon_u8,
+    u8,
+    16,
+    vld1q_u8,
+    vst1q_u8,
+    vmaxq_u8,
+    max
+);
+define_morph_row_neon!(
+    erode_row_neon_u16,
+    u16,
+    8,
+    vld1q_u16,
+    vst1q_u16,
+    vminq_u16,
+    min
+);
+define_morph_row_neon!(
+    dilate_row_neon_u16,
+    u16,
+    8,
+    vld1q_u16,
+    vst1q_u16,
+    vmaxq_u16,
+    max
+);
+define_morph_row_neon!(
+    erode_row_neon_f32,
+    f32,
+    4,
+    vld1q_f32,
+    vst1q_f32,
+    vminq_f32,
+    min
+);
+define_morph_row_neon!(
+    dilate_row_neon_f32,
+    f32,
+    4,
+    vld1q_f32,
+    vst1q_f32,
+    vmaxq_f32,
+    max
+);
@@ -0,0 +1,123 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::{BlurError, KernelShape, MismatchedSize};
+
+/// Structuring element for morphological operations.
+///
+/// Non-zero items of the mask are part of the element, anchor is always at the center.
+#[derive(Debug, Clone)]
+pub struct StructuringElement {
+    /// Mask laid out row by row
+    pub mask: Vec<u8>,
+    pub shape: KernelShape,
+}
+
+impl StructuringElement {
+    /// Creates structuring element from custom mask laid out row by row.
+    pub fn new(mask: Vec<u8>, shape: KernelShape) -> Self {
+        Self { mask, shape }
+    }
+
+    /// Creates rectangular structuring element.
+    pub fn rect(width: usize, height: usize) -> Self {
+        Self::new(vec![1; width * height], KernelShape::new(width, height))
+    }
+
+    /// Creates cross-shaped structuring element.
+    pub fn cross(width: usize, height: usize) -> Self {
+        let mut mask = vec![0; width * height];
+        for (y, row) in mask.chunks_exact_mut(width.max(1)).enumerate() {
+            if y == height / 2 {
+                row.fill(1);
+            } else {
+                row[width / 2] = 1;
+            }
+        }
+        Self::new(mask, KernelShape::new(width, height))
+    }
+
+    /// Creates elliptic structuring element inscribed into `width` x `height` rectangle.
+    ///
+    /// Shape matches OpenCV `MORPH_ELLIPSE`.
+    pub fn ellipse(width: usize, height: usize) -> Self {
+        let mut mask = vec![0; width * height];
+        let r = (height / 2) as f64;
+        let c = (width / 2) as f64;
+        let inv_r2 = if r != 0. { 1. / (r * r) } else { 0. };
+        for (y, row) in mask.chunks_exact_mut(width.max(1)).enumerate() {
+            let dy = y as f64 - r;
+            if dy.abs() > r {
+                continue;
+            }
+            let dx = (c * ((r * r - dy * dy) * inv_r2).sqrt()).round();
+            let start = (c - dx).max(0.) as usize;
+            let end = ((c + dx + 1.) as usize).min(width);
+            row[start..end].fill(1);
+        }
+        Self::new(mask, KernelShape::new(width, height))
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), BlurError> {
+        if self.shape.width == 0 || self.shape.height == 0 {
+            return Err(BlurError::InvalidArguments);
+        }
+        let expected = self.shape.width * self.shape.height;
+        if self.mask.len() != expected {
+            return Err(BlurError::KernelSizeMismatch(MismatchedSize {
+                expected,
+                received: self.mask.len(),
+            }));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_structuring_element_shapes() {
+        let ellipse = StructuringElement::ellipse(5, 5);
+        #[rustfmt::skip]
+        let expected_ellipse = [
+            0, 0, 1, 0, 0,
+            1, 1, 1, 1, 1,
+            1, 1, 1, 1, 1,
+            1, 1, 1, 1, 1,
+            0, 0, 1, 0, 0,
+        ];
+        assert_eq!(ellipse.mask, expected_ellipse);
+        let cross = StructuringElement::cross(3, 3);
+        assert_eq!(cross.mask, [0, 1, 0, 1, 1, 1, 0, 1, 0]);
+        assert!(StructuringElement::new(vec![1; 8], KernelShape::new(3, 3))
+            .validate()
+            .is_err());
+    }
+}
@@ -29,6 +29,9 @@
 use crate::{
     filter_2d, BlurError, BlurImage, BlurImageMut, EdgeMode, KernelShape, Scalar, ThreadingPolicy,
 };
+#[cfg(feature = "fft")]
+use crate::{filter_2d_fft, filter_2d_rgb_fft, filter_2d_rgba_fft, FastBlurChannels};
+use std::fmt::Debug;
 
 #[derive(Copy, Clone)]
 pub struct BresenhamPoint {
@@ -123,8 +126,265 @@ pub fn generate_motion_kernel(size: usize, angle_deg: f32) -> Vec<f32> {
     kernel
 }
 
+/// Weight profile along the motion kernel line
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default)]
+pub enum MotionFalloff {
+    /// All points of the line have equal weight
+    #[default]
+    Uniform = 0,
+    /// Weight grows linearly from zero at the start to the end of the line,
+    /// end of the line points in the direction of the angle
+    Linear = 1,
+    /// Weight follows half period of sine, line center has the most weight
+    Smooth = 2,
+}
+
+impl MotionFalloff {
+    #[inline]
+    fn weight(self, t: f32) -> f32 {
+        match self {
+            MotionFalloff::Uniform => 1.,
+            MotionFalloff::Linear => t,
+            MotionFalloff::Smooth => (std::f32::consts::PI * t).sin(),
+        }
+    }
+}
+
+/// Parameters for [motion_blur_with_params].
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialOrd, PartialEq)]
+pub struct MotionBlurParams {
+    /// Line angle in degrees
+    pub angle: f32,
+    /// Convolve kernel size, must be odd
+    pub kernel_size: usize,
+    /// Line length in pixels, may be fractional, `kernel_size - 1` fits the kernel for any angle
+    pub length: f32,
+    /// Weight profile along the line
+    pub falloff: MotionFalloff,
+}
+
+impl MotionBlurParams {
+    /// Line of length `kernel_size - 1` with uniform weights, same as [motion_blur] uses.
+    pub fn new(angle: f32, kernel_size: usize) -> MotionBlurParams {
+        MotionBlurParams {
+            angle,
+            kernel_size,
+            length: kernel_size.saturating_sub(1) as f32,
+            falloff: MotionFalloff::Uniform,
+        }
+    }
+
+    /// Length must be finite and >= 0, see [generate_motion_kernel_antialiased] for more info.
+    pub fn new_with_line(
+        angle: f32,
+        kernel_size: usize,
+        length: f32,
+        falloff: MotionFalloff,
+    ) -> MotionBlurParams {
+        MotionBlurParams {
+            angle,
+            kernel_size,
+            length,
+            falloff,
+        }
+    }
+}
+
+/// Line samples per pixel of length
+const MOTION_LINE_SUBSAMPLES: f32 = 4.;
+
+/// Generates anti-aliased motion kernel.
+///
+/// Line is centered in the kernel and integrated with sub-pixel steps,
+/// each step is distributed over 4 neighbouring pixels by coverage, as in Wu's algorithm.
+/// Parts of the line outside of the kernel are dropped, kernel is normalized to sum 1.
+///
+/// # Arguments
+///
+/// * `size`: Kernel size, must be odd.
+/// * `length`: Line length in pixels, may be fractional, `size - 1` fits the kernel for any angle.
+/// * `angle_deg`: Line angle in degrees.
+/// * `falloff`: Weight profile along the line, see [MotionFalloff] for more info.
+pub fn generate_motion_kernel_antialiased(
+    size: usize,
+    length: f32,
+    angle_deg: f32,
+    falloff: MotionFalloff,
+) -> Result<Vec<f32>, BlurError> {
+    if size == 0 {
+        return Err(BlurError::ZeroBaseSize);
+    }
+    if size % 2 == 0 {
+        return Err(BlurError::OddKernel(size));
+    }
+    if !length.is_finite() || length < 0. || !angle_deg.is_finite() {
+        return Err(BlurError::InvalidArguments);
+    }
+    let mut kernel = vec![0f32; size * size];
+
+    let center = (size / 2) as f32;
+    let (sin, cos) = angle_deg.to_radians().sin_cos();
+    let steps = ((length * MOTION_LINE_SUBSAMPLES).ceil() as usize).max(1);
+
+    for i in 0..steps {
+        let t = (i as f32 + 0.5) / steps as f32;
+        let offset = (t - 0.5) * length;
+        let x = center + offset * cos;
+        let y = center + offset * sin;
+        let weight = falloff.weight(t);
+
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let fx = x - x0;
+        let fy = y - y0;
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        for (px, py, coverage) in [
+            (x0, y0, (1. - fx) * (1. - fy)),
+            (x0 + 1, y0, fx * (1. - fy)),
+            (x0, y0 + 1, (1. - fx) * fy),
+            (x0 + 1, y0 + 1, fx * fy),
+        ] {
+            if px >= 0 && py >= 0 && (px as usize) < size && (py as usize) < size {
+                kernel[py as usize * size + px as usize] += weight * coverage;
+            }
+        }
+    }
+
+    let sum = kernel.iter().sum::<f32>();
+    if sum != 0. {
+        let recip = 1. / sum;
+        kernel.iter_mut().for_each(|x| *x *= recip);
+    }
+    Ok(kernel)
+}
+
+/// Kernels larger than this are convolved using FFT when `fft` feature is enabled
+#[cfg(feature = "fft")]
+const MOTION_FFT_KERNEL_SIZE: usize = 63;
+
+trait MotionSample: Copy + Default + Debug + Send + Sync + 'static {
+    fn convolve(
+        image: &BlurImage<Self>,
+        destination: &mut BlurImageMut<Self>,
+        kernel: &[f32],
+        kernel_shape: KernelShape,
+        border_mode: EdgeMode,
+        border_constant: Scalar,
+        threading_policy: ThreadingPolicy,
+    ) -> Result<(), BlurError>;
+}
+
+macro_rules! define_motion_sample {
+    ($t: ty) => {
+        impl MotionSample for $t {
+            fn convolve(
+                image: &BlurImage<Self>,
+                destination: &mut BlurImageMut<Self>,
+                kernel: &[f32],
+                kernel_shape: KernelShape,
+                border_mode: EdgeMode,
+                border_constant: Scalar,
+                threading_policy: ThreadingPolicy,
+            ) -> Result<(), BlurError> {
+                #[cfg(feature = "fft")]
+                if kernel_shape.width > MOTION_FFT_KERNEL_SIZE {
+                    match image.channels {
+                        FastBlurChannels::Plane => {
+                            return filter_2d_fft::<$t, f32, f32>(
+                                image,
+                                destination,
+                                kernel,
+                                kernel_shape,
+                                border_mode,
+                                border_constant,
+                                threading_policy,
+                            );
+                        }
+                        FastBlurChannels::Channels3 => {
+                            return filter_2d_rgb_fft::<$t, f32, f32>(
+                                image,
+                                destination,
+                                kernel,
+                                kernel_shape,
+                                border_mode,
+                                border_constant,
+                                threading_policy,
+                            );
+                        }
+                        FastBlurChannels::Channels4 => {
+                            return filter_2d_rgba_fft::<$t, f32, f32>(
+                                image,
+                                destination,
+                                kernel,
+                                kernel_shape,
+                                border_mode,
+                                border_constant,
+                                threading_policy,
+                            );
+                        }
+                        // There is no FFT executor for 2 channels
+                        FastBlurChannels::Channels2 => {}
+                    }
+                }
+                filter_2d::<$t, f32>(
+                    image,
+                    destination,
+                    kernel,
+                    kernel_shape,
+                    border_mode,
+                    border_constant,
+                    threading_policy,
+                )
+            }
+        }
+    };
+}
+
+define_motion_sample!(u8);
+define_motion_sample!(u16);
+define_motion_sample!(f32);
+
+fn motion_blur_impl<T: MotionSample>(
+    image: &BlurImage<T>,
+    destination: &mut BlurImageMut<T>,
+    params: MotionBlurParams,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    image.check_layout()?;
+    destination.check_layout(Some(image))?;
+    image.size_matches_mut(destination)?;
+    let kernel_size = params.kernel_size;
+    if kernel_size & 1 == 0 {
+        return Err(BlurError::OddKernel(kernel_size));
+    }
+    let kernel = generate_motion_kernel_antialiased(
+        kernel_size,
+        params.length,
+        params.angle,
+        params.falloff,
+    )?;
+    T::convolve(
+        image,
+        destination,
+        &kernel,
+        KernelShape::new(kernel_size, kernel_size),
+        border_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
 /// Performs motion blur on the image
 ///
+/// Uses anti-aliased line kernel of length `kernel_size - 1`, see [generate_motion_kernel_antialiased].
+/// Hard line kernel from [generate_motion_kernel] is no longer used, results differ mostly at non-axis angles.
+/// Line length and falloff could be set with [motion_blur_with_params].
+/// Large kernels are convolved using FFT when `fft` feature is enabled.
+///
 /// # Arguments
 ///
 /// * `image`: Source image.
@@ -146,18 +406,127 @@ pub fn motion_blur(
     border_constant: Scalar,
     threading_policy: ThreadingPolicy,
 ) -> Result<(), BlurError> {
-    image.check_layout()?;
-    destination.check_layout(Some(image))?;
-    image.size_matches_mut(destination)?;
-    if kernel_size & 1 == 0 {
-        return Err(BlurError::OddKernel(kernel_size));
-    }
-    let kernel = generate_motion_kernel(kernel_size, angle);
-    filter_2d::<u8, f32>(
+    motion_blur_impl(
         image,
         destination,
-        &kernel,
-        KernelShape::new(kernel_size, kernel_size),
+        MotionBlurParams::new(angle, kernel_size),
+        border_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs motion blur on the 16-bit image
+///
+/// See [motion_blur] for more info.
+pub fn motion_blur_u16(
+    image: &BlurImage<u16>,
+    destination: &mut BlurImageMut<u16>,
+    angle: f32,
+    kernel_size: usize,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    motion_blur_impl(
+        image,
+        destination,
+        MotionBlurParams::new(angle, kernel_size),
+        border_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs motion blur on the f32 image
+///
+/// See [motion_blur] for more info.
+pub fn motion_blur_f32(
+    image: &BlurImage<f32>,
+    destination: &mut BlurImageMut<f32>,
+    angle: f32,
+    kernel_size: usize,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    motion_blur_impl(
+        image,
+        destination,
+        MotionBlurParams::new(angle, kernel_size),
+        border_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs motion blur on the image with custom line length and falloff
+///
+/// # Arguments
+///
+/// * `image`: Source image.
+/// * `destination`: Destination image.
+/// * `params`: See [MotionBlurParams] for more info.
+/// * `border_mode`: See [EdgeMode] for more info.
+/// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn motion_blur_with_params(
+    image: &BlurImage<u8>,
+    destination: &mut BlurImageMut<u8>,
+    params: MotionBlurParams,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    motion_blur_impl(
+        image,
+        destination,
+        params,
+        border_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs motion blur on the 16-bit image with custom line length and falloff
+///
+/// See [motion_blur_with_params] for more info.
+pub fn motion_blur_with_params_u16(
+    image: &BlurImage<u16>,
+    destination: &mut BlurImageMut<u16>,
+    params: MotionBlurParams,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    motion_blur_impl(
+        image,
+        destination,
+        params,
+        border_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs motion blur on the f32 image with custom line length and falloff
+///
+/// See [motion_blur_with_params] for more info.
+pub fn motion_blur_with_params_f32(
+    image: &BlurImage<f32>,
+    destination: &mut BlurImageMut<f32>,
+    params: MotionBlurParams,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    motion_blur_impl(
+        image,
+        destination,
+        params,
         border_mode,
         border_constant,
         threading_policy,
@@ -257,4 +626,168 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_motion_kernel_antialiased() {
+        let size = 15usize;
+        for falloff in [
+            MotionFalloff::Uniform,
+            MotionFalloff::Linear,
+            MotionFalloff::Smooth,
+        ] {
+            let kernel = generate_motion_kernel_antialiased(size, 9.5, 30., falloff).unwrap();
+            assert!((kernel.iter().sum::<f32>() - 1.).abs() < 1e-5);
+            // Diagonal line covers pixels partially
+            assert!(kernel.iter().any(|&v| v > 0. && v < 0.02), "{falloff:?}");
+        }
+        // Uniform line is point symmetric around the center
+        let kernel =
+            generate_motion_kernel_antialiased(size, 9.5, 30., MotionFalloff::Uniform).unwrap();
+        for (i, &v) in kernel.iter().enumerate() {
+            assert!((v - kernel[size * size - 1 - i]).abs() < 1e-5);
+        }
+        let shorter =
+            generate_motion_kernel_antialiased(size, 9., 30., MotionFalloff::Uniform).unwrap();
+        assert_ne!(kernel, shorter);
+        assert!(generate_motion_kernel_antialiased(14, 9., 30., MotionFalloff::Uniform).is_err());
+        assert!(generate_motion_kernel_antialiased(15, -1., 30., MotionFalloff::Uniform).is_err());
+    }
+
+    #[test]
+    fn test_motion_u16_f32() {
+        let width: usize = 96;
+        let height: usize = 96;
+        let src = vec![32000u16; width * height * 3];
+        let src_image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let mut dst = BlurImageMut::default();
+        motion_blur_u16(
+            &src_image,
+            &mut dst,
+            25.,
+            25,
+            EdgeMode::Clamp,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        assert!(dst.data.borrow().iter().all(|&v| v.abs_diff(32000) <= 3));
+
+        // Kernel large enough to be convolved using FFT
+        let src = vec![0.5f32; width * height];
+        let src_image =
+            BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        motion_blur_f32(
+            &src_image,
+            &mut dst,
+            25.,
+            75,
+            EdgeMode::Clamp,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        assert!(dst.data.borrow().iter().all(|&v| (v - 0.5).abs() < 1e-3));
+    }
+
+    #[test]
+    fn test_motion_blur_uses_antialiased_kernel() {
+        let (size, kernel_size) = (31usize, 15usize);
+        let center = size / 2 * size + size / 2;
+        let expected = generate_motion_kernel_antialiased(
+            kernel_size,
+            (kernel_size - 1) as f32,
+            30.,
+            MotionFalloff::Uniform,
+        )
+        .unwrap();
+        let offset = |i: usize| {
+            (i / kernel_size + size / 2 - kernel_size / 2) * size + i % kernel_size + size / 2
+                - kernel_size / 2
+        };
+
+        // Impulse response of symmetric kernel is the kernel itself
+        let mut src = vec![0f32; size * size];
+        src[center] = 1.;
+        let image = BlurImage::borrow(&src, size as u32, size as u32, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        motion_blur_f32(
+            &image,
+            &mut dst,
+            30.,
+            kernel_size,
+            EdgeMode::Constant,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let dst = dst.data.borrow();
+        for (i, &k) in expected.iter().enumerate() {
+            assert!((dst[offset(i)] - k).abs() < 1e-5, "{i}");
+        }
+
+        let mut src = vec![0u8; size * size];
+        src[center] = 255;
+        let image = BlurImage::borrow(&src, size as u32, size as u32, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        motion_blur(
+            &image,
+            &mut dst,
+            30.,
+            kernel_size,
+            EdgeMode::Constant,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let dst = dst.data.borrow();
+        for (i, &k) in expected.iter().enumerate() {
+            let value = dst[offset(i)] as f32;
+            assert!((value - k * 255.).abs() <= 1., "{i}");
+        }
+    }
+
+    #[test]
+    fn test_motion_blur_with_params() {
+        let (size, kernel_size) = (31usize, 15usize);
+        let center = size / 2 * size + size / 2;
+        let params = MotionBlurParams::new_with_line(0., kernel_size, 8., MotionFalloff::Linear);
+        let expected = generate_motion_kernel_antialiased(
+            kernel_size,
+            params.length,
+            params.angle,
+            params.falloff,
+        )
+        .unwrap();
+
+        let mut src = vec![0f32; size * size];
+        src[center] = 1.;
+        let image = BlurImage::borrow(&src, size as u32, size as u32, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        motion_blur_with_params_f32(
+            &image,
+            &mut dst,
+            params,
+            EdgeMode::Constant,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let dst = dst.data.borrow();
+        // Impulse response is the kernel mirrored around its center
+        let row = &dst[size / 2 * size + size / 2 - kernel_size / 2..][..kernel_size];
+        let kernel_row = &expected[kernel_size / 2 * kernel_size..][..kernel_size];
+        for (&v, &k) in row.iter().zip(kernel_row.iter().rev()) {
+            assert!((v - k).abs() < 1e-5, "{v} != {k}");
+        }
+        // Line is 8 pixels long, so it does not reach kernel edges
+        assert_eq!(row[0], 0.);
+        assert_eq!(row[kernel_size - 1], 0.);
+        assert!(row[kernel_size / 2 - 3] > row[kernel_size / 2 + 3]);
+    }
 }
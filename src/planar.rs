@@ -0,0 +1,503 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::{
+    box_blur, box_blur_f32, box_blur_u16, fast_gaussian, fast_gaussian_f32, fast_gaussian_u16,
+    gaussian_blur, gaussian_blur_f32, gaussian_blur_u16, stack_blur, stack_blur_f32,
+    stack_blur_u16, AnisotropicRadius, BlurError, BlurImage, BlurImageMut, BoxBlurParameters,
+    ConvolutionMode, EdgeMode, FastBlurChannels, GaussianBlurParams, IeeeBinaryConvolutionMode,
+    ThreadingPolicy,
+};
+use novtb::{ParallelZonedIterator, TbSliceMut};
+use std::fmt::Debug;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+/// Immutable planar image, every plane is stored in its own slice with its own stride.
+///
+/// Planes may have different sizes, e.g. subsampled chroma, and may be interleaved themselves,
+/// e.g. UV plane of NV12.
+pub struct BlurPlanarImage<'a, T: Clone + Copy + Default + Debug> {
+    pub planes: Vec<BlurImage<'a, T>>,
+}
+
+/// Mutable planar image, every plane is stored in its own slice with its own stride.
+pub struct BlurPlanarImageMut<'a, T: Clone + Copy + Default + Debug> {
+    pub planes: Vec<BlurImageMut<'a, T>>,
+}
+
+impl<T: Clone + Copy + Default + Debug> Default for BlurPlanarImageMut<'_, T> {
+    fn default() -> Self {
+        BlurPlanarImageMut { planes: Vec::new() }
+    }
+}
+
+impl<'a, T: Clone + Copy + Default + Debug> BlurPlanarImage<'a, T> {
+    pub fn new(planes: Vec<BlurImage<'a, T>>) -> Self {
+        Self { planes }
+    }
+
+    /// Borrows single channel planes of the same size, stride of every plane will be `width`.
+    pub fn borrow(planes: &[&'a [T]], width: u32, height: u32) -> Self {
+        Self {
+            planes: planes
+                .iter()
+                .map(|&plane| BlurImage::borrow(plane, width, height, FastBlurChannels::Plane))
+                .collect(),
+        }
+    }
+
+    /// Checks if every plane layout matches necessary requirements
+    pub fn check_layout(&self) -> Result<(), BlurError> {
+        if self.planes.is_empty() {
+            return Err(BlurError::ZeroBaseSize);
+        }
+        for plane in self.planes.iter() {
+            plane.check_layout()?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T: Clone + Copy + Default + Debug> BlurPlanarImageMut<'a, T> {
+    pub fn new(planes: Vec<BlurImageMut<'a, T>>) -> Self {
+        Self { planes }
+    }
+
+    /// Mutable borrows single channel planes of the same size, stride of every plane will be `width`.
+    pub fn borrow(planes: Vec<&'a mut [T]>, width: u32, height: u32) -> Self {
+        Self {
+            planes: planes
+                .into_iter()
+                .map(|plane| BlurImageMut::borrow(plane, width, height, FastBlurChannels::Plane))
+                .collect(),
+        }
+    }
+
+    /// Checks if every plane layout matches necessary requirements.
+    ///
+    /// If image has no planes and `other` is provided, then planes are allocated to match `other`.
+    pub fn check_layout(
+        &mut self,
+        other: Option<&BlurPlanarImage<'_, T>>,
+    ) -> Result<(), BlurError> {
+        if let Some(other) = other {
+            if self.planes.is_empty() {
+                self.planes = other
+                    .planes
+                    .iter()
+                    .map(|plane| BlurImageMut::alloc(plane.width, plane.height, plane.channels))
+                    .collect();
+            }
+            if self.planes.len() != other.planes.len() {
+                return Err(BlurError::ImagesMustMatch);
+            }
+            for (plane, other) in self.planes.iter_mut().zip(other.planes.iter()) {
+                plane.check_layout(Some(other))?;
+                other.size_matches_mut(plane)?;
+            }
+            return Ok(());
+        }
+        if self.planes.is_empty() {
+            return Err(BlurError::ZeroBaseSize);
+        }
+        for plane in self.planes.iter_mut() {
+            plane.check_layout(None)?;
+        }
+        Ok(())
+    }
+
+    pub fn to_immutable_ref(&self) -> BlurPlanarImage<'_, T> {
+        BlurPlanarImage {
+            planes: self.planes.iter().map(|x| x.to_immutable_ref()).collect(),
+        }
+    }
+}
+
+/// Splits threads between planes, returns pool executing planes and per-plane policy.
+fn planes_threading<T: Clone + Copy + Default + Debug>(
+    threading_policy: ThreadingPolicy,
+    planes: &[BlurImage<'_, T>],
+) -> (novtb::ThreadPool, ThreadingPolicy) {
+    let width = planes.iter().map(|x| x.width).max().unwrap_or(0);
+    let height = planes.iter().map(|x| x.height).sum::<u32>();
+    let thread_count = threading_policy.thread_count(width, height);
+    let plane_threads = thread_count.min(planes.len()).max(1);
+    let plane_policy = NonZeroUsize::new(thread_count / plane_threads)
+        .filter(|x| x.get() > 1)
+        .map(ThreadingPolicy::Fixed)
+        .unwrap_or(ThreadingPolicy::Single);
+    (novtb::ThreadPool::new(plane_threads), plane_policy)
+}
+
+fn planar_executor<T, F>(
+    src: &BlurPlanarImage<T>,
+    dst: &mut BlurPlanarImageMut<T>,
+    threading_policy: ThreadingPolicy,
+    executor: F,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + Send + Sync,
+    F: Fn(&BlurImage<T>, &mut BlurImageMut<T>, ThreadingPolicy) -> Result<(), BlurError>
+        + Send
+        + Sync,
+{
+    src.check_layout()?;
+    dst.check_layout(Some(src))?;
+    let (pool, plane_policy) = planes_threading(threading_policy, &src.planes);
+    let error = Mutex::new(Ok(()));
+    dst.planes
+        .tb_par_chunks_exact_mut(1)
+        .for_each_enumerated(&pool, |index, dst| {
+            let result = executor(&src.planes[index], &mut dst[0], plane_policy);
+            if result.is_err() {
+                *error.lock().unwrap() = result;
+            }
+        });
+    error.into_inner().unwrap()
+}
+
+pub(crate) fn planar_executor_in_place<T, F>(
+    image: &mut BlurPlanarImageMut<T>,
+    threading_policy: ThreadingPolicy,
+    executor: F,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + Send + Sync,
+    F: Fn(usize, &mut BlurImageMut<T>, ThreadingPolicy) -> Result<(), BlurError> + Send + Sync,
+{
+    image.check_layout(None)?;
+    let (pool, plane_policy) = planes_threading(threading_policy, &image.to_immutable_ref().planes);
+    let error = Mutex::new(Ok(()));
+    image
+        .planes
+        .tb_par_chunks_exact_mut(1)
+        .for_each_enumerated(&pool, |index, plane| {
+            let result = executor(index, &mut plane[0], plane_policy);
+            if result.is_err() {
+                *error.lock().unwrap() = result;
+            }
+        });
+    error.into_inner().unwrap()
+}
+
+/// Performs gaussian blur on every plane of planar image.
+///
+/// Planes are processed in parallel, threads allowed by [ThreadingPolicy] are split between planes.
+///
+/// # Arguments
+///
+/// * `src` - Source planar image.
+/// * `dst` - Destination planar image, planes are allocated when it is empty.
+/// * `params` - See [GaussianBlurParams] for more info.
+/// * `edge_mode` - Rule to handle edge mode, sse [EdgeMode] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `hint` - see [ConvolutionMode] for more info.
+pub fn gaussian_blur_planar(
+    src: &BlurPlanarImage<u8>,
+    dst: &mut BlurPlanarImageMut<u8>,
+    params: GaussianBlurParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    hint: ConvolutionMode,
+) -> Result<(), BlurError> {
+    planar_executor(src, dst, threading_policy, |src, dst, policy| {
+        gaussian_blur(src, dst, params, edge_mode, policy, hint)
+    })
+}
+
+/// Performs gaussian blur on every plane of 16-bit planar image.
+///
+/// See [gaussian_blur_planar] for more info.
+pub fn gaussian_blur_planar_u16(
+    src: &BlurPlanarImage<u16>,
+    dst: &mut BlurPlanarImageMut<u16>,
+    params: GaussianBlurParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    hint: ConvolutionMode,
+) -> Result<(), BlurError> {
+    planar_executor(src, dst, threading_policy, |src, dst, policy| {
+        gaussian_blur_u16(src, dst, params, edge_mode, policy, hint)
+    })
+}
+
+/// Performs gaussian blur on every plane of f32 planar image.
+///
+/// See [gaussian_blur_planar] for more info.
+pub fn gaussian_blur_planar_f32(
+    src: &BlurPlanarImage<f32>,
+    dst: &mut BlurPlanarImageMut<f32>,
+    params: GaussianBlurParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    convolution_mode: IeeeBinaryConvolutionMode,
+) -> Result<(), BlurError> {
+    planar_executor(src, dst, threading_policy, |src, dst, policy| {
+        gaussian_blur_f32(src, dst, params, edge_mode, policy, convolution_mode)
+    })
+}
+
+/// Performs box blur on every plane of planar image.
+///
+/// Planes are processed in parallel, threads allowed by [ThreadingPolicy] are split between planes.
+///
+/// # Arguments
+///
+/// * `src` - Source planar image.
+/// * `dst` - Destination planar image, planes are allocated when it is empty.
+/// * `parameters` - See [BoxBlurParameters] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn box_blur_planar(
+    src: &BlurPlanarImage<u8>,
+    dst: &mut BlurPlanarImageMut<u8>,
+    parameters: BoxBlurParameters,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    planar_executor(src, dst, threading_policy, |src, dst, policy| {
+        box_blur(src, dst, parameters, policy)
+    })
+}
+
+/// Performs box blur on every plane of 16-bit planar image.
+///
+/// See [box_blur_planar] for more info.
+pub fn box_blur_planar_u16(
+    src: &BlurPlanarImage<u16>,
+    dst: &mut BlurPlanarImageMut<u16>,
+    parameters: BoxBlurParameters,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    planar_executor(src, dst, threading_policy, |src, dst, policy| {
+        box_blur_u16(src, dst, parameters, policy)
+    })
+}
+
+/// Performs box blur on every plane of f32 planar image.
+///
+/// See [box_blur_planar] for more info.
+pub fn box_blur_planar_f32(
+    src: &BlurPlanarImage<f32>,
+    dst: &mut BlurPlanarImageMut<f32>,
+    parameters: BoxBlurParameters,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    planar_executor(src, dst, threading_policy, |src, dst, policy| {
+        box_blur_f32(src, dst, parameters, policy)
+    })
+}
+
+/// Performs stack blur on every plane of planar image in place.
+///
+/// Planes are processed in parallel, threads allowed by [ThreadingPolicy] are split between planes.
+///
+/// # Arguments
+///
+/// * `image` - Planar image, used as source and destination.
+/// * `radius` - Radius more than 1, see [AnisotropicRadius] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn stack_blur_planar(
+    image: &mut BlurPlanarImageMut<u8>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    planar_executor_in_place(image, threading_policy, |_, plane, policy| {
+        stack_blur(plane, radius, policy)
+    })
+}
+
+/// Performs stack blur on every plane of 16-bit planar image in place.
+///
+/// See [stack_blur_planar] for more info.
+pub fn stack_blur_planar_u16(
+    image: &mut BlurPlanarImageMut<u16>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    planar_executor_in_place(image, threading_policy, |_, plane, policy| {
+        stack_blur_u16(plane, radius, policy)
+    })
+}
+
+/// Performs stack blur on every plane of f32 planar image in place.
+///
+/// See [stack_blur_planar] for more info.
+pub fn stack_blur_planar_f32(
+    image: &mut BlurPlanarImageMut<f32>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    planar_executor_in_place(image, threading_policy, |_, plane, policy| {
+        stack_blur_f32(plane, radius, policy)
+    })
+}
+
+/// Performs fast gaussian blur on every plane of planar image in place.
+///
+/// Planes are processed in parallel, threads allowed by [ThreadingPolicy] are split between planes.
+///
+/// # Arguments
+///
+/// * `image` - Planar image, used as source and destination.
+/// * `radius` - Radius more than 1, see [AnisotropicRadius] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `edge_mode` - Rule to handle edge mode, sse [EdgeMode] for more info.
+pub fn fast_gaussian_planar(
+    image: &mut BlurPlanarImageMut<u8>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) -> Result<(), BlurError> {
+    planar_executor_in_place(image, threading_policy, |_, plane, policy| {
+        fast_gaussian(plane, radius, policy, edge_mode)
+    })
+}
+
+/// Performs fast gaussian blur on every plane of 16-bit planar image in place.
+///
+/// See [fast_gaussian_planar] for more info.
+pub fn fast_gaussian_planar_u16(
+    image: &mut BlurPlanarImageMut<u16>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) -> Result<(), BlurError> {
+    planar_executor_in_place(image, threading_policy, |_, plane, policy| {
+        fast_gaussian_u16(plane, radius, policy, edge_mode)
+    })
+}
+
+/// Performs fast gaussian blur on every plane of f32 planar image in place.
+///
+/// See [fast_gaussian_planar] for more info.
+pub fn fast_gaussian_planar_f32(
+    image: &mut BlurPlanarImageMut<f32>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) -> Result<(), BlurError> {
+    planar_executor_in_place(image, threading_policy, |_, plane, policy| {
+        fast_gaussian_f32(plane, radius, policy, edge_mode)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gaussian_blur_planar_matches_interleaved() {
+        let (width, height) = (64u32, 48u32);
+        let interleaved: Vec<u8> = (0..width as usize * height as usize * 3)
+            .map(|i| ((i * 31 + i / 7) % 253) as u8)
+            .collect();
+        let planes: Vec<Vec<u8>> = (0..3)
+            .map(|c| interleaved.iter().skip(c).step_by(3).copied().collect())
+            .collect();
+        let params = GaussianBlurParams::new_from_kernel(7.);
+
+        let src = BlurImage::borrow(&interleaved, width, height, FastBlurChannels::Channels3);
+        let mut dst = BlurImageMut::default();
+        gaussian_blur(
+            &src,
+            &mut dst,
+            params,
+            EdgeMode::Clamp,
+            ThreadingPolicy::Single,
+            ConvolutionMode::Exact,
+        )
+        .unwrap();
+
+        let planar_src = BlurPlanarImage::borrow(
+            &planes.iter().map(|x| x.as_slice()).collect::<Vec<_>>(),
+            width,
+            height,
+        );
+        let mut planar_dst = BlurPlanarImageMut::default();
+        gaussian_blur_planar(
+            &planar_src,
+            &mut planar_dst,
+            params,
+            EdgeMode::Clamp,
+            ThreadingPolicy::Fixed(NonZeroUsize::new(4).unwrap()),
+            ConvolutionMode::Exact,
+        )
+        .unwrap();
+
+        for (c, plane) in planar_dst.planes.iter().enumerate() {
+            for (a, b) in plane
+                .data
+                .borrow()
+                .iter()
+                .zip(dst.data.borrow().iter().skip(c).step_by(3))
+            {
+                assert_eq!(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_stack_blur_planar_subsampled() {
+        let mut luma = vec![90u8; 32 * 32];
+        let mut chroma_u = vec![40u8; 16 * 16];
+        let mut chroma_v = vec![200u8; 16 * 16];
+        let mut image = BlurPlanarImageMut::new(vec![
+            BlurImageMut::borrow(&mut luma, 32, 32, FastBlurChannels::Plane),
+            BlurImageMut::borrow(&mut chroma_u, 16, 16, FastBlurChannels::Plane),
+            BlurImageMut::borrow(&mut chroma_v, 16, 16, FastBlurChannels::Plane),
+        ]);
+        stack_blur_planar(
+            &mut image,
+            AnisotropicRadius::new(3),
+            ThreadingPolicy::Adaptive,
+        )
+        .unwrap();
+        for (plane, expected) in image.planes.iter().zip([90u8, 40, 200]) {
+            assert!(plane
+                .data
+                .borrow()
+                .iter()
+                .all(|&x| (x as i32 - expected as i32).abs() <= 1));
+        }
+    }
+
+    #[test]
+    fn test_planar_planes_mismatch() {
+        let a = vec![0u8; 16];
+        let src = BlurPlanarImage::borrow(&[&a, &a], 4, 4);
+        let mut b = vec![0u8; 16];
+        let mut dst = BlurPlanarImageMut::borrow(vec![&mut b], 4, 4);
+        assert!(box_blur_planar(
+            &src,
+            &mut dst,
+            BoxBlurParameters::new(3),
+            ThreadingPolicy::Single
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,581 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::edge_mode::clamp_edge;
+use crate::to_storage::ToStorage;
+use crate::{
+    BlurError, BlurImage, BlurImageMut, EdgeMode, FastBlurChannels, Scalar, ThreadingPolicy,
+};
+use novtb::{ParallelZonedIterator, TbSliceMut};
+use num_traits::AsPrimitive;
+use std::fmt::Debug;
+
+/// Zoom blur parameters
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ZoomBlurParams {
+    /// Zoom center X in pixels.
+    pub center_x: f32,
+    /// Zoom center Y in pixels.
+    pub center_y: f32,
+    /// Fraction of the distance to the center covered by the blur, should be in [0; 1].
+    pub strength: f32,
+}
+
+impl ZoomBlurParams {
+    pub fn new(center_x: f32, center_y: f32, strength: f32) -> ZoomBlurParams {
+        ZoomBlurParams {
+            center_x,
+            center_y,
+            strength,
+        }
+    }
+
+    fn path(&self) -> Result<RadialPath, BlurError> {
+        if !self.center_x.is_finite()
+            || !self.center_y.is_finite()
+            || self.strength.is_nan()
+            || self.strength < 0.
+            || self.strength > 1.
+        {
+            return Err(BlurError::InvalidArguments);
+        }
+        Ok(RadialPath {
+            center_x: self.center_x,
+            center_y: self.center_y,
+            motion: RadialMotion::Zoom(self.strength),
+        })
+    }
+}
+
+/// Spin blur parameters
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SpinBlurParams {
+    /// Rotation center X in pixels.
+    pub center_x: f32,
+    /// Rotation center Y in pixels.
+    pub center_y: f32,
+    /// Full arc angle in degrees, arc is centered at the pixel, should be in [-360; 360].
+    pub angle: f32,
+}
+
+impl SpinBlurParams {
+    pub fn new(center_x: f32, center_y: f32, angle: f32) -> SpinBlurParams {
+        SpinBlurParams {
+            center_x,
+            center_y,
+            angle,
+        }
+    }
+
+    fn path(&self) -> Result<RadialPath, BlurError> {
+        if !self.center_x.is_finite()
+            || !self.center_y.is_finite()
+            || self.angle.is_nan()
+            || self.angle.abs() > 360.
+        {
+            return Err(BlurError::InvalidArguments);
+        }
+        Ok(RadialPath {
+            center_x: self.center_x,
+            center_y: self.center_y,
+            motion: RadialMotion::Spin(self.angle.to_radians()),
+        })
+    }
+}
+
+#[derive(Copy, Clone)]
+enum RadialMotion {
+    /// Strength
+    Zoom(f32),
+    /// Arc angle in radians
+    Spin(f32),
+}
+
+#[derive(Copy, Clone)]
+struct RadialPath {
+    center_x: f32,
+    center_y: f32,
+    motion: RadialMotion,
+}
+
+impl RadialPath {
+    /// Number of samples for the pixel at offset `(dx, dy)` from the center,
+    /// path is sampled about once per pixel of its length so long streaks do not break into copies.
+    #[inline]
+    fn samples(&self, dx: f32, dy: f32) -> usize {
+        let distance = dx.hypot(dy);
+        let length = match self.motion {
+            RadialMotion::Zoom(strength) => strength * distance,
+            RadialMotion::Spin(angle) => angle.abs() * distance,
+        };
+        length.ceil() as usize + 1
+    }
+
+    /// Sample position for path parameter `t` in [0; 1]
+    #[inline]
+    fn position(&self, dx: f32, dy: f32, t: f32) -> (f32, f32) {
+        match self.motion {
+            RadialMotion::Zoom(strength) => {
+                let scale = 1. - strength * t;
+                (self.center_x + dx * scale, self.center_y + dy * scale)
+            }
+            RadialMotion::Spin(angle) => {
+                let (sin, cos) = (angle * (t - 0.5)).sin_cos();
+                (
+                    self.center_x + dx * cos - dy * sin,
+                    self.center_y + dx * sin + dy * cos,
+                )
+            }
+        }
+    }
+}
+
+#[inline(always)]
+fn fetch<T: Copy + AsPrimitive<f32>, const CN: usize>(
+    src: &[T],
+    stride: usize,
+    width: i64,
+    height: i64,
+    x: i64,
+    y: i64,
+    edge_mode: EdgeMode,
+    border_constant: &Scalar,
+) -> [f32; CN] {
+    if edge_mode == EdgeMode::Constant && (x < 0 || y < 0 || x >= width || y >= height) {
+        return std::array::from_fn(|c| border_constant[c] as f32);
+    }
+    let px = clamp_edge!(edge_mode, x, 0, width);
+    let py = clamp_edge!(edge_mode, y, 0, height);
+    let offset = py * stride + px * CN;
+    std::array::from_fn(|c| src[offset + c].as_())
+}
+
+fn radial_blur_impl<T, const CN: usize>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    path: RadialPath,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) where
+    T: Copy + Default + Send + Sync + Debug + AsPrimitive<f32>,
+    f32: ToStorage<T>,
+{
+    let width = src.width as i64;
+    let height = src.height as i64;
+    let src_stride = src.row_stride() as usize;
+    let dst_stride = dst.row_stride() as usize;
+    let src_data = src.data.as_ref();
+
+    let thread_count = threading_policy.thread_count(src.width, src.height);
+    let pool = novtb::ThreadPool::new(thread_count);
+
+    dst.data
+        .borrow_mut()
+        .tb_par_chunks_mut(dst_stride)
+        .for_each_enumerated(&pool, |y, row| {
+            let dy = y as f32 - path.center_y;
+            for (x, dst) in row[..src.width as usize * CN]
+                .chunks_exact_mut(CN)
+                .enumerate()
+            {
+                let dx = x as f32 - path.center_x;
+                let samples = path.samples(dx, dy);
+                let t_step = if samples > 1 {
+                    1. / (samples - 1) as f32
+                } else {
+                    0.
+                };
+                let mut sums = [0f32; CN];
+                for s in 0..samples {
+                    let (sx, sy) = path.position(dx, dy, s as f32 * t_step);
+                    // Bilinear interpolation
+                    let x0 = sx.floor();
+                    let y0 = sy.floor();
+                    let fx = sx - x0;
+                    let fy = sy - y0;
+                    let (x0, y0) = (x0 as i64, y0 as i64);
+                    let taps = [
+                        (x0, y0, (1. - fx) * (1. - fy)),
+                        (x0 + 1, y0, fx * (1. - fy)),
+                        (x0, y0 + 1, (1. - fx) * fy),
+                        (x0 + 1, y0 + 1, fx * fy),
+                    ];
+                    for (tx, ty, weight) in taps {
+                        if weight == 0. {
+                            continue;
+                        }
+                        let px = fetch::<T, CN>(
+                            src_data,
+                            src_stride,
+                            width,
+                            height,
+                            tx,
+                            ty,
+                            edge_mode,
+                            &border_constant,
+                        );
+                        if CN == 4 {
+                            // Colors are accumulated premultiplied by alpha
+                            let alpha = px[3] * weight;
+                            for (sum, v) in sums[..3].iter_mut().zip(px.iter()) {
+                                *sum += v * alpha;
+                            }
+                            sums[3] += alpha;
+                        } else {
+                            for (sum, v) in sums.iter_mut().zip(px.iter()) {
+                                *sum += v * weight;
+                            }
+                        }
+                    }
+                }
+                if CN == 4 {
+                    let alpha = sums[3];
+                    let recip = if alpha > 0. { 1. / alpha } else { 0. };
+                    for (dst, sum) in dst[..3].iter_mut().zip(sums.iter()) {
+                        *dst = (sum * recip).to_();
+                    }
+                    dst[3] = (alpha / samples as f32).to_();
+                } else {
+                    let norm = 1. / samples as f32;
+                    for (dst, sum) in dst.iter_mut().zip(sums.iter()) {
+                        *dst = (sum * norm).to_();
+                    }
+                }
+            }
+        });
+}
+
+fn radial_blur_dispatch<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    path: RadialPath,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Send + Sync + Debug + AsPrimitive<f32>,
+    f32: ToStorage<T>,
+{
+    src.check_layout()?;
+    dst.check_layout(Some(src))?;
+    src.size_matches_mut(dst)?;
+    match src.channels {
+        FastBlurChannels::Plane => {
+            radial_blur_impl::<T, 1>(src, dst, path, edge_mode, border_constant, threading_policy)
+        }
+        FastBlurChannels::Channels2 => {
+            radial_blur_impl::<T, 2>(src, dst, path, edge_mode, border_constant, threading_policy)
+        }
+        FastBlurChannels::Channels3 => {
+            radial_blur_impl::<T, 3>(src, dst, path, edge_mode, border_constant, threading_policy)
+        }
+        FastBlurChannels::Channels4 => {
+            radial_blur_impl::<T, 4>(src, dst, path, edge_mode, border_constant, threading_policy)
+        }
+    }
+    Ok(())
+}
+
+/// Performs zoom blur, averaging samples along the ray from the pixel towards the center.
+///
+/// Pixels near the center stay sharp, blur grows with distance.
+/// Path is sampled about once per pixel of its length, so cost per pixel grows with the streak.
+/// [FastBlurChannels::Channels4] is averaged with premultiplied alpha.
+///
+/// # Arguments
+///
+/// * `src` - Source image, see [BlurImage] for more info.
+/// * `dst` - Destination image, see [BlurImageMut] for more info.
+/// * `params` - See [ZoomBlurParams] for more info.
+/// * `border_mode` - Border handling mode see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn zoom_blur(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    params: ZoomBlurParams,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    let path = params.path()?;
+    radial_blur_dispatch(
+        src,
+        dst,
+        path,
+        border_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs zoom blur on the 16-bit image.
+///
+/// See [zoom_blur] for more info.
+pub fn zoom_blur_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    params: ZoomBlurParams,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    let path = params.path()?;
+    radial_blur_dispatch(
+        src,
+        dst,
+        path,
+        border_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs zoom blur on the f32 image.
+///
+/// See [zoom_blur] for more info.
+pub fn zoom_blur_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    params: ZoomBlurParams,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    let path = params.path()?;
+    radial_blur_dispatch(
+        src,
+        dst,
+        path,
+        border_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs spin blur, averaging samples along the arc around the center.
+///
+/// Pixels near the center stay sharp, blur grows with distance.
+/// Path is sampled about once per pixel of its length, so cost per pixel grows with the streak.
+/// [FastBlurChannels::Channels4] is averaged with premultiplied alpha.
+///
+/// # Arguments
+///
+/// * `src` - Source image, see [BlurImage] for more info.
+/// * `dst` - Destination image, see [BlurImageMut] for more info.
+/// * `params` - See [SpinBlurParams] for more info.
+/// * `border_mode` - Border handling mode see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn spin_blur(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    params: SpinBlurParams,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    let path = params.path()?;
+    radial_blur_dispatch(
+        src,
+        dst,
+        path,
+        border_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs spin blur on the 16-bit image.
+///
+/// See [spin_blur] for more info.
+pub fn spin_blur_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    params: SpinBlurParams,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    let path = params.path()?;
+    radial_blur_dispatch(
+        src,
+        dst,
+        path,
+        border_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs spin blur on the f32 image.
+///
+/// See [spin_blur] for more info.
+pub fn spin_blur_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    params: SpinBlurParams,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    let path = params.path()?;
+    radial_blur_dispatch(
+        src,
+        dst,
+        path,
+        border_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_radial_blur_flat() {
+        let (width, height) = (64usize, 48usize);
+        let src = vec![126u8; width * height * 3];
+        let image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let mut dst = BlurImageMut::default();
+        zoom_blur(
+            &image,
+            &mut dst,
+            ZoomBlurParams::new(20., 30., 0.5),
+            EdgeMode::Clamp,
+            Scalar::default(),
+            ThreadingPolicy::Adaptive,
+        )
+        .unwrap();
+        assert!(dst.data.borrow().iter().all(|&v| v.abs_diff(126) <= 1));
+        let mut dst = BlurImageMut::default();
+        spin_blur(
+            &image,
+            &mut dst,
+            SpinBlurParams::new(20., 30., 30.),
+            EdgeMode::Reflect101,
+            Scalar::default(),
+            ThreadingPolicy::Adaptive,
+        )
+        .unwrap();
+        assert!(dst.data.borrow().iter().all(|&v| v.abs_diff(126) <= 1));
+    }
+
+    #[test]
+    fn test_spin_blur_keeps_rings() {
+        // Concentric rings are invariant under rotation, while zoom smears them
+        let size = 65usize;
+        let center = 32f32;
+        let src = (0..size * size)
+            .map(|i| {
+                let (x, y) = ((i % size) as f32 - center, (i / size) as f32 - center);
+                if (x.hypot(y) / 6.) as usize % 2 == 0 {
+                    0.
+                } else {
+                    1.
+                }
+            })
+            .collect::<Vec<f32>>();
+        let image = BlurImage::borrow(&src, size as u32, size as u32, FastBlurChannels::Plane);
+        let mut spin = BlurImageMut::default();
+        spin_blur_f32(
+            &image,
+            &mut spin,
+            SpinBlurParams::new(center, center, 40.),
+            EdgeMode::Clamp,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let mut zoom = BlurImageMut::default();
+        zoom_blur_f32(
+            &image,
+            &mut zoom,
+            ZoomBlurParams::new(center, center, 0.4),
+            EdgeMode::Clamp,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let spin = spin.data.borrow();
+        let zoom = zoom.data.borrow();
+        // Middle of the ring at radius 27
+        let idx = 32 * size + 32 + 27;
+        assert!((spin[idx] - src[idx]).abs() < 0.15);
+        assert!((zoom[idx] - src[idx]).abs() > 0.3);
+        assert_eq!(spin[32 * size + 32], src[32 * size + 32]);
+        assert_eq!(zoom[32 * size + 32], src[32 * size + 32]);
+    }
+
+    #[test]
+    fn test_zoom_blur_premultiplies_alpha() {
+        // Opaque red next to transparent green, green must not bleed into the streaks
+        let (width, height) = (48usize, 32usize);
+        let mut src = vec![0u8; width * height * 4];
+        for (i, px) in src.chunks_exact_mut(4).enumerate() {
+            if i % width < width / 2 {
+                px.copy_from_slice(&[255, 0, 0, 255]);
+            } else {
+                px.copy_from_slice(&[0, 255, 0, 0]);
+            }
+        }
+        let image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels4,
+        );
+        let mut dst = BlurImageMut::default();
+        zoom_blur(
+            &image,
+            &mut dst,
+            ZoomBlurParams::new(0., 16., 0.8),
+            EdgeMode::Clamp,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let dst = dst.data.borrow();
+        // Pixels right of the edge pick up red from samples towards the center
+        assert!(dst[(16 * width + 30) * 4 + 3] > 0);
+        for px in dst.chunks_exact(4) {
+            if px[3] > 0 {
+                assert_eq!(px[..3], [255, 0, 0]);
+            }
+        }
+    }
+}
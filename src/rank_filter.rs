@@ -0,0 +1,237 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::median_blur::median_blur_rank;
+use crate::median_blur_f32::median_blur_f32_rank;
+use crate::median_blur_u16::median_blur_u16_rank;
+use crate::{BlurError, BlurImage, BlurImageMut, EdgeMode, Scalar, ThreadingPolicy};
+
+/// Performs rank filter on the image.
+///
+/// Every pixel is replaced with the value at `percentile` of the sorted window,
+/// `0` is minimum (erosion), `50` is median, `100` is maximum (dilation).
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src_image` - Src image, see [BlurImage] for more info
+/// * `dst_image` - Destination image, see [BlurImageMut] for more info
+/// * `radius` - Radius of kernel
+/// * `percentile` - Percentile in range [0, 100]
+/// * `edge_mode` - Rule to handle edge mode, sse [EdgeMode] for more info
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy]
+pub fn rank_filter(
+    src_image: &BlurImage<u8>,
+    dst_image: &mut BlurImageMut<u8>,
+    radius: u32,
+    percentile: f32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    median_blur_rank(
+        src_image,
+        dst_image,
+        radius,
+        percentile,
+        edge_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs rank filter on the 16-bit image.
+///
+/// See [rank_filter] for more info.
+pub fn rank_filter_u16(
+    src_image: &BlurImage<u16>,
+    dst_image: &mut BlurImageMut<u16>,
+    radius: u32,
+    percentile: f32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    median_blur_u16_rank(
+        src_image,
+        dst_image,
+        radius,
+        percentile,
+        edge_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs rank filter on the f32 image.
+///
+/// See [rank_filter] for more info, this one has O(R^2) complexity.
+pub fn rank_filter_f32(
+    src_image: &BlurImage<f32>,
+    dst_image: &mut BlurImageMut<f32>,
+    radius: u32,
+    percentile: f32,
+    edge_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    median_blur_f32_rank(
+        src_image,
+        dst_image,
+        radius,
+        percentile,
+        edge_mode,
+        border_constant,
+        threading_policy,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FastBlurChannels;
+
+    #[test]
+    fn test_rank_filter_min_max() {
+        let (width, height) = (21usize, 14usize);
+        let mut src = vec![17234u16; width * height * 3];
+        src[(6 * width + 4) * 3..(6 * width + 5) * 3].fill(1000);
+        src[(6 * width + 14) * 3..(6 * width + 15) * 3].fill(60000);
+        let src_image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        // Minimum grows the dark spot into the 5x5 window and drops the bright one,
+        // maximum does the opposite.
+        for (percentile, spot_x, spot_value) in [(0f32, 4usize, 1000u16), (100., 14, 60000)] {
+            let mut dst_image = BlurImageMut::default();
+            rank_filter_u16(
+                &src_image,
+                &mut dst_image,
+                2,
+                percentile,
+                EdgeMode::Clamp,
+                Scalar::default(),
+                ThreadingPolicy::Adaptive,
+            )
+            .unwrap();
+            let dst = dst_image.data.borrow();
+            for y in 0..height {
+                for x in 0..width {
+                    let inside = y.abs_diff(6) <= 2 && x.abs_diff(spot_x) <= 2;
+                    let expected = if inside { spot_value } else { 17234 };
+                    for c in 0..3 {
+                        assert_eq!(dst[(y * width + x) * 3 + c], expected);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rank_filter_f32_impulse() {
+        let (width, height) = (15usize, 11usize);
+        let mut src = vec![0.5f32; width * height];
+        src[5 * width + 7] = 0.1;
+        let src_image =
+            BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        for (percentile, expected_spot) in [(0f32, 0.1f32), (50., 0.5), (100., 0.5)] {
+            let mut dst_image = BlurImageMut::default();
+            rank_filter_f32(
+                &src_image,
+                &mut dst_image,
+                2,
+                percentile,
+                EdgeMode::Clamp,
+                Scalar::default(),
+                ThreadingPolicy::Single,
+            )
+            .unwrap();
+            let dst = dst_image.data.borrow();
+            for y in 0..height {
+                for x in 0..width {
+                    let inside = y.abs_diff(5) <= 2 && x.abs_diff(7) <= 2;
+                    let expected = if inside { expected_spot } else { 0.5 };
+                    assert_eq!(dst[y * width + x], expected, "percentile {percentile}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rank_filter_f32_percentile_ramp() {
+        let (width, height) = (12usize, 6usize);
+        let src: Vec<f32> = (0..width * height)
+            .map(|i| (i % width) as f32 * 0.25)
+            .collect();
+        let src_image =
+            BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        // 3x3 window over a horizontal ramp holds each of x - 1, x, x + 1 three times,
+        // so 25% selects the left neighbour and 75% the right one.
+        for (percentile, shift) in [(0f32, -1isize), (25., -1), (50., 0), (75., 1), (100., 1)] {
+            let mut dst_image = BlurImageMut::default();
+            rank_filter_f32(
+                &src_image,
+                &mut dst_image,
+                1,
+                percentile,
+                EdgeMode::Clamp,
+                Scalar::default(),
+                ThreadingPolicy::Single,
+            )
+            .unwrap();
+            let dst = dst_image.data.borrow();
+            for y in 0..height {
+                for x in 1..width - 1 {
+                    let expected = (x as isize + shift) as f32 * 0.25;
+                    assert_eq!(dst[y * width + x], expected, "percentile {percentile}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rank_filter_invalid_percentile() {
+        let src = vec![0u8; 16];
+        let src_image = BlurImage::borrow(&src, 4, 4, FastBlurChannels::Plane);
+        let mut dst_image = BlurImageMut::default();
+        assert!(rank_filter(
+            &src_image,
+            &mut dst_image,
+            1,
+            101.,
+            EdgeMode::Clamp,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .is_err());
+    }
+}
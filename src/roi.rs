@@ -0,0 +1,716 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::filter1d::copy_roi;
+use crate::filter2d::Filter2dHandler;
+use crate::to_storage::ToStorage;
+use crate::{
+    box_blur, box_blur_f32, box_blur_u16, fast_gaussian, fast_gaussian_f32, fast_gaussian_u16,
+    filter_2d, gaussian_blur, gaussian_blur_f32, gaussian_blur_u16, stack_blur, stack_blur_f32,
+    stack_blur_u16, AnisotropicRadius, BlurError, BlurImage, BlurImageMut, BoxBlurParameters,
+    ConvolutionMode, EdgeMode, GaussianBlurParams, IeeeBinaryConvolutionMode, KernelShape, Scalar,
+    ThreadingPolicy,
+};
+use num_traits::{AsPrimitive, MulAdd};
+use std::fmt::Debug;
+use std::ops::Mul;
+
+/// Rectangle in pixels, used as region of interest
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Checks if rectangle is not empty and fully lies inside an image
+    pub fn check_fits(&self, width: u32, height: u32) -> Result<(), BlurError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(BlurError::ZeroBaseSize);
+        }
+        if self.x as u64 + self.width as u64 > width as u64
+            || self.y as u64 + self.height as u64 > height as u64
+        {
+            return Err(BlurError::InvalidArguments);
+        }
+        Ok(())
+    }
+
+    /// Grows rectangle by pads on each side and clips it to image bounds
+    fn expand(&self, pad_x: u32, pad_y: u32, width: u32, height: u32) -> Rect {
+        let x = self.x.saturating_sub(pad_x);
+        let y = self.y.saturating_sub(pad_y);
+        let right = (self.x + self.width).saturating_add(pad_x).min(width);
+        let bottom = (self.y + self.height).saturating_add(pad_y).min(height);
+        Rect::new(x, y, right - x, bottom - y)
+    }
+}
+
+/// Copies padded area around ROI into new image, so area border is an image border
+/// only where ROI actually touches the image edge.
+fn crop<T: Copy + Default + Debug>(image: &BlurImage<T>, area: Rect) -> BlurImageMut<'static, T> {
+    let cn = image.channels.channels();
+    let stride = image.row_stride() as usize;
+    let mut cropped = BlurImageMut::alloc(area.width, area.height, image.channels);
+    let cropped_stride = cropped.row_stride() as usize;
+    let offset = area.y as usize * stride + area.x as usize * cn;
+    copy_roi(
+        cropped.data.borrow_mut(),
+        &image.data.as_ref()[offset..],
+        cropped_stride,
+        stride,
+    );
+    cropped
+}
+
+/// Writes ROI part of blurred area back into the image.
+fn write_back<T: Copy + Default + Debug>(
+    blurred: &BlurImageMut<T>,
+    area: Rect,
+    dst: &mut BlurImageMut<T>,
+    roi: Rect,
+) {
+    let cn = dst.channels.channels();
+    let dst_stride = dst.row_stride() as usize;
+    let blurred_stride = blurred.row_stride() as usize;
+    let start_x = (roi.x - area.x) as usize * cn;
+    let start_y = (roi.y - area.y) as usize;
+    let row_length = roi.width as usize * cn;
+    let src_rows = blurred
+        .data
+        .borrow()
+        .chunks(blurred_stride)
+        .skip(start_y)
+        .take(roi.height as usize);
+    let dst_rows = dst
+        .data
+        .borrow_mut()
+        .chunks_mut(dst_stride)
+        .skip(roi.y as usize)
+        .take(roi.height as usize);
+    for (src, dst) in src_rows.zip(dst_rows) {
+        let src = &src[start_x..start_x + row_length];
+        let dst = &mut dst[roi.x as usize * cn..roi.x as usize * cn + row_length];
+        dst.copy_from_slice(src);
+    }
+}
+
+fn roi_executor<T, F>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    roi: Rect,
+    pad_x: u32,
+    pad_y: u32,
+    executor: F,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + 'static,
+    F: FnOnce(&BlurImage<T>, &mut BlurImageMut<T>) -> Result<(), BlurError>,
+{
+    src.check_layout()?;
+    dst.check_layout(Some(src))?;
+    src.size_matches_mut(dst)?;
+    roi.check_fits(src.width, src.height)?;
+    let area = roi.expand(pad_x, pad_y, src.width, src.height);
+    let cropped = crop(src, area);
+    let mut blurred = BlurImageMut::default();
+    executor(&cropped.to_immutable_ref(), &mut blurred)?;
+    write_back(&blurred, area, dst, roi);
+    Ok(())
+}
+
+fn roi_executor_in_place<T, F>(
+    image: &mut BlurImageMut<T>,
+    roi: Rect,
+    pad_x: u32,
+    pad_y: u32,
+    executor: F,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + 'static,
+    F: FnOnce(&mut BlurImageMut<T>) -> Result<(), BlurError>,
+{
+    image.check_layout(None)?;
+    roi.check_fits(image.width, image.height)?;
+    let area = roi.expand(pad_x, pad_y, image.width, image.height);
+    let mut cropped = crop(&image.to_immutable_ref(), area);
+    executor(&mut cropped)?;
+    write_back(&cropped, area, image, roi);
+    Ok(())
+}
+
+/// Performs gaussian blur only inside region of interest.
+///
+/// Pixels around ROI are read as real neighbourhood, edge handling applies only where
+/// ROI touches image bounds. Pixels outside ROI in `dst` are left untouched.
+///
+/// # Arguments
+///
+/// * `src` - Source image.
+/// * `dst` - Destination image, must have the same size as source.
+/// * `roi` - Region of interest, must lie inside the image, see [Rect].
+/// * `params` - See [GaussianBlurParams] for more info.
+/// * `edge_mode` - Rule to handle edge mode, sse [EdgeMode] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `hint` - see [ConvolutionMode] for more info.
+pub fn gaussian_blur_roi(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    roi: Rect,
+    params: GaussianBlurParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    hint: ConvolutionMode,
+) -> Result<(), BlurError> {
+    let (x_kernel, y_kernel) = params.kernel_sizes();
+    roi_executor(src, dst, roi, x_kernel / 2, y_kernel / 2, |s, d| {
+        gaussian_blur(s, d, params, edge_mode, threading_policy, hint)
+    })
+}
+
+/// Performs gaussian blur only inside region of interest.
+///
+/// Pixels around ROI are read as real neighbourhood, edge handling applies only where
+/// ROI touches image bounds. Pixels outside ROI in `dst` are left untouched.
+///
+/// # Arguments
+///
+/// * `src` - Source image.
+/// * `dst` - Destination image, must have the same size as source.
+/// * `roi` - Region of interest, must lie inside the image, see [Rect].
+/// * `params` - See [GaussianBlurParams] for more info.
+/// * `edge_mode` - Rule to handle edge mode, sse [EdgeMode] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `hint` - see [ConvolutionMode] for more info.
+pub fn gaussian_blur_roi_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    roi: Rect,
+    params: GaussianBlurParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    hint: ConvolutionMode,
+) -> Result<(), BlurError> {
+    let (x_kernel, y_kernel) = params.kernel_sizes();
+    roi_executor(src, dst, roi, x_kernel / 2, y_kernel / 2, |s, d| {
+        gaussian_blur_u16(s, d, params, edge_mode, threading_policy, hint)
+    })
+}
+
+/// Performs gaussian blur only inside region of interest.
+///
+/// Pixels around ROI are read as real neighbourhood, edge handling applies only where
+/// ROI touches image bounds. Pixels outside ROI in `dst` are left untouched.
+///
+/// # Arguments
+///
+/// * `src` - Source image.
+/// * `dst` - Destination image, must have the same size as source.
+/// * `roi` - Region of interest, must lie inside the image, see [Rect].
+/// * `params` - See [GaussianBlurParams] for more info.
+/// * `edge_mode` - Rule to handle edge mode, sse [EdgeMode] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `convolution_mode` - see [IeeeBinaryConvolutionMode] for more info.
+pub fn gaussian_blur_roi_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    roi: Rect,
+    params: GaussianBlurParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    convolution_mode: IeeeBinaryConvolutionMode,
+) -> Result<(), BlurError> {
+    let (x_kernel, y_kernel) = params.kernel_sizes();
+    roi_executor(src, dst, roi, x_kernel / 2, y_kernel / 2, |s, d| {
+        gaussian_blur_f32(s, d, params, edge_mode, threading_policy, convolution_mode)
+    })
+}
+
+/// Performs box blur only inside region of interest.
+///
+/// Pixels around ROI are read as real neighbourhood, pixels outside ROI in `dst` are left untouched.
+///
+/// # Arguments
+///
+/// * `src` - Source image.
+/// * `dst` - Destination image, must have the same size as source.
+/// * `roi` - Region of interest, must lie inside the image, see [Rect].
+/// * `parameters` - See [BoxBlurParameters] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn box_blur_roi(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    roi: Rect,
+    parameters: BoxBlurParameters,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    roi_executor(
+        src,
+        dst,
+        roi,
+        parameters.x_axis_kernel / 2,
+        parameters.y_axis_kernel / 2,
+        |s, d| box_blur(s, d, parameters, threading_policy),
+    )
+}
+
+/// Performs box blur only inside region of interest.
+///
+/// Pixels around ROI are read as real neighbourhood, pixels outside ROI in `dst` are left untouched.
+///
+/// # Arguments
+///
+/// * `src` - Source image.
+/// * `dst` - Destination image, must have the same size as source.
+/// * `roi` - Region of interest, must lie inside the image, see [Rect].
+/// * `parameters` - See [BoxBlurParameters] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn box_blur_roi_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    roi: Rect,
+    parameters: BoxBlurParameters,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    roi_executor(
+        src,
+        dst,
+        roi,
+        parameters.x_axis_kernel / 2,
+        parameters.y_axis_kernel / 2,
+        |s, d| box_blur_u16(s, d, parameters, threading_policy),
+    )
+}
+
+/// Performs box blur only inside region of interest.
+///
+/// Pixels around ROI are read as real neighbourhood, pixels outside ROI in `dst` are left untouched.
+///
+/// # Arguments
+///
+/// * `src` - Source image.
+/// * `dst` - Destination image, must have the same size as source.
+/// * `roi` - Region of interest, must lie inside the image, see [Rect].
+/// * `parameters` - See [BoxBlurParameters] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn box_blur_roi_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    roi: Rect,
+    parameters: BoxBlurParameters,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    roi_executor(
+        src,
+        dst,
+        roi,
+        parameters.x_axis_kernel / 2,
+        parameters.y_axis_kernel / 2,
+        |s, d| box_blur_f32(s, d, parameters, threading_policy),
+    )
+}
+
+/// Performs stack blur in place only inside region of interest.
+///
+/// Pixels around ROI are read as real neighbourhood, pixels outside ROI are left untouched.
+///
+/// # Arguments
+///
+/// * `image` - Image to work in place, see [BlurImageMut] for more info.
+/// * `roi` - Region of interest, must lie inside the image, see [Rect].
+/// * `radius` - See [crate::stack_blur] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn stack_blur_roi(
+    image: &mut BlurImageMut<u8>,
+    roi: Rect,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    roi_executor_in_place(image, roi, radius.x_axis, radius.y_axis, |i| {
+        stack_blur(i, radius, threading_policy)
+    })
+}
+
+/// Performs stack blur in place only inside region of interest.
+///
+/// Pixels around ROI are read as real neighbourhood, pixels outside ROI are left untouched.
+///
+/// # Arguments
+///
+/// * `image` - Image to work in place, see [BlurImageMut] for more info.
+/// * `roi` - Region of interest, must lie inside the image, see [Rect].
+/// * `radius` - See [crate::stack_blur_u16] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn stack_blur_roi_u16(
+    image: &mut BlurImageMut<u16>,
+    roi: Rect,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    roi_executor_in_place(image, roi, radius.x_axis, radius.y_axis, |i| {
+        stack_blur_u16(i, radius, threading_policy)
+    })
+}
+
+/// Performs stack blur in place only inside region of interest.
+///
+/// Pixels around ROI are read as real neighbourhood, pixels outside ROI are left untouched.
+///
+/// # Arguments
+///
+/// * `image` - Image to work in place, see [BlurImageMut] for more info.
+/// * `roi` - Region of interest, must lie inside the image, see [Rect].
+/// * `radius` - See [crate::stack_blur_f32] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn stack_blur_roi_f32(
+    image: &mut BlurImageMut<f32>,
+    roi: Rect,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    roi_executor_in_place(image, roi, radius.x_axis, radius.y_axis, |i| {
+        stack_blur_f32(i, radius, threading_policy)
+    })
+}
+
+/// Performs gaussian approximation in place only inside region of interest.
+///
+/// Pixels around ROI are read as real neighbourhood, edge handling applies only where
+/// ROI touches image bounds. Pixels outside ROI are left untouched.
+///
+/// # Arguments
+///
+/// * `image` - Image to work in place, see [BlurImageMut] for more info.
+/// * `roi` - Region of interest, must lie inside the image, see [Rect].
+/// * `radius` - See [crate::fast_gaussian] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+pub fn fast_gaussian_roi(
+    image: &mut BlurImageMut<u8>,
+    roi: Rect,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) -> Result<(), BlurError> {
+    roi_executor_in_place(image, roi, radius.x_axis, radius.y_axis, |i| {
+        fast_gaussian(i, radius, threading_policy, edge_mode)
+    })
+}
+
+/// Performs gaussian approximation in place only inside region of interest.
+///
+/// Pixels around ROI are read as real neighbourhood, edge handling applies only where
+/// ROI touches image bounds. Pixels outside ROI are left untouched.
+///
+/// # Arguments
+///
+/// * `image` - Image to work in place, see [BlurImageMut] for more info.
+/// * `roi` - Region of interest, must lie inside the image, see [Rect].
+/// * `radius` - See [crate::fast_gaussian_u16] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+pub fn fast_gaussian_roi_u16(
+    image: &mut BlurImageMut<u16>,
+    roi: Rect,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) -> Result<(), BlurError> {
+    roi_executor_in_place(image, roi, radius.x_axis, radius.y_axis, |i| {
+        fast_gaussian_u16(i, radius, threading_policy, edge_mode)
+    })
+}
+
+/// Performs gaussian approximation in place only inside region of interest.
+///
+/// Pixels around ROI are read as real neighbourhood, edge handling applies only where
+/// ROI touches image bounds. Pixels outside ROI are left untouched.
+///
+/// # Arguments
+///
+/// * `image` - Image to work in place, see [BlurImageMut] for more info.
+/// * `roi` - Region of interest, must lie inside the image, see [Rect].
+/// * `radius` - See [crate::fast_gaussian_f32] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+pub fn fast_gaussian_roi_f32(
+    image: &mut BlurImageMut<f32>,
+    roi: Rect,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) -> Result<(), BlurError> {
+    roi_executor_in_place(image, roi, radius.x_axis, radius.y_axis, |i| {
+        fast_gaussian_f32(i, radius, threading_policy, edge_mode)
+    })
+}
+
+/// This performs direct 2D convolution only inside region of interest.
+///
+/// Pixels around ROI are read as real neighbourhood, border handling applies only where
+/// ROI touches image bounds. Pixels outside ROI in `dst` are left untouched.
+///
+/// # Arguments
+///
+/// * `src`: Source image.
+/// * `dst`: Destination image, must have the same size as source.
+/// * `roi`: Region of interest, must lie inside the image, see [Rect].
+/// * `kernel`: Kernel.
+/// * `kernel_shape`: Kernel size, see [KernelShape] for more info.
+/// * `border_mode`: Border handling mode see [EdgeMode] for more info.
+/// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy`: See [ThreadingPolicy] for more info.
+pub fn filter_2d_roi<T, F>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    roi: Rect,
+    kernel: &[F],
+    kernel_shape: KernelShape,
+    border_mode: EdgeMode,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + AsPrimitive<F> + Default + Send + Sync + Filter2dHandler<T, F> + Debug,
+    F: ToStorage<T> + Mul<F> + MulAdd<F, Output = F> + Send + Sync + PartialEq + AsPrimitive<f64>,
+    i32: AsPrimitive<F>,
+    f64: AsPrimitive<T>,
+{
+    roi_executor(
+        src,
+        dst,
+        roi,
+        (kernel_shape.width / 2) as u32,
+        (kernel_shape.height / 2) as u32,
+        |s, d| {
+            filter_2d(
+                s,
+                d,
+                kernel,
+                kernel_shape,
+                border_mode,
+                border_constant,
+                threading_policy,
+            )
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FastBlurChannels;
+
+    const WIDTH: usize = 148;
+    const HEIGHT: usize = 148;
+
+    fn roi_source() -> Vec<u8> {
+        (0..WIDTH * HEIGHT * 3)
+            .map(|i| ((i * 31 + i / 7) % 255) as u8)
+            .collect()
+    }
+
+    /// Inside ROI result must match full image blur, outside it must match `untouched`
+    fn assert_roi_matches(full: &[u8], dst: &[u8], untouched: &[u8], roi: Rect, method: &str) {
+        let x_range = roi.x as usize * 3..(roi.x + roi.width) as usize * 3;
+        let y_range = roi.y as usize..(roi.y + roi.height) as usize;
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH * 3 {
+                let idx = y * WIDTH * 3 + x;
+                if y_range.contains(&y) && x_range.contains(&x) {
+                    assert_eq!(full[idx], dst[idx], "{method}: mismatch at x {x}, y {y}");
+                } else {
+                    assert_eq!(
+                        dst[idx], untouched[idx],
+                        "{method}: pixel outside ROI was touched at x {x}, y {y}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_roi_matches_full_blur() {
+        let src_data = roi_source();
+        let zeros = vec![0u8; src_data.len()];
+        let src = BlurImage::borrow(
+            &src_data,
+            WIDTH as u32,
+            HEIGHT as u32,
+            FastBlurChannels::Channels3,
+        );
+        let roi = Rect::new(20, 30, 50, 40);
+        let alloc =
+            || BlurImageMut::alloc(WIDTH as u32, HEIGHT as u32, FastBlurChannels::Channels3);
+
+        let params = GaussianBlurParams::new_from_kernel(7.);
+        let mut full = alloc();
+        gaussian_blur(
+            &src,
+            &mut full,
+            params,
+            EdgeMode::Clamp,
+            ThreadingPolicy::Single,
+            ConvolutionMode::Exact,
+        )
+        .unwrap();
+        let mut dst = alloc();
+        gaussian_blur_roi(
+            &src,
+            &mut dst,
+            roi,
+            params,
+            EdgeMode::Clamp,
+            ThreadingPolicy::Single,
+            ConvolutionMode::Exact,
+        )
+        .unwrap();
+        assert_roi_matches(
+            full.data.borrow(),
+            dst.data.borrow(),
+            &zeros,
+            roi,
+            "gaussian_blur_roi",
+        );
+
+        let parameters = BoxBlurParameters::new(9);
+        let mut full = alloc();
+        box_blur(&src, &mut full, parameters, ThreadingPolicy::Single).unwrap();
+        let mut dst = alloc();
+        box_blur_roi(&src, &mut dst, roi, parameters, ThreadingPolicy::Single).unwrap();
+        assert_roi_matches(
+            full.data.borrow(),
+            dst.data.borrow(),
+            &zeros,
+            roi,
+            "box_blur_roi",
+        );
+
+        let kernel = [1f32 / 15.; 15];
+        let kernel_shape = KernelShape::new(5, 3);
+        let mut full = alloc();
+        filter_2d(
+            &src,
+            &mut full,
+            &kernel,
+            kernel_shape,
+            EdgeMode::Clamp,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let mut dst = alloc();
+        filter_2d_roi(
+            &src,
+            &mut dst,
+            roi,
+            &kernel,
+            kernel_shape,
+            EdgeMode::Clamp,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        assert_roi_matches(
+            full.data.borrow(),
+            dst.data.borrow(),
+            &zeros,
+            roi,
+            "filter_2d_roi",
+        );
+
+        let radius = AnisotropicRadius::create(5, 3);
+        let mut full_data = src_data.clone();
+        let mut full = BlurImageMut::borrow(
+            &mut full_data,
+            WIDTH as u32,
+            HEIGHT as u32,
+            FastBlurChannels::Channels3,
+        );
+        stack_blur(&mut full, radius, ThreadingPolicy::Single).unwrap();
+        let mut dst_data = src_data.clone();
+        let mut dst = BlurImageMut::borrow(
+            &mut dst_data,
+            WIDTH as u32,
+            HEIGHT as u32,
+            FastBlurChannels::Channels3,
+        );
+        stack_blur_roi(&mut dst, roi, radius, ThreadingPolicy::Single).unwrap();
+        assert_roi_matches(&full_data, &dst_data, &src_data, roi, "stack_blur_roi");
+
+        let mut full_data = src_data.clone();
+        let mut full = BlurImageMut::borrow(
+            &mut full_data,
+            WIDTH as u32,
+            HEIGHT as u32,
+            FastBlurChannels::Channels3,
+        );
+        fast_gaussian(&mut full, radius, ThreadingPolicy::Single, EdgeMode::Clamp).unwrap();
+        let mut dst_data = src_data.clone();
+        let mut dst = BlurImageMut::borrow(
+            &mut dst_data,
+            WIDTH as u32,
+            HEIGHT as u32,
+            FastBlurChannels::Channels3,
+        );
+        fast_gaussian_roi(
+            &mut dst,
+            roi,
+            radius,
+            ThreadingPolicy::Single,
+            EdgeMode::Clamp,
+        )
+        .unwrap();
+        assert_roi_matches(&full_data, &dst_data, &src_data, roi, "fast_gaussian_roi");
+    }
+
+    #[test]
+    fn test_roi_out_of_bounds() {
+        let mut data = vec![0u16; 32 * 32];
+        let mut image = BlurImageMut::borrow(&mut data, 32, 32, FastBlurChannels::Plane);
+        let result = stack_blur_roi_u16(
+            &mut image,
+            Rect::new(20, 20, 13, 5),
+            AnisotropicRadius::new(3),
+            ThreadingPolicy::Single,
+        );
+        assert!(result.is_err());
+    }
+}
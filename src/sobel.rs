@@ -57,6 +57,7 @@ pub fn sobel(
     let sobel_vertical: [i16; 3] = [1, 2, 1];
     let _dispatcher = match image.channels {
         FastBlurChannels::Plane => filter_1d_exact::<u8, i16, 1>,
+        FastBlurChannels::Channels2 => filter_1d_exact::<u8, i16, 2>,
         FastBlurChannels::Channels3 => filter_1d_exact::<u8, i16, 3>,
         FastBlurChannels::Channels4 => filter_1d_exact::<u8, i16, 4>,
     };
@@ -127,6 +127,8 @@ unsafe fn avx_horiz_pass_impl<const CN: usize>(
             if i <= wm {
                 src_ptr0 += CN;
                 src_ptr1 += CN;
+                src_ptr2 += CN;
+                src_ptr3 += CN;
             }
             let stack_ptr = stacks.as_mut_ptr().add((i + radius) as usize * 2);
 
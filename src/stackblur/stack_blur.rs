@@ -105,6 +105,9 @@ fn stack_blur_worker_horizontal(
         FastBlurChannels::Plane => {
             pass::<1>(slice, stride, width, height, radius, thread, thread_count);
         }
+        FastBlurChannels::Channels2 => {
+            pass::<2>(slice, stride, width, height, radius, thread, thread_count);
+        }
         FastBlurChannels::Channels3 => {
             pass::<3>(slice, stride, width, height, radius, thread, thread_count);
         }
@@ -183,6 +186,9 @@ fn stack_blur_worker_vertical(
         FastBlurChannels::Plane => {
             pass::<1>(slice, stride, width, height, radius, thread, thread_count);
         }
+        FastBlurChannels::Channels2 => {
+            pass::<2>(slice, stride, width, height, radius, thread, thread_count);
+        }
         FastBlurChannels::Channels3 => {
             pass::<3>(slice, stride, width, height, radius, thread, thread_count);
         }
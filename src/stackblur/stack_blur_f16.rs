@@ -67,6 +67,25 @@ fn stack_blur_worker_horizontal(
             }
             _executor.pass(slice, stride, width, height, radius, thread, thread_count);
         }
+        FastBlurChannels::Channels2 => {
+            let mut _executor: Box<dyn StackBlurWorkingPass<f16, 2>> =
+                Box::new(HorizontalStackBlurPass::<f16, f32, f32, 2>::default());
+            #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+            {
+                #[cfg(feature = "sse")]
+                if std::arch::is_x86_feature_detected!("sse4.1")
+                    && std::arch::is_x86_feature_detected!("f16c")
+                {
+                    _executor =
+                        Box::new(HorizontalSseStackBlurPassFloat16::<f16, f32, 2>::default());
+                }
+            }
+            #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+            {
+                _executor = Box::new(HorizontalNeonStackBlurPassFloat16::<f16, f32, 2>::default());
+            }
+            _executor.pass(slice, stride, width, height, radius, thread, thread_count);
+        }
         FastBlurChannels::Channels3 => {
             let mut _executor: Box<dyn StackBlurWorkingPass<f16, 3>> =
                 Box::new(HorizontalStackBlurPass::<f16, f32, f32, 3>::default());
@@ -137,6 +156,24 @@ fn stack_blur_worker_vertical(
             }
             _executor.pass(slice, stride, width, height, radius, thread, thread_count);
         }
+        FastBlurChannels::Channels2 => {
+            let mut _executor: Box<dyn StackBlurWorkingPass<f16, 2>> =
+                Box::new(VerticalStackBlurPass::<f16, f32, f32, 2>::default());
+            #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+            {
+                #[cfg(feature = "sse")]
+                if std::arch::is_x86_feature_detected!("sse4.1")
+                    && std::arch::is_x86_feature_detected!("f16c")
+                {
+                    _executor = Box::new(VerticalSseStackBlurPassFloat16::<f16, f32, 2>::default());
+                }
+            }
+            #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+            {
+                _executor = Box::new(VerticalNeonStackBlurPassFloat16::<f16, f32, 2>::default());
+            }
+            _executor.pass(slice, stride, width, height, radius, thread, thread_count);
+        }
         FastBlurChannels::Channels3 => {
             let mut _executor: Box<dyn StackBlurWorkingPass<f16, 3>> =
                 Box::new(VerticalStackBlurPass::<f16, f32, f32, 3>::default());
@@ -0,0 +1,329 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::gradient::gradient_xy;
+use crate::{
+    gaussian_blur_f32, BlurError, BlurImage, BlurImageMut, BufferStore, EdgeMode, FastBlurChannels,
+    GaussianBlurParams, GradientKernel, IeeeBinaryConvolutionMode, Scalar, ThreadingPolicy,
+};
+use novtb::{ParallelZonedIterator, TbSliceMut};
+use num_traits::AsPrimitive;
+use std::fmt::Debug;
+
+/// Smoothed products of image derivatives, each component is single channel image.
+pub struct StructureTensor {
+    /// `dx * dx`
+    pub ixx: BlurImageMut<'static, f32>,
+    /// `dx * dy`
+    pub ixy: BlurImageMut<'static, f32>,
+    /// `dy * dy`
+    pub iyy: BlurImageMut<'static, f32>,
+}
+
+/// Computes structure tensor.
+///
+/// For multichannel images products of derivatives are summed over channels.
+///
+/// # Arguments
+///
+/// * `src` - Source image, `u8`, `u16` or `f32`, see [BlurImage] for more info.
+/// * `gradient_kernel` - Derivative kernel, see [GradientKernel] for more info.
+/// * `sigma` - Sigma of Gaussian window integrating products, must be > 0.
+/// * `border_mode` - Border handling mode see [EdgeMode] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn structure_tensor<T>(
+    src: &BlurImage<T>,
+    gradient_kernel: GradientKernel,
+    sigma: f32,
+    border_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<StructureTensor, BlurError>
+where
+    T: Copy + Default + Debug + AsPrimitive<f32>,
+{
+    if sigma <= 0. || !sigma.is_finite() {
+        return Err(BlurError::NegativeOrZeroSigma);
+    }
+    let (dx, dy) = gradient_xy(
+        src,
+        gradient_kernel,
+        border_mode,
+        Scalar::default(),
+        threading_policy,
+    )?;
+
+    let width = src.width as usize;
+    let cn = src.channels.channels();
+    let dx_stride = dx.row_stride() as usize;
+    let dy_stride = dy.row_stride() as usize;
+    let dx = dx.data.borrow();
+    let dy = dy.data.borrow();
+
+    let thread_count = threading_policy.thread_count(src.width, src.height);
+    let pool = novtb::ThreadPool::new(thread_count);
+
+    // Interleaved ixx, ixy, iyy
+    let mut products = BlurImageMut::alloc(src.width, src.height, FastBlurChannels::Channels3);
+    products
+        .data
+        .borrow_mut()
+        .tb_par_chunks_exact_mut(width * 3)
+        .for_each_enumerated(&pool, |y, row| {
+            let dx_row = &dx[y * dx_stride..y * dx_stride + width * cn];
+            let dy_row = &dy[y * dy_stride..y * dy_stride + width * cn];
+            for ((dst, dx), dy) in row
+                .chunks_exact_mut(3)
+                .zip(dx_row.chunks_exact(cn))
+                .zip(dy_row.chunks_exact(cn))
+            {
+                let (mut xx, mut xy, mut yy) = (0f32, 0f32, 0f32);
+                for (&dx, &dy) in dx.iter().zip(dy.iter()) {
+                    xx += dx * dx;
+                    xy += dx * dy;
+                    yy += dy * dy;
+                }
+                dst[0] = xx;
+                dst[1] = xy;
+                dst[2] = yy;
+            }
+        });
+
+    let mut smoothed = BlurImageMut::default();
+    gaussian_blur_f32(
+        &products.to_immutable_ref(),
+        &mut smoothed,
+        GaussianBlurParams::new_from_sigma(sigma as f64),
+        border_mode,
+        threading_policy,
+        IeeeBinaryConvolutionMode::Normal,
+    )?;
+
+    let mut tensor = StructureTensor {
+        ixx: BlurImageMut::alloc(src.width, src.height, FastBlurChannels::Plane),
+        ixy: BlurImageMut::alloc(src.width, src.height, FastBlurChannels::Plane),
+        iyy: BlurImageMut::alloc(src.width, src.height, FastBlurChannels::Plane),
+    };
+    let smoothed_stride = smoothed.row_stride() as usize;
+    for (y, src) in smoothed
+        .data
+        .borrow()
+        .chunks(smoothed_stride)
+        .take(src.height as usize)
+        .enumerate()
+    {
+        let ixx = &mut tensor.ixx.data.borrow_mut()[y * width..(y + 1) * width];
+        for (dst, src) in ixx.iter_mut().zip(src.chunks_exact(3)) {
+            *dst = src[0];
+        }
+        let ixy = &mut tensor.ixy.data.borrow_mut()[y * width..(y + 1) * width];
+        for (dst, src) in ixy.iter_mut().zip(src.chunks_exact(3)) {
+            *dst = src[1];
+        }
+        let iyy = &mut tensor.iyy.data.borrow_mut()[y * width..(y + 1) * width];
+        for (dst, src) in iyy.iter_mut().zip(src.chunks_exact(3)) {
+            *dst = src[2];
+        }
+    }
+    Ok(tensor)
+}
+
+fn corner_response<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<f32>,
+    gradient_kernel: GradientKernel,
+    sigma: f32,
+    border_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    response: impl Fn(f32, f32, f32) -> f32 + Send + Sync,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + AsPrimitive<f32>,
+{
+    src.check_layout()?;
+    if matches!(dst.data, BufferStore::Owned(_)) {
+        dst.resize(src.width, src.height, FastBlurChannels::Plane);
+    }
+    dst.check_layout(None)?;
+    if dst.width != src.width || dst.height != src.height || dst.channels != FastBlurChannels::Plane
+    {
+        return Err(BlurError::ImagesMustMatch);
+    }
+    let tensor = structure_tensor(src, gradient_kernel, sigma, border_mode, threading_policy)?;
+    let width = src.width as usize;
+    let ixx = tensor.ixx.data.borrow();
+    let ixy = tensor.ixy.data.borrow();
+    let iyy = tensor.iyy.data.borrow();
+
+    let thread_count = threading_policy.thread_count(src.width, src.height);
+    let pool = novtb::ThreadPool::new(thread_count);
+    let dst_stride = dst.row_stride() as usize;
+    dst.data
+        .borrow_mut()
+        .tb_par_chunks_mut(dst_stride)
+        .for_each_enumerated(&pool, |y, row| {
+            let offset = y * width;
+            for (x, dst) in row[..width].iter_mut().enumerate() {
+                *dst = response(ixx[offset + x], ixy[offset + x], iyy[offset + x]);
+            }
+        });
+    Ok(())
+}
+
+/// Computes Harris corner response `det(M) - k * trace(M)^2` of structure tensor `M`.
+///
+/// # Arguments
+///
+/// * `src` - Source image, `u8`, `u16` or `f32`, see [BlurImage] for more info.
+/// * `dst` - Single channel destination image, see [BlurImageMut] for more info.
+/// * `gradient_kernel` - Derivative kernel, see [GradientKernel] for more info.
+/// * `sigma` - Sigma of Gaussian window, must be > 0.
+/// * `k` - Harris free parameter, usually `0.04..0.06`.
+/// * `border_mode` - Border handling mode see [EdgeMode] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn corner_harris<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<f32>,
+    gradient_kernel: GradientKernel,
+    sigma: f32,
+    k: f32,
+    border_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + AsPrimitive<f32>,
+{
+    corner_response(
+        src,
+        dst,
+        gradient_kernel,
+        sigma,
+        border_mode,
+        threading_policy,
+        |xx, xy, yy| {
+            let trace = xx + yy;
+            xx * yy - xy * xy - k * trace * trace
+        },
+    )
+}
+
+/// Computes Shi-Tomasi corner response, the smallest eigenvalue of structure tensor.
+///
+/// # Arguments
+///
+/// * `src` - Source image, `u8`, `u16` or `f32`, see [BlurImage] for more info.
+/// * `dst` - Single channel destination image, see [BlurImageMut] for more info.
+/// * `gradient_kernel` - Derivative kernel, see [GradientKernel] for more info.
+/// * `sigma` - Sigma of Gaussian window, must be > 0.
+/// * `border_mode` - Border handling mode see [EdgeMode] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn corner_min_eigenvalue<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<f32>,
+    gradient_kernel: GradientKernel,
+    sigma: f32,
+    border_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + AsPrimitive<f32>,
+{
+    corner_response(
+        src,
+        dst,
+        gradient_kernel,
+        sigma,
+        border_mode,
+        threading_policy,
+        |xx, xy, yy| {
+            let half_sum = (xx + yy) * 0.5;
+            let half_difference = (xx - yy) * 0.5;
+            half_sum - half_difference.hypot(xy)
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square() -> Vec<u8> {
+        let mut src = vec![0u8; 32 * 32];
+        for y in 8..24 {
+            for x in 8..24 {
+                src[y * 32 + x] = 255;
+            }
+        }
+        src
+    }
+
+    #[test]
+    fn test_corner_responses() {
+        let src = square();
+        let image = BlurImage::borrow(&src, 32, 32, FastBlurChannels::Plane);
+        let mut harris = BlurImageMut::default();
+        corner_harris(
+            &image,
+            &mut harris,
+            GradientKernel::Sobel3,
+            1.,
+            0.04,
+            EdgeMode::Reflect101,
+            ThreadingPolicy::Adaptive,
+        )
+        .unwrap();
+        let mut min_eigen = BlurImageMut::default();
+        corner_min_eigenvalue(
+            &image,
+            &mut min_eigen,
+            GradientKernel::Sobel3,
+            1.,
+            EdgeMode::Reflect101,
+            ThreadingPolicy::Adaptive,
+        )
+        .unwrap();
+        let harris = harris.data.borrow();
+        let min_eigen = min_eigen.data.borrow();
+        let corner = 8 * 32 + 8;
+        let edge = 16 * 32 + 8;
+        let flat = 16 * 32 + 16;
+        // Corners are strong, edges are negative for Harris, flat areas are zero
+        assert!(harris[corner] > 0.);
+        assert!(harris[edge] < 0.);
+        assert!(harris[flat].abs() < 1e-3);
+        assert!(min_eigen[corner] > 100. * min_eigen[edge].abs().max(1.));
+        assert!(min_eigen[flat].abs() < 1e-3);
+        assert!(structure_tensor(
+            &image,
+            GradientKernel::Sobel3,
+            0.,
+            EdgeMode::Clamp,
+            ThreadingPolicy::Single
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,478 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::{
+    box_blur, box_blur_f32, box_blur_u16, fast_gaussian, fast_gaussian_f32, fast_gaussian_u16,
+    gaussian_blur, gaussian_blur_f32, gaussian_blur_u16, sigma_size, stack_blur, stack_blur_f32,
+    stack_blur_u16, AnisotropicRadius, BlurError, BlurImage, BlurImageMut, BoxBlurParameters,
+    ConvolutionMode, EdgeMode, GaussianBlurParams, IeeeBinaryConvolutionMode, ThreadingPolicy,
+};
+use novtb::{ParallelZonedIterator, TbSliceMut};
+use std::fmt::Debug;
+
+/// Low-pass filter used to build unsharp mask
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default)]
+pub enum UnsharpLowPass {
+    /// [crate::gaussian_blur]
+    #[default]
+    Gaussian = 0,
+    /// [crate::fast_gaussian]
+    FastGaussian = 1,
+    /// [crate::stack_blur], edges are always replicated and `edge_mode` is ignored.
+    StackBlur = 2,
+    /// [crate::box_blur], edges are always replicated and `edge_mode` is ignored.
+    BoxBlur = 3,
+}
+
+/// Unsharp mask parameters
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct UnsharpMaskParams {
+    /// Sharpening strength, `0` keeps image as is.
+    pub amount: f32,
+    /// Low-pass radius, Gaussian and box kernel size is `2 * radius + 1`.
+    pub radius: u32,
+    /// Gaussian sigma, if `<= 0` it is computed from radius. Used only by [UnsharpLowPass::Gaussian].
+    pub sigma: f32,
+    /// Differences with absolute value below threshold are not sharpened, in image units.
+    pub threshold: f32,
+    /// Low-pass filter, see [UnsharpLowPass] for more info.
+    pub low_pass: UnsharpLowPass,
+    /// Sharpen only luma of RGB and RGBA images, this avoids color fringes. Ignored for other layouts.
+    pub luma_only: bool,
+}
+
+impl UnsharpMaskParams {
+    pub fn new(amount: f32, radius: u32) -> UnsharpMaskParams {
+        UnsharpMaskParams {
+            amount,
+            radius,
+            sigma: 0.,
+            threshold: 0.,
+            low_pass: UnsharpLowPass::Gaussian,
+            luma_only: false,
+        }
+    }
+
+    fn validate(&self) -> Result<(), BlurError> {
+        if !self.amount.is_finite()
+            || self.threshold.is_nan()
+            || self.threshold < 0.
+            || self.radius == 0
+        {
+            return Err(BlurError::InvalidArguments);
+        }
+        Ok(())
+    }
+
+    fn gaussian_params(&self) -> GaussianBlurParams {
+        let kernel = self.radius * 2 + 1;
+        let sigma = if self.sigma > 0. {
+            self.sigma
+        } else {
+            sigma_size(kernel as f32)
+        };
+        GaussianBlurParams::new(kernel, sigma as f64)
+    }
+}
+
+trait UnsharpSample: Copy + Default + Debug + Send + Sync + 'static {
+    fn low_pass(
+        src: &BlurImage<Self>,
+        dst: &mut BlurImageMut<Self>,
+        params: UnsharpMaskParams,
+        edge_mode: EdgeMode,
+        threading_policy: ThreadingPolicy,
+    ) -> Result<(), BlurError>;
+
+    fn to_f32(self) -> f32;
+
+    fn from_f32(value: f32) -> Self;
+}
+
+macro_rules! define_unsharp_sample {
+    ($t: ty, $gaussian: ident, $gaussian_mode: expr, $fast_gaussian: ident, $stack_blur: ident, $box_blur: ident) => {
+        impl UnsharpSample for $t {
+            fn low_pass(
+                src: &BlurImage<Self>,
+                dst: &mut BlurImageMut<Self>,
+                params: UnsharpMaskParams,
+                edge_mode: EdgeMode,
+                threading_policy: ThreadingPolicy,
+            ) -> Result<(), BlurError> {
+                let radius = AnisotropicRadius::new(params.radius);
+                match params.low_pass {
+                    UnsharpLowPass::Gaussian => $gaussian(
+                        src,
+                        dst,
+                        params.gaussian_params(),
+                        edge_mode,
+                        threading_policy,
+                        $gaussian_mode,
+                    ),
+                    UnsharpLowPass::FastGaussian => {
+                        src.copy_to_mut(dst)?;
+                        $fast_gaussian(dst, radius, threading_policy, edge_mode)
+                    }
+                    UnsharpLowPass::StackBlur => {
+                        src.copy_to_mut(dst)?;
+                        $stack_blur(dst, radius, threading_policy)
+                    }
+                    UnsharpLowPass::BoxBlur => $box_blur(
+                        src,
+                        dst,
+                        BoxBlurParameters::new(params.radius * 2 + 1),
+                        threading_policy,
+                    ),
+                }
+            }
+
+            #[inline(always)]
+            fn to_f32(self) -> f32 {
+                self as f32
+            }
+
+            #[inline(always)]
+            fn from_f32(value: f32) -> Self {
+                // Float to integer cast saturates to type range
+                value.round() as $t
+            }
+        }
+    };
+}
+
+define_unsharp_sample!(
+    u8,
+    gaussian_blur,
+    ConvolutionMode::Exact,
+    fast_gaussian,
+    stack_blur,
+    box_blur
+);
+define_unsharp_sample!(
+    u16,
+    gaussian_blur_u16,
+    ConvolutionMode::Exact,
+    fast_gaussian_u16,
+    stack_blur_u16,
+    box_blur_u16
+);
+
+impl UnsharpSample for f32 {
+    fn low_pass(
+        src: &BlurImage<Self>,
+        dst: &mut BlurImageMut<Self>,
+        params: UnsharpMaskParams,
+        edge_mode: EdgeMode,
+        threading_policy: ThreadingPolicy,
+    ) -> Result<(), BlurError> {
+        let radius = AnisotropicRadius::new(params.radius);
+        match params.low_pass {
+            UnsharpLowPass::Gaussian => gaussian_blur_f32(
+                src,
+                dst,
+                params.gaussian_params(),
+                edge_mode,
+                threading_policy,
+                IeeeBinaryConvolutionMode::Normal,
+            ),
+            UnsharpLowPass::FastGaussian => {
+                src.copy_to_mut(dst)?;
+                fast_gaussian_f32(dst, radius, threading_policy, edge_mode)
+            }
+            UnsharpLowPass::StackBlur => {
+                src.copy_to_mut(dst)?;
+                stack_blur_f32(dst, radius, threading_policy)
+            }
+            UnsharpLowPass::BoxBlur => box_blur_f32(
+                src,
+                dst,
+                BoxBlurParameters::new(params.radius * 2 + 1),
+                threading_policy,
+            ),
+        }
+    }
+
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self
+    }
+
+    #[inline(always)]
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+}
+
+#[inline(always)]
+fn sharpen_value(value: f32, low: f32, params: &UnsharpMaskParams) -> f32 {
+    let difference = value - low;
+    if difference.abs() < params.threshold {
+        value
+    } else {
+        value + difference * params.amount
+    }
+}
+
+fn unsharp_mask_impl<T: UnsharpSample>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    params: UnsharpMaskParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    params.validate()?;
+    src.check_layout()?;
+    dst.check_layout(Some(src))?;
+    src.size_matches_mut(dst)?;
+
+    let mut blurred = BlurImageMut::default();
+    T::low_pass(src, &mut blurred, params, edge_mode, threading_policy)?;
+
+    let cn = src.channels.channels();
+    let row_length = src.width as usize * cn;
+    let src_stride = src.row_stride() as usize;
+    let blurred_stride = blurred.row_stride() as usize;
+    let dst_stride = dst.row_stride() as usize;
+    let blurred = blurred.data.borrow();
+    let luma_only = params.luma_only && cn >= 3;
+
+    let thread_count = threading_policy.thread_count(src.width, src.height);
+    let pool = novtb::ThreadPool::new(thread_count);
+
+    dst.data
+        .borrow_mut()
+        .tb_par_chunks_mut(dst_stride)
+        .for_each_enumerated(&pool, |y, row| {
+            let src = &src.data[y * src_stride..y * src_stride + row_length];
+            let low = &blurred[y * blurred_stride..y * blurred_stride + row_length];
+            for ((dst, src), low) in row[..row_length]
+                .chunks_exact_mut(cn)
+                .zip(src.chunks_exact(cn))
+                .zip(low.chunks_exact(cn))
+            {
+                if luma_only {
+                    // Rec.601 luma, difference is added equally to all components to keep chroma
+                    let luma = |p: &[T]| {
+                        0.299 * p[0].to_f32() + 0.587 * p[1].to_f32() + 0.114 * p[2].to_f32()
+                    };
+                    let luma_src = luma(src);
+                    let shift = sharpen_value(luma_src, luma(low), &params) - luma_src;
+                    for (dst, &src) in dst[..3].iter_mut().zip(src.iter()) {
+                        *dst = T::from_f32(src.to_f32() + shift);
+                    }
+                } else {
+                    for ((dst, &src), &low) in
+                        dst[..cn.min(3)].iter_mut().zip(src.iter()).zip(low.iter())
+                    {
+                        *dst = T::from_f32(sharpen_value(src.to_f32(), low.to_f32(), &params));
+                    }
+                }
+                if cn == 4 {
+                    dst[3] = src[3];
+                }
+            }
+        });
+    Ok(())
+}
+
+/// Sharpens image using unsharp mask.
+///
+/// Result is `src + amount * (src - low_pass(src))`, alpha of [FastBlurChannels::Channels4] is kept as is.
+/// Integer results are clamped to the type range, f32 results are not clamped.
+///
+/// # Arguments
+///
+/// * `src` - Source image, [FastBlurChannels::Channels2] components are sharpened independently
+///   as planes, see [BlurImage] for more info.
+/// * `dst` - Destination image, see [BlurImageMut] for more info.
+/// * `params` - See [UnsharpMaskParams] for more info.
+/// * `edge_mode` - Border handling mode see [EdgeMode] for more info, used by Gaussian low-pass filters.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn unsharp_mask(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    params: UnsharpMaskParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    unsharp_mask_impl(src, dst, params, edge_mode, threading_policy)
+}
+
+/// Sharpens 16-bit image using unsharp mask.
+///
+/// See [unsharp_mask] for more info.
+pub fn unsharp_mask_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    params: UnsharpMaskParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    unsharp_mask_impl(src, dst, params, edge_mode, threading_policy)
+}
+
+/// Sharpens f32 image using unsharp mask.
+///
+/// See [unsharp_mask] for more info.
+pub fn unsharp_mask_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    params: UnsharpMaskParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    unsharp_mask_impl(src, dst, params, edge_mode, threading_policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FastBlurChannels;
+
+    #[test]
+    fn test_unsharp_mask_step() {
+        let (width, height) = (32usize, 8usize);
+        let mut src = vec![0u8; width * height * 4];
+        for (i, px) in src.chunks_exact_mut(4).enumerate() {
+            let v = if i % width < 16 { 80 } else { 160 };
+            px.copy_from_slice(&[v, v, v, 200]);
+        }
+        let image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels4,
+        );
+        for low_pass in [
+            UnsharpLowPass::Gaussian,
+            UnsharpLowPass::FastGaussian,
+            UnsharpLowPass::StackBlur,
+            UnsharpLowPass::BoxBlur,
+        ] {
+            for luma_only in [false, true] {
+                let mut dst = BlurImageMut::default();
+                let mut params = UnsharpMaskParams::new(1., 3);
+                params.low_pass = low_pass;
+                params.luma_only = luma_only;
+                unsharp_mask(
+                    &image,
+                    &mut dst,
+                    params,
+                    EdgeMode::Clamp,
+                    ThreadingPolicy::Adaptive,
+                )
+                .unwrap();
+                let row = &dst.data.borrow()[4 * width * 4..5 * width * 4];
+                // Overshoot on both sides of the step, flat areas and alpha are untouched
+                assert!(row[15 * 4] < 80, "{low_pass:?}");
+                assert!(row[16 * 4] > 160, "{low_pass:?}");
+                assert_eq!(row[2 * 4], 80);
+                assert_eq!(row[29 * 4], 160);
+                assert!(row.chunks_exact(4).all(|x| x[3] == 200));
+            }
+        }
+    }
+
+    #[test]
+    fn test_unsharp_mask_threshold() {
+        let src: Vec<f32> = (0..64)
+            .map(|i| if i % 8 < 4 { 0.5 } else { 0.52 })
+            .collect();
+        let image = BlurImage::borrow(&src, 8, 8, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        let mut params = UnsharpMaskParams::new(2., 1);
+        params.threshold = 0.05;
+        unsharp_mask_f32(
+            &image,
+            &mut dst,
+            params,
+            EdgeMode::Clamp,
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        assert_eq!(dst.data.borrow(), src.as_slice());
+    }
+
+    #[test]
+    fn test_unsharp_mask_f32_not_clamped() {
+        let src: Vec<f32> = (0..64).map(|i| if i % 8 < 4 { 0. } else { 1. }).collect();
+        let image = BlurImage::borrow(&src, 8, 8, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        unsharp_mask_f32(
+            &image,
+            &mut dst,
+            UnsharpMaskParams::new(1., 1),
+            EdgeMode::Clamp,
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let row = &dst.data.borrow()[..8];
+        assert!(row[3] < 0., "{}", row[3]);
+        assert!(row[4] > 1., "{}", row[4]);
+    }
+
+    #[test]
+    fn test_unsharp_mask_two_channels() {
+        let (width, height) = (16usize, 4usize);
+        let src: Vec<u16> = (0..width * height * 2)
+            .map(|i| if i / 2 % width < 8 { 1000 } else { 2000 } + i as u16 % 2 * 500)
+            .collect();
+        let image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels2,
+        );
+        for low_pass in [
+            UnsharpLowPass::Gaussian,
+            UnsharpLowPass::FastGaussian,
+            UnsharpLowPass::StackBlur,
+            UnsharpLowPass::BoxBlur,
+        ] {
+            let mut params = UnsharpMaskParams::new(1., 2);
+            params.low_pass = low_pass;
+            params.luma_only = true;
+            let mut dst = BlurImageMut::default();
+            unsharp_mask_u16(
+                &image,
+                &mut dst,
+                params,
+                EdgeMode::Clamp,
+                ThreadingPolicy::Single,
+            )
+            .unwrap();
+            let row = &dst.data.borrow()[2 * width * 2..3 * width * 2];
+            // Both components overshoot around the step, luma only has no effect
+            assert!(row[7 * 2] < 1000 && row[7 * 2 + 1] < 1500, "{low_pass:?}");
+            assert!(row[8 * 2] > 2000 && row[8 * 2 + 1] > 2500, "{low_pass:?}");
+            assert_eq!(&row[..2], &[1000, 1500], "{low_pass:?}");
+        }
+    }
+}
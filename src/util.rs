@@ -48,6 +48,7 @@ pub enum BlurError {
     ExceedingPointerSize,
     NegativeOrZeroSigma,
     InvalidArguments,
+    ChannelsNotSupported(usize),
 }
 
 impl Error for BlurError {}
@@ -76,6 +77,9 @@ impl std::fmt::Display for BlurError {
             }
             BlurError::StrideIsNotSupported => f.write_str("Stride is not supported"),
             BlurError::FftChannelsNotSupported => f.write_str("Fft supports only planar images"),
+            BlurError::ChannelsNotSupported(cn) => {
+                f.write_fmt(format_args!("Channels count {cn} is not supported"))
+            }
             BlurError::ExceedingPointerSize => {
                 f.write_str("Image bounds and blurring kernel/radius exceeds pointer capacity")
             }
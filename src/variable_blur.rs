@@ -0,0 +1,406 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::to_storage::ToStorage;
+use crate::{
+    gaussian_blur_f32, BlurError, BlurImage, BlurImageMut, EdgeMode, FastBlurChannels,
+    GaussianBlurParams, IeeeBinaryConvolutionMode, ThreadingPolicy,
+};
+use novtb::{ParallelZonedIterator, TbSliceMut};
+use num_traits::AsPrimitive;
+use std::fmt::Debug;
+
+/// Per-pixel blur strength map for [variable_blur].
+///
+/// Map must be a [FastBlurChannels::Plane] image with the same size as the source image.
+#[derive(Copy, Clone)]
+pub enum VariableBlurMap<'a> {
+    /// Normalized strength, `0` means no blur, `255` means [VariableBlurParams::max_sigma].
+    Strength(&'a BlurImage<'a, u8>),
+    /// Absolute gaussian sigma in pixels, clamped into `0..=max_sigma`.
+    Sigma(&'a BlurImage<'a, f32>),
+}
+
+impl VariableBlurMap<'_> {
+    fn check(&self, width: u32, height: u32) -> Result<(), BlurError> {
+        let (map_width, map_height, channels) = match self {
+            VariableBlurMap::Strength(map) => {
+                map.check_layout()?;
+                (map.width, map.height, map.channels)
+            }
+            VariableBlurMap::Sigma(map) => {
+                map.check_layout()?;
+                (map.width, map.height, map.channels)
+            }
+        };
+        if channels != FastBlurChannels::Plane {
+            return Err(BlurError::InvalidArguments);
+        }
+        if map_width != width || map_height != height {
+            return Err(BlurError::ImagesMustMatch);
+        }
+        Ok(())
+    }
+
+    /// Resolves the map into dense sigma plane
+    fn sigmas(&self, max_sigma: f32) -> Vec<f32> {
+        match self {
+            VariableBlurMap::Strength(map) => {
+                let scale = max_sigma / 255.;
+                let width = map.width as usize;
+                let mut sigmas = Vec::with_capacity(width * map.height as usize);
+                for row in map
+                    .data
+                    .as_ref()
+                    .chunks(map.row_stride() as usize)
+                    .take(map.height as usize)
+                {
+                    sigmas.extend(row[..width].iter().map(|&v| v as f32 * scale));
+                }
+                sigmas
+            }
+            VariableBlurMap::Sigma(map) => {
+                let width = map.width as usize;
+                let mut sigmas = Vec::with_capacity(width * map.height as usize);
+                for row in map
+                    .data
+                    .as_ref()
+                    .chunks(map.row_stride() as usize)
+                    .take(map.height as usize)
+                {
+                    sigmas.extend(row[..width].iter().map(|&v| {
+                        if v.is_nan() {
+                            0.
+                        } else {
+                            v.max(0.).min(max_sigma)
+                        }
+                    }));
+                }
+                sigmas
+            }
+        }
+    }
+}
+
+/// Parameters for [variable_blur].
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialOrd, PartialEq)]
+pub struct VariableBlurParams {
+    /// Largest sigma that the map may request
+    pub max_sigma: f32,
+    /// Count of blurred levels including unblurred source, must be at least 2.
+    /// More levels gives smoother transitions with more computation.
+    pub levels: u32,
+}
+
+impl VariableBlurParams {
+    /// Sigma must be > 0, levels count will be chosen so that neighbour levels differ in sigma at most by 1.
+    ///
+    /// Count is capped at 17 levels, so for `max_sigma` above 16 neighbour levels
+    /// differ by `max_sigma / 16`, use [VariableBlurParams::new_with_levels] to get finer steps.
+    pub fn new(max_sigma: f32) -> VariableBlurParams {
+        VariableBlurParams {
+            max_sigma,
+            levels: (max_sigma.ceil() as u32).clamp(2, 16) + 1,
+        }
+    }
+
+    /// Sigma must be > 0, levels must be at least 2.
+    pub fn new_with_levels(max_sigma: f32, levels: u32) -> VariableBlurParams {
+        VariableBlurParams { max_sigma, levels }
+    }
+
+    fn validate(&self) -> Result<(), BlurError> {
+        if self.max_sigma.is_nan() || self.max_sigma <= 0. {
+            return Err(BlurError::NegativeOrZeroSigma);
+        }
+        if self.levels < 2 {
+            return Err(BlurError::InvalidArguments);
+        }
+        Ok(())
+    }
+}
+
+/// Adds level contribution to accumulator with tent weight around level sigma.
+fn accumulate(
+    accumulator: &mut [f32],
+    level: &BlurImageMut<f32>,
+    sigmas: &[f32],
+    level_index: usize,
+    step: f32,
+    cn: usize,
+    pool: &novtb::ThreadPool,
+) {
+    let width = level.width as usize;
+    let level_stride = level.row_stride() as usize;
+    let level_data = level.data.borrow();
+    accumulator
+        .tb_par_chunks_exact_mut(width * cn)
+        .for_each_enumerated(pool, |y, row| {
+            let src = &level_data[y * level_stride..y * level_stride + width * cn];
+            let sigmas = &sigmas[y * width..(y + 1) * width];
+            for ((dst, src), &sigma) in row
+                .chunks_exact_mut(cn)
+                .zip(src.chunks_exact(cn))
+                .zip(sigmas.iter())
+            {
+                let weight = 1. - (sigma / step - level_index as f32).abs();
+                if weight <= 0. {
+                    continue;
+                }
+                for (dst, &src) in dst.iter_mut().zip(src.iter()) {
+                    *dst += src * weight;
+                }
+            }
+        });
+}
+
+fn variable_blur_impl<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    map: VariableBlurMap,
+    params: VariableBlurParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + Send + Sync + AsPrimitive<f32> + 'static,
+    f32: ToStorage<T>,
+{
+    src.check_layout()?;
+    dst.check_layout(Some(src))?;
+    src.size_matches_mut(dst)?;
+    params.validate()?;
+    map.check(src.width, src.height)?;
+
+    let width = src.width as usize;
+    let height = src.height as usize;
+    let cn = src.channels.channels();
+    let src_stride = src.row_stride() as usize;
+
+    let sigmas = map.sigmas(params.max_sigma);
+    let step = params.max_sigma / (params.levels - 1) as f32;
+    let highest_sigma = sigmas.iter().fold(0f32, |acc, &x| acc.max(x));
+    let top_level = ((highest_sigma / step).ceil() as usize).min(params.levels as usize - 1);
+
+    let thread_count = threading_policy.thread_count(src.width, src.height);
+    let pool = novtb::ThreadPool::new(thread_count);
+
+    let mut working = BlurImageMut::<f32>::alloc(src.width, src.height, src.channels);
+    for (dst, src) in working
+        .data
+        .borrow_mut()
+        .chunks_exact_mut(width * cn)
+        .zip(src.data.as_ref().chunks(src_stride))
+    {
+        for (dst, &src) in dst.iter_mut().zip(src.iter()) {
+            *dst = src.as_();
+        }
+    }
+
+    let mut accumulator = vec![0f32; width * height * cn];
+    accumulate(&mut accumulator, &working, &sigmas, 0, step, cn, &pool);
+
+    let working_ref = working.to_immutable_ref();
+    let mut blurred = BlurImageMut::default();
+    for level_index in 1..=top_level {
+        let sigma = step * level_index as f32;
+        gaussian_blur_f32(
+            &working_ref,
+            &mut blurred,
+            GaussianBlurParams::new_from_sigma(sigma as f64),
+            edge_mode,
+            threading_policy,
+            IeeeBinaryConvolutionMode::Normal,
+        )?;
+        accumulate(
+            &mut accumulator,
+            &blurred,
+            &sigmas,
+            level_index,
+            step,
+            cn,
+            &pool,
+        );
+    }
+
+    let dst_stride = dst.row_stride() as usize;
+    dst.data
+        .borrow_mut()
+        .tb_par_chunks_exact_mut(dst_stride)
+        .for_each_enumerated(&pool, |y, row| {
+            if y >= height {
+                return;
+            }
+            let src = &accumulator[y * width * cn..(y + 1) * width * cn];
+            for (dst, &src) in row[..width * cn].iter_mut().zip(src.iter()) {
+                *dst = src.to_();
+            }
+        });
+    Ok(())
+}
+
+/// Performs spatially varying gaussian blur driven by per-pixel map.
+///
+/// Image is blurred with set of gaussian levels evenly distributed in `0..=max_sigma`,
+/// then every pixel is linearly blended from two levels closest to the requested sigma.
+/// Useful for depth of field, tilt-shift or vignette blur.
+/// O(L*R) complexity, where L is levels count.
+///
+/// # Arguments
+///
+/// * `src` - Source image.
+/// * `dst` - Destination image.
+/// * `map` - Per-pixel blur strength, see [VariableBlurMap] for more info.
+/// * `params` - See [VariableBlurParams] for more info.
+/// * `edge_mode` - Rule to handle edge mode, sse [EdgeMode] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn variable_blur(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    map: VariableBlurMap,
+    params: VariableBlurParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    variable_blur_impl(src, dst, map, params, edge_mode, threading_policy)
+}
+
+/// Performs spatially varying gaussian blur driven by per-pixel map.
+///
+/// Image is blurred with set of gaussian levels evenly distributed in `0..=max_sigma`,
+/// then every pixel is linearly blended from two levels closest to the requested sigma.
+/// O(L*R) complexity, where L is levels count.
+///
+/// # Arguments
+///
+/// * `src` - Source image.
+/// * `dst` - Destination image.
+/// * `map` - Per-pixel blur strength, see [VariableBlurMap] for more info.
+/// * `params` - See [VariableBlurParams] for more info.
+/// * `edge_mode` - Rule to handle edge mode, sse [EdgeMode] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn variable_blur_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    map: VariableBlurMap,
+    params: VariableBlurParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    variable_blur_impl(src, dst, map, params, edge_mode, threading_policy)
+}
+
+/// Performs spatially varying gaussian blur driven by per-pixel map.
+///
+/// Image is blurred with set of gaussian levels evenly distributed in `0..=max_sigma`,
+/// then every pixel is linearly blended from two levels closest to the requested sigma.
+/// O(L*R) complexity, where L is levels count.
+///
+/// # Arguments
+///
+/// * `src` - Source image.
+/// * `dst` - Destination image.
+/// * `map` - Per-pixel blur strength, see [VariableBlurMap] for more info.
+/// * `params` - See [VariableBlurParams] for more info.
+/// * `edge_mode` - Rule to handle edge mode, sse [EdgeMode] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn variable_blur_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    map: VariableBlurMap,
+    params: VariableBlurParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    variable_blur_impl(src, dst, map, params, edge_mode, threading_policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variable_blur_u8() {
+        let width = 148usize;
+        let height = 148usize;
+        let src = vec![126u8; width * height * 3];
+        let src_image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let mut map = vec![0u8; width * height];
+        for (y, row) in map.chunks_exact_mut(width).enumerate() {
+            row.fill((y * 255 / height) as u8);
+        }
+        let map_image =
+            BlurImage::borrow(&map, width as u32, height as u32, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        variable_blur(
+            &src_image,
+            &mut dst,
+            VariableBlurMap::Strength(&map_image),
+            VariableBlurParams::new(6.),
+            EdgeMode::Clamp,
+            ThreadingPolicy::Adaptive,
+        )
+        .unwrap();
+        for (i, &cn) in dst.data.borrow().iter().enumerate() {
+            let diff = (cn as i32 - 126).abs();
+            assert!(
+                diff <= 3,
+                "Diff expected to be less than 3 but it was {diff} at {i}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_variable_blur_zero_map_is_identity() {
+        let width = 64usize;
+        let height = 64usize;
+        let src: Vec<f32> = (0..width * height).map(|x| (x % 17) as f32).collect();
+        let src_image =
+            BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        let map = vec![0f32; width * height];
+        let map_image =
+            BlurImage::borrow(&map, width as u32, height as u32, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        variable_blur_f32(
+            &src_image,
+            &mut dst,
+            VariableBlurMap::Sigma(&map_image),
+            VariableBlurParams::new(4.),
+            EdgeMode::Reflect,
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        assert_eq!(dst.data.borrow(), src.as_slice());
+    }
+}
@@ -0,0 +1,511 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::image::BufferStore;
+use crate::planar::planar_executor_in_place;
+use crate::{
+    fast_gaussian, fast_gaussian_u16, gaussian_blur, gaussian_blur_u16, stack_blur, stack_blur_u16,
+    AnisotropicRadius, BlurError, BlurImage, BlurImageMut, BlurPlanarImageMut, ConvolutionMode,
+    EdgeMode, FastBlurChannels, GaussianBlurParams, ThreadingPolicy,
+};
+use std::borrow::Cow;
+use std::fmt::Debug;
+
+/// Chroma subsampling of YUV image
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum YuvChromaSubsampling {
+    /// Chroma is halved in both directions, I420, NV12, NV21
+    Yuv420,
+    /// Chroma is halved horizontally, I422, NV16, NV61
+    Yuv422,
+    /// Chroma is not subsampled, I444, NV24, NV42
+    Yuv444,
+}
+
+impl YuvChromaSubsampling {
+    /// Returns size of chroma plane for luma plane of the given size
+    pub fn chroma_size(self, width: u32, height: u32) -> (u32, u32) {
+        match self {
+            YuvChromaSubsampling::Yuv420 => (width.div_ceil(2), height.div_ceil(2)),
+            YuvChromaSubsampling::Yuv422 => (width.div_ceil(2), height),
+            YuvChromaSubsampling::Yuv444 => (width, height),
+        }
+    }
+
+    fn scale_radius(self, radius: AnisotropicRadius) -> AnisotropicRadius {
+        match self {
+            YuvChromaSubsampling::Yuv420 => {
+                AnisotropicRadius::create((radius.x_axis / 2).max(1), (radius.y_axis / 2).max(1))
+            }
+            YuvChromaSubsampling::Yuv422 => {
+                AnisotropicRadius::create((radius.x_axis / 2).max(1), radius.y_axis)
+            }
+            YuvChromaSubsampling::Yuv444 => radius,
+        }
+    }
+
+    fn scale_params(self, params: GaussianBlurParams) -> GaussianBlurParams {
+        // Odd kernel halved and rounded back to odd
+        let half_kernel = |kernel: u32| if kernel == 0 { 0 } else { (kernel / 2) | 1 };
+        match self {
+            YuvChromaSubsampling::Yuv420 => GaussianBlurParams::new_asymmetric(
+                half_kernel(params.x_kernel),
+                params.x_sigma * 0.5,
+                half_kernel(params.y_kernel),
+                params.y_sigma * 0.5,
+            ),
+            YuvChromaSubsampling::Yuv422 => GaussianBlurParams::new_asymmetric(
+                half_kernel(params.x_kernel),
+                params.x_sigma * 0.5,
+                params.y_kernel,
+                params.y_sigma,
+            ),
+            YuvChromaSubsampling::Yuv444 => params,
+        }
+    }
+}
+
+/// Mutable YUV image, planar (I420, I422, I444) or semi-planar (NV12, NV16, NV24 and their VU variants).
+///
+/// First plane is always luma, chroma planes are blurred with radius scaled according to subsampling.
+/// Strides are in items per row.
+pub struct YuvImageMut<'a, T: Clone + Copy + Default + Debug> {
+    pub planes: BlurPlanarImageMut<'a, T>,
+    pub subsampling: YuvChromaSubsampling,
+}
+
+impl<'a, T: Clone + Copy + Default + Debug> YuvImageMut<'a, T> {
+    /// Creates planar YUV image with separate U and V planes
+    #[allow(clippy::too_many_arguments)]
+    pub fn planar(
+        y_plane: &'a mut [T],
+        y_stride: u32,
+        u_plane: &'a mut [T],
+        u_stride: u32,
+        v_plane: &'a mut [T],
+        v_stride: u32,
+        width: u32,
+        height: u32,
+        subsampling: YuvChromaSubsampling,
+    ) -> Self {
+        let (chroma_width, chroma_height) = subsampling.chroma_size(width, height);
+        Self {
+            planes: BlurPlanarImageMut::new(vec![
+                make_plane(y_plane, y_stride, width, height, FastBlurChannels::Plane),
+                make_plane(
+                    u_plane,
+                    u_stride,
+                    chroma_width,
+                    chroma_height,
+                    FastBlurChannels::Plane,
+                ),
+                make_plane(
+                    v_plane,
+                    v_stride,
+                    chroma_width,
+                    chroma_height,
+                    FastBlurChannels::Plane,
+                ),
+            ]),
+            subsampling,
+        }
+    }
+
+    /// Creates semi-planar YUV image with interleaved UV or VU plane
+    pub fn bi_planar(
+        y_plane: &'a mut [T],
+        y_stride: u32,
+        uv_plane: &'a mut [T],
+        uv_stride: u32,
+        width: u32,
+        height: u32,
+        subsampling: YuvChromaSubsampling,
+    ) -> Self {
+        let (chroma_width, chroma_height) = subsampling.chroma_size(width, height);
+        Self {
+            planes: BlurPlanarImageMut::new(vec![
+                make_plane(y_plane, y_stride, width, height, FastBlurChannels::Plane),
+                make_plane(
+                    uv_plane,
+                    uv_stride,
+                    chroma_width,
+                    chroma_height,
+                    FastBlurChannels::Channels2,
+                ),
+            ]),
+            subsampling,
+        }
+    }
+
+    /// Checks if planes layout matches necessary requirements
+    pub fn check_layout(&mut self) -> Result<(), BlurError> {
+        self.planes.check_layout(None)?;
+        let planes = &self.planes.planes;
+        let chroma_channels = match planes.len() {
+            2 => FastBlurChannels::Channels2,
+            3 => FastBlurChannels::Plane,
+            _ => return Err(BlurError::InvalidArguments),
+        };
+        let luma = &planes[0];
+        if luma.channels != FastBlurChannels::Plane {
+            return Err(BlurError::InvalidArguments);
+        }
+        let (chroma_width, chroma_height) = self.subsampling.chroma_size(luma.width, luma.height);
+        for chroma in planes.iter().skip(1) {
+            if chroma.width != chroma_width
+                || chroma.height != chroma_height
+                || chroma.channels != chroma_channels
+            {
+                return Err(BlurError::ImagesMustMatch);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn make_plane<T: Clone + Copy + Default + Debug>(
+    data: &mut [T],
+    stride: u32,
+    width: u32,
+    height: u32,
+    channels: FastBlurChannels,
+) -> BlurImageMut<'_, T> {
+    BlurImageMut {
+        data: BufferStore::Borrowed(data),
+        width,
+        height,
+        stride,
+        channels,
+    }
+}
+
+fn yuv_executor_in_place<T, F>(
+    image: &mut YuvImageMut<T>,
+    threading_policy: ThreadingPolicy,
+    executor: F,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + Send + Sync,
+    F: Fn(&mut BlurImageMut<T>, YuvChromaSubsampling, ThreadingPolicy) -> Result<(), BlurError>
+        + Send
+        + Sync,
+{
+    image.check_layout()?;
+    let subsampling = image.subsampling;
+    planar_executor_in_place(
+        &mut image.planes,
+        threading_policy,
+        |index, plane, policy| {
+            // Luma is not subsampled
+            let plane_subsampling = if index == 0 {
+                YuvChromaSubsampling::Yuv444
+            } else {
+                subsampling
+            };
+            executor(plane, plane_subsampling, policy)
+        },
+    )
+}
+
+fn gaussian_blur_plane_in_place<T: Copy + Default + Debug>(
+    plane: &mut BlurImageMut<T>,
+    executor: impl FnOnce(&BlurImage<T>, &mut BlurImageMut<T>) -> Result<(), BlurError>,
+) -> Result<(), BlurError> {
+    let src = BlurImage {
+        data: Cow::Owned(plane.data.borrow().to_vec()),
+        width: plane.width,
+        height: plane.height,
+        stride: plane.stride,
+        channels: plane.channels,
+    };
+    executor(&src, plane)
+}
+
+/// Performs stack blur on YUV image in place.
+///
+/// Chroma planes are blurred with radius scaled according to [YuvChromaSubsampling],
+/// so the result matches blurring of the full resolution image.
+///
+/// # Arguments
+///
+/// * `image` - YUV image, see [YuvImageMut] for more info.
+/// * `radius` - Luma radius more than 1, see [AnisotropicRadius] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn stack_blur_yuv(
+    image: &mut YuvImageMut<u8>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    yuv_executor_in_place(image, threading_policy, |plane, subsampling, policy| {
+        stack_blur(plane, subsampling.scale_radius(radius), policy)
+    })
+}
+
+/// Performs stack blur on 16-bit YUV image in place.
+///
+/// See [stack_blur_yuv] for more info.
+pub fn stack_blur_yuv_u16(
+    image: &mut YuvImageMut<u16>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    yuv_executor_in_place(image, threading_policy, |plane, subsampling, policy| {
+        stack_blur_u16(plane, subsampling.scale_radius(radius), policy)
+    })
+}
+
+/// Performs fast gaussian blur on YUV image in place.
+///
+/// Chroma planes are blurred with radius scaled according to [YuvChromaSubsampling].
+///
+/// # Arguments
+///
+/// * `image` - YUV image, see [YuvImageMut] for more info.
+/// * `radius` - Luma radius more than 1, see [AnisotropicRadius] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `edge_mode` - Rule to handle edge mode, sse [EdgeMode] for more info.
+pub fn fast_gaussian_yuv(
+    image: &mut YuvImageMut<u8>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) -> Result<(), BlurError> {
+    yuv_executor_in_place(image, threading_policy, |plane, subsampling, policy| {
+        fast_gaussian(plane, subsampling.scale_radius(radius), policy, edge_mode)
+    })
+}
+
+/// Performs fast gaussian blur on 16-bit YUV image in place.
+///
+/// See [fast_gaussian_yuv] for more info.
+pub fn fast_gaussian_yuv_u16(
+    image: &mut YuvImageMut<u16>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) -> Result<(), BlurError> {
+    yuv_executor_in_place(image, threading_policy, |plane, subsampling, policy| {
+        fast_gaussian_u16(plane, subsampling.scale_radius(radius), policy, edge_mode)
+    })
+}
+
+/// Performs gaussian blur on YUV image in place.
+///
+/// Chroma planes are blurred with kernel and sigma scaled according to [YuvChromaSubsampling].
+///
+/// # Arguments
+///
+/// * `image` - YUV image, see [YuvImageMut] for more info.
+/// * `params` - Luma parameters, see [GaussianBlurParams] for more info.
+/// * `edge_mode` - Rule to handle edge mode, sse [EdgeMode] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `hint` - see [ConvolutionMode] for more info.
+pub fn gaussian_blur_yuv(
+    image: &mut YuvImageMut<u8>,
+    params: GaussianBlurParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    hint: ConvolutionMode,
+) -> Result<(), BlurError> {
+    yuv_executor_in_place(image, threading_policy, |plane, subsampling, policy| {
+        gaussian_blur_plane_in_place(plane, |src, dst| {
+            gaussian_blur(
+                src,
+                dst,
+                subsampling.scale_params(params),
+                edge_mode,
+                policy,
+                hint,
+            )
+        })
+    })
+}
+
+/// Performs gaussian blur on 16-bit YUV image in place.
+///
+/// See [gaussian_blur_yuv] for more info.
+pub fn gaussian_blur_yuv_u16(
+    image: &mut YuvImageMut<u16>,
+    params: GaussianBlurParams,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    hint: ConvolutionMode,
+) -> Result<(), BlurError> {
+    yuv_executor_in_place(image, threading_policy, |plane, subsampling, policy| {
+        gaussian_blur_plane_in_place(plane, |src, dst| {
+            gaussian_blur_u16(
+                src,
+                dst,
+                subsampling.scale_params(params),
+                edge_mode,
+                policy,
+                hint,
+            )
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nv12_matches_i420() {
+        let (width, height) = (33u32, 21u32);
+        let (chroma_width, chroma_height) = YuvChromaSubsampling::Yuv420.chroma_size(width, height);
+        let chroma_len = chroma_width as usize * chroma_height as usize;
+        let mut y0: Vec<u8> = (0..width as usize * height as usize)
+            .map(|i| (i * 13 % 251) as u8)
+            .collect();
+        let mut y1 = y0.clone();
+        let mut u: Vec<u8> = (0..chroma_len).map(|i| (i * 7 % 239) as u8).collect();
+        let mut v: Vec<u8> = (0..chroma_len).map(|i| (i * 29 % 241) as u8).collect();
+        let mut uv: Vec<u8> = u.iter().zip(v.iter()).flat_map(|(&u, &v)| [u, v]).collect();
+        let mut direct_uv = uv.clone();
+
+        let mut i420 = YuvImageMut::planar(
+            &mut y0,
+            width,
+            &mut u,
+            chroma_width,
+            &mut v,
+            chroma_width,
+            width,
+            height,
+            YuvChromaSubsampling::Yuv420,
+        );
+        stack_blur_yuv(
+            &mut i420,
+            AnisotropicRadius::new(6),
+            ThreadingPolicy::Adaptive,
+        )
+        .unwrap();
+        drop(i420);
+
+        let mut nv12 = YuvImageMut::bi_planar(
+            &mut y1,
+            width,
+            &mut uv,
+            chroma_width * 2,
+            width,
+            height,
+            YuvChromaSubsampling::Yuv420,
+        );
+        stack_blur_yuv(
+            &mut nv12,
+            AnisotropicRadius::new(6),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        drop(nv12);
+
+        assert_eq!(y0, y1);
+        for (i, pair) in uv.chunks_exact(2).enumerate() {
+            assert_eq!(pair[0], u[i]);
+            assert_eq!(pair[1], v[i]);
+        }
+
+        // Chroma is blurred with radius scaled to its own resolution
+        let mut chroma = BlurImageMut::borrow(
+            &mut direct_uv,
+            chroma_width,
+            chroma_height,
+            FastBlurChannels::Channels2,
+        );
+        stack_blur(
+            &mut chroma,
+            YuvChromaSubsampling::Yuv420.scale_radius(AnisotropicRadius::new(6)),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        assert_eq!(direct_uv, uv);
+
+        let params = GaussianBlurParams::new_from_kernel(9.);
+        let chroma = BlurImage::borrow(&u, chroma_width, chroma_height, FastBlurChannels::Plane);
+        let mut expected_u = BlurImageMut::default();
+        gaussian_blur(
+            &chroma,
+            &mut expected_u,
+            YuvChromaSubsampling::Yuv420.scale_params(params),
+            EdgeMode::Clamp,
+            ThreadingPolicy::Single,
+            ConvolutionMode::Exact,
+        )
+        .unwrap();
+        let expected_u = expected_u.data.borrow().to_vec();
+        let mut i420 = YuvImageMut::planar(
+            &mut y0,
+            width,
+            &mut u,
+            chroma_width,
+            &mut v,
+            chroma_width,
+            width,
+            height,
+            YuvChromaSubsampling::Yuv420,
+        );
+        gaussian_blur_yuv(
+            &mut i420,
+            params,
+            EdgeMode::Clamp,
+            ThreadingPolicy::Single,
+            ConvolutionMode::Exact,
+        )
+        .unwrap();
+        drop(i420);
+        assert_eq!(u, expected_u);
+    }
+
+    #[test]
+    fn test_yuv_chroma_size_mismatch() {
+        let mut y = vec![0u8; 16 * 16];
+        let mut u = vec![0u8; 16 * 16];
+        let mut v = vec![0u8; 16 * 16];
+        let mut image = YuvImageMut::planar(
+            &mut y,
+            16,
+            &mut u,
+            16,
+            &mut v,
+            16,
+            16,
+            16,
+            YuvChromaSubsampling::Yuv444,
+        );
+        image.subsampling = YuvChromaSubsampling::Yuv420;
+        assert!(gaussian_blur_yuv(
+            &mut image,
+            GaussianBlurParams::new_from_kernel(5.),
+            EdgeMode::Clamp,
+            ThreadingPolicy::Single,
+            ConvolutionMode::Exact,
+        )
+        .is_err());
+    }
+}